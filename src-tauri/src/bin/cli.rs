@@ -7,6 +7,8 @@ use airdb_lib::engine::{
     config::Config,
     database::Database,
     migrations::MigrationRunner,
+    adapter::{guard::{self, StatementKind}, sqlite::SqliteAdapter, transfer, transpile, AdapterError, ConnectionTuning, DatabaseAdapter, PlanNode, TransferOptions, DialectGenerator, SchemaDiff, SqlDialect},
+    connections::{AdapterConfig, ConnectionManager},
     keystore::Keystore,
     api::{ApiState, create_router},
 };
@@ -64,6 +66,16 @@ fn run_cli(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Commands::Info => {
             cmd_info(&project_dir, json_output)?;
         }
+        Commands::Export { dialect, from, output } => {
+            cmd_export(&dialect, from.as_deref(), output.as_deref(), &project_dir, json_output)?;
+        }
+        Commands::Explain { sql, from } => {
+            cmd_explain(&sql, from.as_deref(), &project_dir, json_output)?;
+        }
+        Commands::Transfer { from, to, tables, batch_size, no_create, restart } => {
+            let options = TransferOptions { tables, batch_size, create_schema: !no_create, restart };
+            cmd_transfer(&from, &to, &options, &project_dir, json_output)?;
+        }
     }
 
     Ok(())
@@ -154,7 +166,7 @@ fn cmd_init(name: &str, visibility: &str, no_github: bool, json: bool) -> Result
 fn cmd_migrate(action: MigrateAction, project_dir: &PathBuf, json: bool) -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::load(project_dir)?;
     let db_path = project_dir.join(&config.database.path);
-    let db = Database::new(&db_path)?;
+    let db = Database::with_tuning(&db_path, &config.database.connection_tuning(project_dir))?;
     let runner = MigrationRunner::new(project_dir);
 
     match action {
@@ -170,7 +182,45 @@ fn cmd_migrate(action: MigrateAction, project_dir: &PathBuf, json: bool) -> Resu
                 println!("   Edit the file and run `airdb migrate push`");
             }
         }
-        MigrateAction::Push => {
+        MigrateAction::Push { dry_run: true } => {
+            let report = runner.dry_run(&db)?;
+            if json {
+                println!("{}", serde_json::to_string(&report)?);
+            } else if report.migrations.is_empty() && report.failure.is_none() {
+                println!("✅ No pending migrations");
+            } else {
+                println!("🧪 Dry run on a copy of the database:");
+                for step in &report.migrations {
+                    println!("   ✓ {} ({} ms)", step.name, step.duration_ms);
+                }
+                if let Some(failure) = &report.failure {
+                    println!("   ✗ {}: {}", failure.migration, failure.error);
+                    if let Some(statement) = &failure.statement {
+                        println!("     Failing statement: {}", statement);
+                    }
+                }
+                if !report.schema_changes.is_empty() {
+                    println!("\n   Schema changes:");
+                    for change in &report.schema_changes {
+                        println!("   • {}", change);
+                    }
+                }
+                if !report.row_counts.is_empty() {
+                    println!("\n   Row counts:");
+                    for change in &report.row_counts {
+                        let count = |rows: Option<u64>| rows.map_or("-".to_string(), |n| n.to_string());
+                        println!("   • {}: {} → {}", change.table, count(change.before), count(change.after));
+                    }
+                }
+                if report.destructive {
+                    eprintln!("\n⚠️  These migrations drop tables or columns, or change column types");
+                }
+            }
+            if report.failure.is_some() {
+                std::process::exit(1);
+            }
+        }
+        MigrateAction::Push { dry_run: false } => {
             let applied = runner.push(&db)?;
             if json {
                 println!("{}", serde_json::json!({
@@ -189,13 +239,24 @@ fn cmd_migrate(action: MigrateAction, project_dir: &PathBuf, json: bool) -> Resu
             // Generate schema snapshot
             runner.generate_schema_snapshot(&db, project_dir)?;
         }
-        MigrateAction::Check => {
+        MigrateAction::Check { repair } => {
+            let repaired = if repair { runner.repair(&db)? } else { Vec::new() };
             let status = runner.check(&db)?;
             if json {
                 println!("{}", serde_json::json!({
                     "applied": status.applied_count,
                     "pending": status.pending_count,
-                    "pending_migrations": status.pending_migrations
+                    "pending_migrations": status.pending_migrations,
+                    "drift": status.drift.iter().map(|d| serde_json::json!({
+                        "name": d.name,
+                        "kind": d.kind.to_string()
+                    })).collect::<Vec<_>>(),
+                    "schema_drift": status.schema_drift.iter().map(|d| serde_json::json!({
+                        "kind": d.kind,
+                        "name": d.name,
+                        "change": d.change.to_string()
+                    })).collect::<Vec<_>>(),
+                    "repaired": repaired
                 }));
             } else {
                 println!("📊 Migration Status:");
@@ -207,15 +268,102 @@ fn cmd_migrate(action: MigrateAction, project_dir: &PathBuf, json: bool) -> Resu
                         println!("   • {}", name);
                     }
                 }
+                if !repaired.is_empty() {
+                    println!("\n   Re-recorded checksums:");
+                    for name in &repaired {
+                        println!("   • {}", name);
+                    }
+                }
+                if !status.drift.is_empty() {
+                    println!("\n⚠️  Applied migrations changed since they were applied:");
+                    for drift in &status.drift {
+                        println!("   • {} ({})", drift.name, drift.kind);
+                    }
+                    println!("   Restore them, or run `airdb migrate check --repair` to accept edits");
+                }
+                if !status.schema_drift.is_empty() {
+                    println!("\n⚠️  The database differs from sql/schema.sql:");
+                    for drift in &status.schema_drift {
+                        println!("   • {} {} ({})", drift.kind, drift.name, drift.change);
+                    }
+                    println!("   Was it changed outside migrations? See `airdb migrate diff` for the DDL");
+                }
             }
         }
-        MigrateAction::Rollback { count: _ } => {
+        MigrateAction::Rollback { count } => {
+            let rolled_back = runner.rollback(&db, count)?;
             if json {
                 println!("{}", serde_json::json!({
-                    "error": "Rollback not yet implemented"
+                    "success": true,
+                    "rolled_back": rolled_back
                 }));
+            } else if rolled_back.is_empty() {
+                println!("✅ No applied migrations to roll back");
             } else {
-                println!("⚠️  Rollback not yet implemented");
+                println!("✅ Rolled back {} migration(s):", rolled_back.len());
+                for name in &rolled_back {
+                    println!("   • {}", name);
+                }
+            }
+
+            runner.generate_schema_snapshot(&db, project_dir)?;
+        }
+        MigrateAction::Generate { name, schema } => {
+            let migration = match &schema {
+                Some(file) => runner.generate_from_schema(&db, &name, &project_dir.join(file))?,
+                None => runner.generate_from_database(&db, &name)?,
+            };
+            // Captured changes are already in the database, so the snapshot moves on
+            if migration.is_some() && schema.is_none() {
+                runner.generate_schema_snapshot(&db, project_dir)?;
+            }
+            if json {
+                println!("{}", serde_json::json!({
+                    "generated": migration.as_ref().map(|m| &m.name),
+                    "path": migration.as_ref().map(|m| m.path.display().to_string()),
+                    "applied": migration.is_some() && schema.is_none()
+                }));
+            } else {
+                match &migration {
+                    None if schema.is_some() => println!("✅ The database already matches the schema file"),
+                    None => println!("✅ The database matches sql/schema.sql; nothing to generate"),
+                    Some(m) => {
+                        println!("✅ Generated migration: {}", m.path.display());
+                        println!("\n{}", m.up.trim_end());
+                        if schema.is_some() {
+                            println!("\n   Review it, then run `airdb migrate push`");
+                        } else {
+                            println!("\n   Recorded as applied, since the database already has these changes");
+                        }
+                    }
+                }
+            }
+        }
+        MigrateAction::Diff { from, to, dialect } => {
+            let from = open_schema_source(from.as_deref().unwrap_or(&db_path.to_string_lossy()), project_dir, true)?;
+            let to = open_schema_source(to.as_deref().unwrap_or("sql/schema.sql"), project_dir, true)?;
+            let dialect = match dialect {
+                Some(ref name) => SqlDialect::from_str(name).ok_or_else(|| format!("Unknown dialect: {}", name))?,
+                None => from.dialect(),
+            };
+
+            let diff = SchemaDiff::between(&from.get_schema()?, &to.get_schema()?);
+            let statements = diff.render(&DialectGenerator::new(dialect));
+            if json {
+                println!("{}", serde_json::json!({
+                    "dialect": dialect,
+                    "changes": diff.changes,
+                    "destructive": diff.is_destructive(),
+                    "sql": statements
+                }));
+            } else if diff.is_empty() {
+                println!("✅ Schemas match");
+            } else {
+                println!("-- {} change(s), {} DDL\n", diff.changes.len(), dialect);
+                println!("{}", statements.join("\n\n"));
+                if diff.is_destructive() {
+                    eprintln!("\n⚠️  This diff drops tables or columns, or changes column types");
+                }
             }
         }
         MigrateAction::List => {
@@ -240,11 +388,224 @@ fn cmd_migrate(action: MigrateAction, project_dir: &PathBuf, json: bool) -> Resu
     Ok(())
 }
 
+/// Open a schema source for `migrate diff`, `export` and `transfer`: a `.sql` file
+/// (loaded into an in-memory SQLite database), a SQLite database file, a
+/// connection URL, a connection named in airdb.config.json, or the name or
+/// ID of a saved connection. Sources that are only read are opened with
+/// `read_only`, so SQLite files are left exactly as they were.
+fn open_schema_source(
+    source: &str,
+    project_dir: &Path,
+    read_only: bool,
+) -> Result<Box<dyn DatabaseAdapter>, Box<dyn std::error::Error>> {
+    let path = project_dir.join(source);
+    if source.ends_with(".sql") {
+        let adapter = SqliteAdapter::in_memory()?;
+        adapter.execute_batch(&std::fs::read_to_string(&path)?)?;
+        return Ok(Box::new(adapter));
+    }
+    if source.contains("://") {
+        return open_url(source, project_dir, read_only);
+    }
+    if path.is_file() {
+        return Ok(open_sqlite(&path, read_only)?);
+    }
+    if let Some(url) = Config::load(project_dir).ok().and_then(|c| c.connections.get(source).cloned()) {
+        return open_url(&url, project_dir, read_only);
+    }
+
+    let connection = ConnectionManager::new()
+        .list_connections()?
+        .into_iter()
+        .find(|c| c.name == source || c.id == source)
+        .ok_or_else(|| format!("No schema file, database or saved connection named '{}'", source))?;
+    Ok(ConnectionManager::open(&connection)?)
+}
+
+/// Open a connection URL; relative SQLite paths are taken from the project
+fn open_url(url: &str, project_dir: &Path, read_only: bool) -> Result<Box<dyn DatabaseAdapter>, Box<dyn std::error::Error>> {
+    match AdapterConfig::from_url(url)? {
+        AdapterConfig::Sqlite { path } => Ok(open_sqlite(&project_dir.join(path), read_only)?),
+        config => Ok(ConnectionManager::create_adapter(&config)?),
+    }
+}
+
+/// Opening read-write sets up the AirDB journal and WAL mode in the file,
+/// which a source that is only read must not get
+fn open_sqlite(path: &Path, read_only: bool) -> Result<Box<dyn DatabaseAdapter>, AdapterError> {
+    if read_only {
+        Ok(Box::new(SqliteAdapter::open_read_only(path, &ConnectionTuning::default())?))
+    } else {
+        Ok(Box::new(SqliteAdapter::new(path)?))
+    }
+}
+
+fn cmd_export(
+    dialect: &str,
+    from: Option<&str>,
+    output: Option<&Path>,
+    project_dir: &Path,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = SqlDialect::from_str(dialect).ok_or_else(|| format!("Unknown dialect: {}", dialect))?;
+    let source = match from {
+        Some(source) => open_schema_source(source, project_dir, true)?,
+        None => {
+            let config = Config::load(project_dir)?;
+            open_schema_source(&config.database.path.to_string_lossy(), project_dir, true)?
+        }
+    };
+    let export = transpile::export_schema(source.as_ref(), target)?;
+
+    if let Some(path) = output {
+        std::fs::write(path, format!("{}\n", export.sql))?;
+    }
+    if json {
+        println!("{}", serde_json::to_string(&export)?);
+        return Ok(());
+    }
+
+    if let Some(path) = output {
+        CliFormatter::success(&format!("Exported {} schema as {} to {}", export.source, export.target, path.display()));
+    } else {
+        println!("{}", export.sql);
+    }
+    if !export.warnings.is_empty() {
+        eprintln!("\n⚠️  {} lossy or skipped item(s):", export.warnings.len());
+        for warning in &export.warnings {
+            let at = match warning.column {
+                Some(ref column) => format!("{}.{}", warning.table, column),
+                None => warning.table.clone(),
+            };
+            eprintln!("   • {}: {}", at, warning.message);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_explain(sql: &str, from: Option<&str>, project_dir: &Path, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Some drivers run every statement of a script, EXPLAIN prefix or not
+    match guard::classify(sql).as_slice() {
+        [statement] if statement.kind == StatementKind::Read => {}
+        [statement] => return Err(format!("Only reads can be explained, not {} statements", statement.kind).into()),
+        [] => return Err("No SQL statement to explain".into()),
+        _ => return Err("Only a single statement can be explained".into()),
+    }
+
+    let adapter = match from {
+        Some(source) => open_schema_source(source, project_dir, true)?,
+        None => {
+            let config = Config::load(project_dir)?;
+            open_schema_source(&config.database.path.to_string_lossy(), project_dir, true)?
+        }
+    };
+    let plan = adapter.explain(sql, &[])?;
+
+    if json {
+        println!("{}", serde_json::to_string(&plan)?);
+        return Ok(());
+    }
+
+    fn print_node(node: &PlanNode, depth: usize) {
+        let mut line = format!("{}{}", "  ".repeat(depth + 1), node.detail);
+        if let Some(rows) = node.estimated_rows {
+            line.push_str(&format!("  (rows≈{}", rows));
+            if let Some(cost) = node.estimated_cost {
+                line.push_str(&format!(", cost={:.2}", cost));
+            }
+            line.push(')');
+        }
+        if node.full_scan {
+            line.push_str("  ⚠ full table scan");
+        }
+        println!("{}", line);
+        for child in &node.children {
+            print_node(child, depth + 1);
+        }
+    }
+
+    CliFormatter::header(&format!("Query plan ({})", plan.dialect));
+    for node in &plan.nodes {
+        print_node(node, 0);
+    }
+    if let Some(cost) = plan.total_cost {
+        CliFormatter::blank();
+        CliFormatter::kv("Estimated cost", &format!("{:.2}", cost));
+    }
+    if !plan.full_scans.is_empty() {
+        CliFormatter::blank();
+        CliFormatter::warning(&format!("Full table scan of {}; an index may help", plan.full_scans.join(", ")));
+    }
+    Ok(())
+}
+
+fn cmd_transfer(
+    from: &str,
+    to: &str,
+    options: &TransferOptions,
+    project_dir: &Path,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if to.ends_with(".sql") {
+        return Err("The transfer target must be a database or saved connection, not a .sql file".into());
+    }
+    let source = open_schema_source(from, project_dir, true)?;
+    // A SQLite target is created if it doesn't exist yet
+    let is_sqlite_file = !to.contains("://")
+        && matches!(
+            Path::new(to).extension().and_then(|e| e.to_str()),
+            Some("db" | "sqlite" | "sqlite3")
+        );
+    let target: Box<dyn DatabaseAdapter> = if is_sqlite_file {
+        Box::new(SqliteAdapter::new(&project_dir.join(to))?)
+    } else {
+        open_schema_source(to, project_dir, false)?
+    };
+    // Name checkpoints by the URL without its password
+    let label = |source: &str| match AdapterConfig::from_url(source) {
+        Ok(config) if source.contains("://") => config.display_string(),
+        _ => source.to_string(),
+    };
+    let checkpoint = transfer::checkpoint_path(&label(from), &label(to));
+    if !json && !options.restart && checkpoint.exists() {
+        CliFormatter::info("Resuming interrupted transfer");
+    }
+
+    let state = transfer::run(source.as_ref(), target.as_ref(), options, &checkpoint, |progress| {
+        if !json {
+            CliFormatter::progress(progress.rows_copied as usize, progress.total_rows as usize, &progress.table);
+        }
+    })?;
+
+    if json {
+        println!("{}", serde_json::to_string(&state)?);
+        return Ok(());
+    }
+    CliFormatter::success(&format!(
+        "Copied {} rows in {} table(s) from {} to {}",
+        state.rows_copied(),
+        state.tables.len(),
+        state.source,
+        state.target
+    ));
+    if !state.warnings.is_empty() {
+        eprintln!("\n⚠️  {} lossy or skipped item(s):", state.warnings.len());
+        for warning in &state.warnings {
+            let at = match warning.column {
+                Some(ref column) => format!("{}.{}", warning.table, column),
+                None => warning.table.clone(),
+            };
+            eprintln!("   • {}: {}", at, warning.message);
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn cmd_serve(project_dir: &PathBuf, host: &str, port: u16) -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::load(project_dir)?;
     let db_path = project_dir.join(&config.database.path);
-    let db = Database::new(&db_path)?;
+    let db = Database::with_tuning(&db_path, &config.database.connection_tuning(project_dir))?;
 
     let state = ApiState { db: Arc::new(db) };
     let app = create_router(state);
@@ -264,7 +625,7 @@ async fn cmd_serve(project_dir: &PathBuf, host: &str, port: u16) -> Result<(), B
 fn cmd_status(project_dir: &PathBuf, json: bool) -> Result<(), Box<dyn std::error::Error>> {
     let config = Config::load(project_dir)?;
     let db_path = project_dir.join(&config.database.path);
-    let db = Database::new(&db_path)?;
+    let db = Database::with_tuning(&db_path, &config.database.connection_tuning(project_dir))?;
     let runner = MigrationRunner::new(project_dir);
     let status = runner.check(&db)?;
     let tables = db.get_tables()?;
@@ -276,6 +637,8 @@ fn cmd_status(project_dir: &PathBuf, json: bool) -> Result<(), Box<dyn std::erro
             "api_port": config.api.port,
             "migrations_applied": status.applied_count,
             "migrations_pending": status.pending_count,
+            "migrations_drifted": status.drift.len(),
+            "schema_drifted": status.schema_drift.len(),
             "tables": tables
         }));
     } else {
@@ -285,6 +648,12 @@ fn cmd_status(project_dir: &PathBuf, json: bool) -> Result<(), Box<dyn std::erro
         CliFormatter::kv("API Port", &config.api.port.to_string());
         CliFormatter::blank();
         CliFormatter::kv("Migrations", &format!("{} applied, {} pending", status.applied_count, status.pending_count));
+        if !status.drift.is_empty() {
+            CliFormatter::kv("Drift", &format!("{} applied migration(s) changed; run `airdb migrate check`", status.drift.len()));
+        }
+        if !status.schema_drift.is_empty() {
+            CliFormatter::kv("Schema", &format!("{} object(s) differ from sql/schema.sql; run `airdb migrate check`", status.schema_drift.len()));
+        }
         CliFormatter::kv("Tables", &if tables.is_empty() { "(none)".to_string() } else { tables.join(", ") });
     }
 
@@ -298,7 +667,7 @@ fn cmd_info(project_dir: &PathBuf, json: bool) -> Result<(), Box<dyn std::error:
     
     let config = Config::load(project_dir)?;
     let db_path = project_dir.join(&config.database.path);
-    let db = Database::new(&db_path)?;
+    let db = Database::with_tuning(&db_path, &config.database.connection_tuning(project_dir))?;
     let runner = MigrationRunner::new(project_dir);
     let status = runner.check(&db)?;
     let tables = db.get_tables()?;
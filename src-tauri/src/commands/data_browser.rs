@@ -7,8 +7,15 @@ use serde::Deserialize;
 use tauri::State;
 
 use crate::AppState;
-use crate::engine::adapter::{FilterOp, FilterSpec, SortDirection, SortSpec};
+use crate::engine::adapter::bulk::DEFAULT_BATCH_SIZE;
+use crate::engine::adapter::cursor::DEFAULT_CHUNK_SIZE;
+use crate::engine::adapter::{
+    guard, transpile, CountMode, DialectGenerator, FilterExpr, FilterOp, FilterSpec, FtsIndex, QueryPlan, SchemaExport,
+    SearchQuery, SearchResult, SortDirection, SortSpec, SqlDialect, SqlValue, StatementKind,
+};
 use crate::engine::audit::{AuditLog, AuditEntry, AuditAction};
+use crate::engine::migrations::MigrationRunner;
+use super::schema_editor::check_statements;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,38 +24,119 @@ pub struct SortParam {
     pub direction: String,
 }
 
+impl SortParam {
+    fn into_sort_spec(self) -> SortSpec {
+        SortSpec {
+            column: self.column,
+            direction: if self.direction.to_lowercase() == "desc" {
+                SortDirection::Desc
+            } else {
+                SortDirection::Asc
+            },
+        }
+    }
+}
+
+/// Opts a data query into keyset pagination
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct FilterParam {
-    pub column: String,
-    pub operator: String,
-    pub value: String,
+pub struct KeysetParam {
+    /// Cursor from a previous page's `nextCursor`/`prevCursor`; omit for the first page
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub count: CountMode,
+}
+
+/// A filter from the UI: a single condition, or a nested and/or/not group.
+/// Operators accept both names (`eq`, `not_like`) and symbols (`=`, `NOT LIKE`).
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum FilterParam {
+    And {
+        and: Vec<FilterParam>,
+    },
+    Or {
+        or: Vec<FilterParam>,
+    },
+    Not {
+        not: Box<FilterParam>,
+    },
+    Condition {
+        column: String,
+        operator: String,
+        #[serde(default)]
+        value: SqlValue,
+        #[serde(default)]
+        values: Vec<SqlValue>,
+    },
 }
 
 impl FilterParam {
-    fn to_filter_spec(&self) -> Result<FilterSpec, String> {
-        let operator = match self.operator.as_str() {
-            "eq" | "=" => FilterOp::Eq,
-            "neq" | "!=" => FilterOp::Neq,
-            "gt" | ">" => FilterOp::Gt,
-            "lt" | "<" => FilterOp::Lt,
-            "gte" | ">=" => FilterOp::Gte,
-            "lte" | "<=" => FilterOp::Lte,
-            "like" | "LIKE" => FilterOp::Like,
-            "is_null" | "IS NULL" => FilterOp::IsNull,
-            "is_not_null" | "IS NOT NULL" => FilterOp::IsNotNull,
-            _ => return Err(format!("Unknown filter operator: {}", self.operator)),
+    fn to_filter_expr(&self) -> Result<FilterExpr, String> {
+        let convert = |params: &[FilterParam]| {
+            params
+                .iter()
+                .map(|p| p.to_filter_expr())
+                .collect::<Result<Vec<_>, _>>()
         };
 
-        Ok(FilterSpec {
-            column: self.column.clone(),
-            operator,
-            value: self.value.clone(),
-        })
+        match self {
+            FilterParam::And { and } => Ok(FilterExpr::And { and: convert(and)? }),
+            FilterParam::Or { or } => Ok(FilterExpr::Or { or: convert(or)? }),
+            FilterParam::Not { not } => Ok(FilterExpr::Not {
+                not: Box::new(not.to_filter_expr()?),
+            }),
+            FilterParam::Condition {
+                column,
+                operator,
+                value,
+                values,
+            } => {
+                let operator = match operator.as_str() {
+                    "eq" | "=" => FilterOp::Eq,
+                    "neq" | "!=" => FilterOp::Neq,
+                    "gt" | ">" => FilterOp::Gt,
+                    "lt" | "<" => FilterOp::Lt,
+                    "gte" | ">=" => FilterOp::Gte,
+                    "lte" | "<=" => FilterOp::Lte,
+                    "like" | "LIKE" => FilterOp::Like,
+                    "not_like" | "NOT LIKE" => FilterOp::NotLike,
+                    "ilike" | "ILIKE" => FilterOp::ILike,
+                    "in" | "IN" => FilterOp::In,
+                    "not_in" | "NOT IN" => FilterOp::NotIn,
+                    "between" | "BETWEEN" => FilterOp::Between,
+                    "is_null" | "IS NULL" => FilterOp::IsNull,
+                    "is_not_null" | "IS NOT NULL" => FilterOp::IsNotNull,
+                    _ => return Err(format!("Unknown filter operator: {}", operator)),
+                };
+
+                Ok(FilterExpr::Condition(FilterSpec {
+                    column: column.clone(),
+                    operator,
+                    value: value.clone(),
+                    values: values.clone(),
+                }))
+            }
+        }
+    }
+}
+
+/// A plain list of filters is ANDed together
+fn filter_expr(filters: Option<Vec<FilterParam>>) -> Result<Option<FilterExpr>, String> {
+    match filters {
+        Some(filters) if !filters.is_empty() => Ok(Some(FilterExpr::And {
+            and: filters
+                .iter()
+                .map(|f| f.to_filter_expr())
+                .collect::<Result<Vec<_>, _>>()?,
+        })),
+        _ => Ok(None),
     }
 }
 
-/// Query table data with pagination, sorting, and filtering
+/// Query table data with pagination, sorting, and filtering.
+///
+/// With `keyset` set, rows are paged by cursor instead of `offset`.
 #[tauri::command]
 pub fn query_table_data(
     state: State<AppState>,
@@ -57,40 +145,212 @@ pub fn query_table_data(
     offset: Option<usize>,
     sort: Option<SortParam>,
     filters: Option<Vec<FilterParam>>,
+    keyset: Option<KeysetParam>,
 ) -> Result<serde_json::Value, String> {
     let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
     let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
 
     let limit = limit.unwrap_or(100);
     let offset = offset.unwrap_or(0);
-
-    let sort_spec = sort.map(|s| SortSpec {
-        column: s.column,
-        direction: if s.direction.to_lowercase() == "desc" {
-            SortDirection::Desc
-        } else {
-            SortDirection::Asc
-        },
-    });
-
-    let filter_specs: Vec<FilterSpec> = filters
-        .unwrap_or_default()
-        .iter()
-        .map(|f| f.to_filter_spec())
-        .collect::<Result<Vec<_>, _>>()?;
-
-    let page = adapter
-        .query_rows(&table, limit, offset, sort_spec.as_ref(), &filter_specs)
-        .map_err(|e| e.to_string())?;
+    let sort_spec = sort.map(SortParam::into_sort_spec);
+    let filter = filter_expr(filters)?;
+
+    let page = if let Some(keyset) = keyset {
+        adapter.query_rows_keyset(
+            &table,
+            limit,
+            sort_spec.as_ref(),
+            filter.as_ref(),
+            keyset.cursor.as_deref(),
+            keyset.count,
+        )
+    } else {
+        adapter.query_rows(&table, limit, offset, sort_spec.as_ref(), filter.as_ref())
+    }
+    .map_err(|e| e.to_string())?;
 
     Ok(serde_json::json!({
         "rows": page.rows,
         "totalCount": page.total_count,
+        "countIsEstimate": page.count_is_estimate,
         "columns": page.columns,
         "executionTimeMs": page.execution_time_ms,
+        "rowKey": page.row_key,
+        "nextCursor": page.next_cursor,
+        "prevCursor": page.prev_cursor,
+    }))
+}
+
+/// Query plan for a SQL statement, without running it
+#[tauri::command]
+pub fn explain_query(state: State<AppState>, sql: String) -> Result<QueryPlan, String> {
+    let guard = *state.sql_guard.lock().map_err(|e| e.to_string())?;
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    // Some drivers run every statement of a script, EXPLAIN prefix or not
+    let statements = guard::classify(&sql);
+    if statements.len() > 1 {
+        return Err("Only a single statement can be explained".to_string());
+    }
+    let kind = check_statements(&guard, adapter.as_ref(), &statements)?;
+    if kind != StatementKind::Read {
+        return Err(format!("Only reads can be explained, not {} statements", kind));
+    }
+
+    adapter.explain(&sql, &[]).map_err(|e| e.to_string())
+}
+
+/// Query plan for the data browser's query of a table with the given
+/// sorting and filters
+#[tauri::command]
+pub fn explain_table_query(
+    state: State<AppState>,
+    table: String,
+    sort: Option<SortParam>,
+    filters: Option<Vec<FilterParam>>,
+) -> Result<QueryPlan, String> {
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+    let gen = DialectGenerator::new(adapter.dialect());
+
+    let mut sql = format!("SELECT * FROM {}", gen.quote_table(&table));
+    let mut params = Vec::new();
+    if let Some(filter) = filter_expr(filters)? {
+        sql.push_str(&format!(" WHERE {}", gen.filter_sql(&filter, &mut params).map_err(|e| e.to_string())?));
+    }
+    if let Some(sort) = sort.map(SortParam::into_sort_spec) {
+        sql.push_str(&format!(" ORDER BY {} {}", gen.quote_ident(&sort.column), sort.direction));
+    }
+
+    adapter.explain(&sql, &params).map_err(|e| e.to_string())
+}
+
+/// Ranked full-text search over a table's search index
+#[tauri::command]
+pub fn search_table(
+    state: State<AppState>,
+    table: String,
+    query: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    raw: Option<bool>,
+) -> Result<SearchResult, String> {
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    let mut search = SearchQuery::new(query);
+    search.limit = limit.unwrap_or(search.limit);
+    search.offset = offset.unwrap_or(0);
+    search.raw = raw.unwrap_or(false);
+
+    adapter.search(&table, &search).map_err(|e| e.to_string())
+}
+
+/// The table's full-text index definition, or null if it has none
+#[tauri::command]
+pub fn get_search_index(state: State<AppState>, table: String) -> Result<Option<FtsIndex>, String> {
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+    adapter.search_index(&table).map_err(|e| e.to_string())
+}
+
+/// Create a full-text index over `columns` of a project table. The DDL is
+/// written as a migration and applied, so the index syncs like other schema.
+#[tauri::command]
+pub fn create_search_index(
+    state: State<AppState>,
+    table: String,
+    columns: Vec<String>,
+    tokenize: Option<String>,
+) -> Result<serde_json::Value, String> {
+    require_project_database(&state)?;
+    let index = FtsIndex {
+        table,
+        columns,
+        tokenize: tokenize.filter(|t| !t.trim().is_empty()),
+    };
+
+    {
+        let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+        let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+        if adapter.dialect() != SqlDialect::Sqlite {
+            return Err("Full-text indexes are only supported for SQLite".to_string());
+        }
+        if adapter.search_index(&index.table).map_err(|e| e.to_string())?.is_some() {
+            return Err(format!("Table {} already has a full-text index", index.table));
+        }
+        if index.columns.is_empty() {
+            return Err("Choose at least one column to index".to_string());
+        }
+        let schema = adapter.get_table_schema(&index.table).map_err(|e| e.to_string())?;
+        for column in &index.columns {
+            if !schema.columns.iter().any(|c| &c.name == column) {
+                return Err(format!("Column {} not found in {}", column, index.table));
+            }
+        }
+        // The index reads row text back by rowid
+        let table = DialectGenerator::new(SqlDialect::Sqlite).quote_table(&index.table);
+        adapter
+            .query(&format!("SELECT rowid FROM {} LIMIT 0", table), &[])
+            .map_err(|_| format!("{} is a WITHOUT ROWID table and can't be indexed", index.table))?;
+    }
+
+    let migration = apply_schema_migration(&state, &format!("fts_{}", index.table), &index.create_sql())?;
+    Ok(serde_json::json!({
+        "index": index,
+        "migration": migration,
     }))
 }
 
+/// Drop a table's full-text index through a migration
+#[tauri::command]
+pub fn drop_search_index(state: State<AppState>, table: String) -> Result<serde_json::Value, String> {
+    require_project_database(&state)?;
+    let index = {
+        let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+        let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+        adapter
+            .search_index(&table)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Table {} has no full-text index", table))?
+    };
+
+    let migration = apply_schema_migration(&state, &format!("drop_fts_{}", table), &index.drop_sql())?;
+    Ok(serde_json::json!({ "migration": migration }))
+}
+
+/// Search indexes are checked against the active adapter but applied to
+/// the project database as migrations, so both must be the same database
+fn require_project_database(state: &State<AppState>) -> Result<(), String> {
+    match state.active_connection.lock().map_err(|e| e.to_string())?.as_deref() {
+        None => Ok(()),
+        Some(connection) => Err(format!(
+            "Full-text indexes are kept in project migrations, but the active connection is {}; \
+             open the project database to manage them",
+            connection
+        )),
+    }
+}
+
+/// Record generated DDL as a migration in the open project and apply it
+pub(crate) fn apply_schema_migration(state: &State<AppState>, name: &str, sql: &str) -> Result<String, String> {
+    let project_dir_lock = state.project_dir.lock().map_err(|e| e.to_string())?;
+    let project_dir = project_dir_lock.as_ref().ok_or("No project open")?;
+    let db_lock = state.db.lock().map_err(|e| e.to_string())?;
+    let db = db_lock.as_ref().ok_or("Database not initialized")?;
+
+    let migration = MigrationRunner::new(project_dir)
+        .create_and_apply(db, name, sql)
+        .map_err(|e| e.to_string())?;
+
+    if let Ok(log) = AuditLog::new(project_dir) {
+        let entry = AuditEntry::new(AuditAction::SchemaMigrate, "migration", &migration.name);
+        let _ = log.append(&entry);
+    }
+    Ok(migration.name)
+}
+
 /// Insert a new row into a table
 #[tauri::command]
 pub fn adapter_insert_row(
@@ -121,19 +381,62 @@ pub fn adapter_insert_row(
     }))
 }
 
-/// Update an existing row by primary key
+/// Insert many rows in batches. With `upsert` set, rows colliding on
+/// `conflictColumns` (the primary key by default) update the existing row.
+#[tauri::command]
+pub fn adapter_insert_rows(
+    state: State<AppState>,
+    table: String,
+    rows: Vec<serde_json::Map<String, serde_json::Value>>,
+    upsert: Option<bool>,
+    conflict_columns: Option<Vec<String>>,
+    batch_size: Option<usize>,
+) -> Result<serde_json::Value, String> {
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    let result = if upsert.unwrap_or(false) {
+        adapter.upsert_rows(&table, &rows, &conflict_columns.unwrap_or_default(), batch_size)
+    } else {
+        adapter.insert_rows(&table, &rows, batch_size)
+    }
+    .map_err(|e| e.to_string())?;
+
+    // Audit log
+    if let Ok(project_dir) = state.project_dir.lock() {
+        if let Some(dir) = project_dir.as_ref() {
+            if let Ok(log) = AuditLog::new(dir) {
+                let entry = AuditEntry::new(AuditAction::Insert, "table", &table)
+                    .with_metadata(serde_json::json!({
+                        "rows": rows.len(),
+                        "affected": result.rows_affected,
+                        "errors": result.errors.len(),
+                    }));
+                let _ = log.append(&entry);
+            }
+        }
+    }
+
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+/// Update an existing row by primary key. `id` is the key value for
+/// single-column keys, or an object of column values for composite keys
+/// (`rowid` for SQLite tables without a primary key).
 #[tauri::command]
 pub fn adapter_update_row(
     state: State<AppState>,
     table: String,
-    id: i64,
+    id: serde_json::Value,
     data: serde_json::Value,
 ) -> Result<serde_json::Value, String> {
     let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
     let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
 
     let obj = data.as_object().ok_or("Data must be a JSON object")?;
-    let affected = adapter.update_row(&table, id, obj).map_err(|e| e.to_string())?;
+    let key = adapter.resolve_row_key(&table, &id).map_err(|e| e.to_string())?;
+    let affected = adapter.update_row(&table, &key, obj).map_err(|e| e.to_string())?;
 
     // Audit log
     if let Ok(project_dir) = state.project_dir.lock() {
@@ -152,17 +455,18 @@ pub fn adapter_update_row(
     }))
 }
 
-/// Delete a row by primary key
+/// Delete a row by primary key (see `adapter_update_row` for the `id` forms)
 #[tauri::command]
 pub fn adapter_delete_row(
     state: State<AppState>,
     table: String,
-    id: i64,
+    id: serde_json::Value,
 ) -> Result<serde_json::Value, String> {
     let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
     let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
 
-    let affected = adapter.delete_row(&table, id).map_err(|e| e.to_string())?;
+    let key = adapter.resolve_row_key(&table, &id).map_err(|e| e.to_string())?;
+    let affected = adapter.delete_row(&table, &key).map_err(|e| e.to_string())?;
 
     // Audit log
     if let Ok(project_dir) = state.project_dir.lock() {
@@ -181,6 +485,134 @@ pub fn adapter_delete_row(
     }))
 }
 
+/// A single statement in a transactional batch
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementParam {
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<SqlValue>,
+}
+
+/// Execute a batch of statements atomically on one connection.
+/// Any failure rolls back every statement in the batch.
+///
+/// The statements are checked like `execute_raw_sql`'s: read-only
+/// connections refuse anything but reads, and a destructive batch comes
+/// back for confirmation until it is sent again with `confirm`.
+#[tauri::command]
+pub fn adapter_execute_transaction(
+    state: State<AppState>,
+    statements: Vec<StatementParam>,
+    confirm: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let guard = *state.sql_guard.lock().map_err(|e| e.to_string())?;
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    let classified: Vec<guard::Statement> = statements.iter().flat_map(|s| guard::classify(&s.sql)).collect();
+    let kind = check_statements(&guard, adapter.as_ref(), &classified)?;
+    if guard.needs_confirmation(&classified) && !confirm.unwrap_or(false) {
+        // Bound parameters aren't part of the preview, so none is run
+        return Ok(serde_json::json!({
+            "requiresConfirmation": true,
+            "kind": kind,
+            "statements": classified,
+            "dryRun": null,
+        }));
+    }
+
+    let mut tx = adapter.begin().map_err(|e| e.to_string())?;
+    let mut affected = 0u64;
+    for (i, stmt) in statements.iter().enumerate() {
+        // Dropping the transaction on error rolls it back
+        let result = tx
+            .execute(&stmt.sql, &stmt.params)
+            .map_err(|e| format!("Statement {} failed: {}", i + 1, e))?;
+        affected += result.affected_rows;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({
+        "affected": affected,
+        "statements": statements.len(),
+        "message": "Transaction committed successfully"
+    }))
+}
+
+/// Open a streaming cursor over a query. Rows are fetched in chunks with
+/// `fetch_query_cursor` so large results never sit in memory at once.
+/// Only reads are accepted.
+#[tauri::command]
+pub fn open_query_cursor(
+    state: State<AppState>,
+    sql: String,
+    params: Option<Vec<SqlValue>>,
+    chunk_size: Option<usize>,
+) -> Result<serde_json::Value, String> {
+    let cursor = {
+        let guard = *state.sql_guard.lock().map_err(|e| e.to_string())?;
+        let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+        let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+        let kind = check_statements(&guard, adapter.as_ref(), &guard::classify(&sql))?;
+        if kind != StatementKind::Read {
+            return Err(format!("Cursors only run reads, not {} statements", kind));
+        }
+        adapter
+            .query_cursor(&sql, &params.unwrap_or_default(), chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE))
+            .map_err(|e| e.to_string())?
+    };
+
+    let cursor_id = uuid::Uuid::new_v4().to_string();
+    let columns = serde_json::to_value(cursor.columns()).map_err(|e| e.to_string())?;
+    state
+        .cursors
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(cursor_id.clone(), cursor);
+
+    Ok(serde_json::json!({
+        "cursorId": cursor_id,
+        "columns": columns,
+    }))
+}
+
+/// Fetch the next chunk from an open cursor. The cursor is closed
+/// automatically once it is exhausted or fails.
+#[tauri::command]
+pub fn fetch_query_cursor(
+    state: State<AppState>,
+    cursor_id: String,
+) -> Result<serde_json::Value, String> {
+    let mut cursors = state.cursors.lock().map_err(|e| e.to_string())?;
+    let cursor = cursors
+        .get_mut(&cursor_id)
+        .ok_or_else(|| format!("Cursor {} not found", cursor_id))?;
+
+    match cursor.next_chunk() {
+        Ok(Some(rows)) => Ok(serde_json::json!({ "rows": rows, "done": false })),
+        Ok(None) => {
+            cursors.remove(&cursor_id);
+            Ok(serde_json::json!({ "rows": [], "done": true }))
+        }
+        Err(e) => {
+            cursors.remove(&cursor_id);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Close a cursor early, releasing its connection
+#[tauri::command]
+pub fn close_query_cursor(state: State<AppState>, cursor_id: String) -> Result<(), String> {
+    state
+        .cursors
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&cursor_id);
+    Ok(())
+}
+
 /// Get full table schema via adapter
 #[tauri::command]
 pub fn adapter_get_table_schema(
@@ -195,6 +627,28 @@ pub fn adapter_get_table_schema(
     Ok(serde_json::to_value(schema).map_err(|e| e.to_string())?)
 }
 
+/// Get all views with their defining queries via adapter
+#[tauri::command]
+pub fn adapter_get_views(state: State<AppState>) -> Result<serde_json::Value, String> {
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    let views = adapter.get_views().map_err(|e| e.to_string())?;
+
+    serde_json::to_value(views).map_err(|e| e.to_string())
+}
+
+/// Export the connected database's schema as DDL for another dialect,
+/// with warnings for anything the target can't represent exactly
+#[tauri::command]
+pub fn export_schema(state: State<AppState>, dialect: String) -> Result<SchemaExport, String> {
+    let target = SqlDialect::from_str(&dialect).ok_or_else(|| format!("Unknown dialect: {}", dialect))?;
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    transpile::export_schema(adapter.as_ref(), target).map_err(|e| e.to_string())
+}
+
 /// Get current dialect information
 #[tauri::command]
 pub fn get_dialect(state: State<AppState>) -> Result<String, String> {
@@ -256,6 +710,7 @@ pub fn get_schema_graph(state: State<AppState>) -> Result<serde_json::Value, Str
                 "isNullable": col.nullable,
                 "isUnique": col.is_unique,
                 "defaultValue": col.default_value,
+                "generated": col.generated,
                 "fkTable": fk_match.map(|fk| &fk.to_table),
                 "fkColumn": fk_match.map(|fk| &fk.to_column),
             }));
@@ -274,8 +729,12 @@ pub fn get_schema_graph(state: State<AppState>) -> Result<serde_json::Value, Str
             "name": tname,
             "columns": columns,
             "rowCount": row_count,
+            "checkConstraints": schema.check_constraints,
+            "triggers": schema.triggers,
         }));
     }
 
-    Ok(serde_json::json!({ "tables": tables, "edges": edges }))
+    let views = adapter.get_views().map_err(|e| e.to_string())?;
+
+    Ok(serde_json::json!({ "tables": tables, "views": views, "edges": edges }))
 }
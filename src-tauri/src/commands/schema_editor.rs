@@ -5,6 +5,14 @@
 use tauri::State;
 use serde::{Deserialize, Serialize};
 use crate::AppState;
+use crate::engine::adapter::{
+    self, guard, introspect, CancelHandle, CheckConstraint, ColumnSchema, DatabaseAdapter, ForeignKeyRef, GeneratedColumn,
+    IndexInfo, SchemaDiff, QueryOptions, SqlDialect, SqlGuard, StatementKind, TriggerInfo,
+};
+use crate::engine::database::Database;
+use crate::engine::migrations::{self, DryRunReport};
+use super::data_browser::apply_schema_migration;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Column {
@@ -21,6 +29,9 @@ pub struct Column {
     pub is_unique: bool,
     #[serde(alias = "foreignKey", alias = "foreign_key", rename(serialize = "foreign_key"), default)]
     pub foreign_key: Option<ForeignKey>,
+    /// Set for generated (computed) columns
+    #[serde(default)]
+    pub generated: Option<GeneratedColumn>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +52,10 @@ pub struct TableSchema {
     pub name: String,
     pub columns: Vec<Column>,
     pub indexes: Vec<Index>,
+    #[serde(default)]
+    pub check_constraints: Vec<CheckConstraint>,
+    #[serde(default)]
+    pub triggers: Vec<TriggerInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,33 +132,32 @@ pub fn get_table_schema(
 ) -> Result<TableSchema, String> {
     let db_lock = state.db.lock().map_err(|e| e.to_string())?;
     let db = db_lock.as_ref().ok_or("Database not initialized")?;
+    read_table_schema(db, table_name)
+}
+
+/// Columns, indexes, CHECK constraints and triggers of a table
+fn read_table_schema(db: &Database, table_name: String) -> Result<TableSchema, String> {
     let conn = db.get_connection().map_err(|e| e.to_string())?;
     
-    // Get column info using PRAGMA
-    let mut stmt = conn
-        .prepare(&format!("PRAGMA table_info('{}')", table_name))
-        .map_err(|e| e.to_string())?;
+    let columns = read_columns(&conn, &table_name)?;
+    let create_sql = db.table_sql(&table_name).unwrap_or_default();
     
-    let columns: Vec<Column> = stmt
-        .query_map([], |row| {
-            let name: String = row.get(1)?;
-            let col_type: String = row.get(2)?;
-            let not_null: i32 = row.get(3)?;
-            let default: Option<String> = row.get(4)?;
-            let pk: i32 = row.get(5)?;
-            
-            Ok(Column {
-                name,
-                column_type: col_type,
-                nullable: not_null == 0,
-                default_value: default,
-                is_primary_key: pk > 0,
-                is_unique: false, // Will be updated from index check
-                foreign_key: None,
-            })
-        })
+    // Triggers are listed with the table they fire on
+    let triggers = db
+        .get_schema_objects("trigger")
         .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
+        .into_iter()
+        .filter(|t| t.table == table_name)
+        .map(|t| {
+            let (timing, events) = introspect::trigger_header(&t.sql);
+            TriggerInfo {
+                name: t.name,
+                table: t.table,
+                timing,
+                events,
+                definition: t.sql,
+            }
+        })
         .collect();
     
     // Get indexes
@@ -175,9 +189,109 @@ pub fn get_table_schema(
         name: table_name,
         columns,
         indexes,
+        check_constraints: introspect::check_constraints(&create_sql),
+        triggers,
     })
 }
 
+/// Column definitions of a table, including generated columns, which
+/// `PRAGMA table_info` leaves out, single-column foreign keys and inline
+/// UNIQUE constraints, so a table rebuilt from them keeps all three
+fn read_columns(conn: &rusqlite::Connection, table_name: &str) -> Result<Vec<Column>, String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_xinfo('{}')", table_name))
+        .map_err(|e| e.to_string())?;
+    
+    let mut columns: Vec<Column> = stmt
+        .query_map([], |row| {
+            let name: String = row.get(1)?;
+            let col_type: String = row.get(2)?;
+            let not_null: i32 = row.get(3)?;
+            let default: Option<String> = row.get(4)?;
+            let pk: i32 = row.get(5)?;
+            let hidden: i32 = row.get(6)?;
+            
+            Ok((hidden, Column {
+                name,
+                column_type: col_type,
+                nullable: not_null == 0,
+                default_value: default,
+                is_primary_key: pk > 0,
+                is_unique: false,
+                foreign_key: None,
+                generated: None,
+            }))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        // hidden = 1 marks the hidden columns of virtual tables
+        .filter(|(hidden, _)| *hidden != 1)
+        .map(|(_, column)| column)
+        .collect();
+    
+    let create_sql: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type='table' AND name = ?1",
+            [table_name],
+            |row| row.get(0),
+        )
+        .ok();
+    if let Some(create_sql) = create_sql {
+        for (name, generated) in introspect::generated_columns(&create_sql) {
+            if let Some(col) = columns.iter_mut().find(|c| c.name == name) {
+                col.generated = Some(generated);
+            }
+        }
+    }
+
+    // Columns with a UNIQUE constraint of their own; unique indexes created
+    // separately are listed as indexes instead
+    let mut stmt = conn
+        .prepare("SELECT name FROM pragma_index_list(?1) WHERE \"unique\" AND origin = 'u'")
+        .map_err(|e| e.to_string())?;
+    let unique_indexes = stmt
+        .query_map([table_name], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for index in unique_indexes {
+        if let [column] = get_index_columns(conn, &index)?.as_slice() {
+            if let Some(col) = columns.iter_mut().find(|c| &c.name == column) {
+                col.is_unique = true;
+            }
+        }
+    }
+
+    // Composite foreign keys can't be shown per column, so they're left out
+    let mut stmt = conn
+        .prepare(
+            "SELECT \"table\", \"from\", \"to\" FROM pragma_foreign_key_list(?1)
+             WHERE id IN (SELECT id FROM pragma_foreign_key_list(?1) GROUP BY id HAVING count(*) = 1)",
+        )
+        .map_err(|e| e.to_string())?;
+    let foreign_keys = stmt
+        .query_map([table_name], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for (table, from, to) in foreign_keys {
+        // `REFERENCES parent` without a column means the parent's primary key
+        let column = match to {
+            Some(column) => column,
+            None => conn
+                .query_row("SELECT name FROM pragma_table_info(?1) WHERE pk = 1", [&table], |row| row.get(0))
+                .map_err(|e| e.to_string())?,
+        };
+        if let Some(col) = columns.iter_mut().find(|c| c.name == from) {
+            col.foreign_key = Some(ForeignKey { table, column });
+        }
+    }
+    
+    Ok(columns)
+}
+
 fn get_index_columns(conn: &rusqlite::Connection, index_name: &str) -> Result<Vec<String>, String> {
     let mut stmt = conn
         .prepare(&format!("PRAGMA index_info('{}')", index_name))
@@ -206,45 +320,25 @@ pub fn generate_table_migration(
     if is_new {
         generate_create_table_migration(&table_name, &columns)
     } else {
-        // Load original columns from the database instead of requiring frontend to send them
-        let original_columns = load_original_columns(&state, &table_name)?;
-        generate_alter_table_migration(&table_name, &columns, &original_columns)
+        // Load the original table from the database instead of requiring frontend to send it
+        let original = load_original_schema(&state, &table_name)?;
+        generate_alter_table_migration(&original, &columns)
     }
 }
 
-/// Load original column definitions from the database for diff-based migration generation
-fn load_original_columns(state: &State<AppState>, table_name: &str) -> Result<Vec<Column>, String> {
+/// Load the table as it is in the database, for diff-based migration generation
+fn load_original_schema(state: &State<AppState>, table_name: &str) -> Result<TableSchema, String> {
     let db_lock = state.db.lock().map_err(|e| e.to_string())?;
     let db = db_lock.as_ref().ok_or("Database not initialized")?;
-    let conn = db.get_connection().map_err(|e| e.to_string())?;
-
-    let mut stmt = conn
-        .prepare(&format!("PRAGMA table_info('{}')", table_name))
-        .map_err(|e| e.to_string())?;
-
-    let columns: Vec<Column> = stmt
-        .query_map([], |row| {
-            let name: String = row.get(1)?;
-            let col_type: String = row.get(2)?;
-            let not_null: i32 = row.get(3)?;
-            let default: Option<String> = row.get(4)?;
-            let pk: i32 = row.get(5)?;
-
-            Ok(Column {
-                name,
-                column_type: col_type,
-                nullable: not_null == 0,
-                default_value: default,
-                is_primary_key: pk > 0,
-                is_unique: false,
-                foreign_key: None,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+    read_table_schema(db, table_name.to_string())
+}
 
-    Ok(columns)
+/// ` GENERATED ALWAYS AS (...)` clause for a column, or nothing
+fn generated_clause(col: &Column) -> String {
+    col.generated
+        .as_ref()
+        .map(|g| format!(" GENERATED ALWAYS AS ({}){}", g.expression, if g.stored { " STORED" } else { "" }))
+        .unwrap_or_default()
 }
 
 fn generate_create_table_migration(
@@ -278,6 +372,8 @@ fn generate_create_table_migration(
             }
         }
         
+        def.push_str(&generated_clause(col));
+        
         if let Some(fk) = &col.foreign_key {
             def.push_str(&format!(" REFERENCES {}({})", fk.table, fk.column));
         }
@@ -303,77 +399,78 @@ fn generate_create_table_migration(
     })
 }
 
+/// Diff the edited columns against the table in the database. Indexes,
+/// CHECK constraints and triggers are kept, except indexes on removed
+/// columns, and SQLite tables are rebuilt where ALTER TABLE falls short.
 fn generate_alter_table_migration(
-    table_name: &str,
+    original: &TableSchema,
     new_columns: &[Column],
-    original_columns: &[Column],
 ) -> Result<MigrationPreview, String> {
-    let mut up_statements = Vec::new();
-    let mut down_statements = Vec::new();
-    
-    // Find added columns
-    for col in new_columns {
-        if !original_columns.iter().any(|c| c.name == col.name) {
-            let mut def = format!("ALTER TABLE {} ADD COLUMN {} {}", 
-                table_name, col.name, col.column_type);
-            
-            if !col.nullable {
-                def.push_str(" NOT NULL");
-            }
-            
-            if let Some(default) = &col.default_value {
-                if !default.is_empty() {
-                    def.push_str(&format!(" DEFAULT {}", default));
-                }
-            }
-            
-            def.push(';');
-            up_statements.push(def);
-            
-            // SQLite doesn't support DROP COLUMN easily, note in down
-            down_statements.push(format!("-- Cannot easily drop column {} in SQLite", col.name));
-        }
-    }
-    
-    // Find removed columns (note: SQLite doesn't support ALTER TABLE DROP COLUMN easily)
-    for col in original_columns {
-        if !new_columns.iter().any(|c| c.name == col.name) {
-            up_statements.push(format!(
-                "-- Column {} removed (requires table rebuild in SQLite)",
-                col.name
-            ));
-            
-            let mut def = format!("ALTER TABLE {} ADD COLUMN {} {}",
-                table_name, col.name, col.column_type);
-            if !col.nullable {
-                def.push_str(" NOT NULL");
-            }
-            def.push(';');
-            down_statements.push(def);
-        }
-    }
-    
-    if up_statements.is_empty() {
+    let before = adapter_schema(original, &original.columns);
+    let mut after = adapter_schema(original, new_columns);
+    after
+        .indexes
+        .retain(|idx| idx.columns.iter().all(|c| new_columns.iter().any(|col| &col.name == c)));
+    
+    let up = SchemaDiff::between(std::slice::from_ref(&before), std::slice::from_ref(&after));
+    if up.is_empty() {
         return Err("No changes detected".to_string());
     }
+    let down = SchemaDiff::between(std::slice::from_ref(&after), std::slice::from_ref(&before));
     
-    let up_sql = up_statements.join("\n");
-    let down_sql = if down_statements.is_empty() {
-        "-- No automatic rollback available".to_string()
-    } else {
-        down_statements.join("\n")
-    };
-    
-    let name = format!("alter_{}", table_name);
+    let name = format!("alter_{}", original.name);
     
     Ok(MigrationPreview {
-        up_sql,
-        down_sql,
+        up_sql: up.to_sql(SqlDialect::Sqlite),
+        down_sql: down.to_sql(SqlDialect::Sqlite),
         version: get_next_migration_version()?,
         name,
     })
 }
 
+/// An editor table in the adapter's schema terms, with the given columns
+fn adapter_schema(table: &TableSchema, columns: &[Column]) -> adapter::TableSchema {
+    // Only a lone INTEGER primary key is SQLite's auto-assigned rowid alias
+    let rowid_alias = match columns.iter().filter(|c| c.is_primary_key).collect::<Vec<_>>().as_slice() {
+        [pk] if pk.column_type.eq_ignore_ascii_case("INTEGER") => Some(pk.name.clone()),
+        _ => None,
+    };
+    let columns = columns
+        .iter()
+        .map(|col| ColumnSchema {
+            name: col.name.clone(),
+            col_type: col.column_type.clone(),
+            nullable: col.nullable,
+            default_value: col.default_value.clone(),
+            is_primary_key: col.is_primary_key,
+            is_unique: col.is_unique,
+            is_auto_increment: rowid_alias.as_ref() == Some(&col.name),
+            foreign_key: col.foreign_key.as_ref().map(|fk| ForeignKeyRef {
+                table: fk.table.clone(),
+                column: fk.column.clone(),
+            }),
+            generated: col.generated.clone(),
+        })
+        .collect();
+    
+    adapter::TableSchema {
+        name: table.name.clone(),
+        columns,
+        indexes: table
+            .indexes
+            .iter()
+            .map(|idx| IndexInfo {
+                name: idx.name.clone(),
+                columns: idx.columns.clone(),
+                unique: idx.unique,
+            })
+            .collect(),
+        row_count: None,
+        check_constraints: table.check_constraints.clone(),
+        triggers: table.triggers.clone(),
+    }
+}
+
 fn get_next_migration_version() -> Result<u32, String> {
     // In a real implementation, scan migrations directory
     // For now, use timestamp-based version
@@ -384,7 +481,9 @@ fn get_next_migration_version() -> Result<u32, String> {
     Ok((duration.as_secs() % 1000000) as u32)
 }
 
-/// Apply a generated migration
+/// Apply a generated migration: it is written to `sql/migrations`, run in
+/// a transaction and recorded in the journal, so a failed table rebuild
+/// leaves nothing behind
 #[tauri::command]
 pub fn apply_generated_migration(
     state: State<AppState>,
@@ -392,98 +491,158 @@ pub fn apply_generated_migration(
     up_sql: String,
     down_sql: String,
 ) -> Result<(), String> {
-    use std::fs;
-    
-    let project_dir_lock = state.project_dir.lock().map_err(|e| e.to_string())?;
-    let project_dir = project_dir_lock.as_ref().ok_or("Project directory not set")?;
-    let migrations_dir = project_dir.join("migrations");
-    
-    // Create migrations directory if needed
-    fs::create_dir_all(&migrations_dir).map_err(|e| e.to_string())?;
-    
-    // Find next migration number
-    let existing: Vec<_> = fs::read_dir(&migrations_dir)
-        .map_err(|e| e.to_string())?
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map(|x| x == "sql").unwrap_or(false))
-        .collect();
-    
-    let next_num = existing.len() + 1;
-    let filename = format!("{:03}_{}.sql", next_num, name);
-    let filepath = migrations_dir.join(&filename);
-    
-    // Write migration file
-    let content = format!("-- Up\n{}\n\n-- Down\n{}", up_sql, down_sql);
-    fs::write(&filepath, content).map_err(|e| e.to_string())?;
-    
-    // Apply migration
-    let db_lock = state.db.lock().map_err(|e| e.to_string())?;
-    let db = db_lock.as_ref().ok_or("Database not initialized")?;
-    let conn = db.get_connection().map_err(|e| e.to_string())?;
-    conn.execute_batch(&up_sql).map_err(|e| e.to_string())?;
-    
+    let sql = format!("-- up\n{}\n\n-- down\n{}\n", up_sql, down_sql);
+    apply_schema_migration(&state, &name, &sql)?;
     Ok(())
 }
 
-/// Execute raw SQL query
+/// Try a generated migration on a scratch copy of the database, reporting
+/// its timing, schema and row count changes, or the statement that fails
 #[tauri::command]
-pub fn execute_raw_sql(
+pub fn dry_run_generated_migration(
     state: State<AppState>,
-    sql: String,
-) -> Result<serde_json::Value, String> {
+    name: String,
+    up_sql: String,
+) -> Result<DryRunReport, String> {
     let db_lock = state.db.lock().map_err(|e| e.to_string())?;
     let db = db_lock.as_ref().ok_or("Database not initialized")?;
-    let conn = db.get_connection().map_err(|e| e.to_string())?;
-    
-    // Check if it's a SELECT query
-    let is_select = sql.trim().to_uppercase().starts_with("SELECT");
-    
-    if is_select {
-        // Execute SELECT query and return results
-        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-        let column_count = stmt.column_count();
-        let column_names: Vec<String> = stmt
-            .column_names()
+    migrations::dry_run(db, &[(&name, &up_sql)]).map_err(|e| e.to_string())
+}
+
+/// Execute raw SQL against the active connection.
+///
+/// Statements are classified first: read-only connections refuse anything
+/// but reads, and destructive statements (DROP, TRUNCATE, unfiltered
+/// DELETE/UPDATE) come back with a dry-run preview until they are sent
+/// again with `confirm`. `dry_run` runs the SQL inside a transaction that
+/// is rolled back and reports what it would have changed.
+///
+/// Each statement is aborted after `timeout_ms`; passing a `query_id` lets
+/// `cancel_query` abort the run from the UI.
+#[tauri::command]
+pub async fn execute_raw_sql(
+    state: State<'_, AppState>,
+    sql: String,
+    confirm: Option<bool>,
+    dry_run: Option<bool>,
+    query_id: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<serde_json::Value, String> {
+    let handle = CancelHandle::new();
+    let options = QueryOptions {
+        timeout: timeout_ms.map(Duration::from_millis),
+        cancel: Some(handle.clone()),
+    };
+    // Registered before waiting on the adapter, so a queued run can be cancelled too
+    if let Some(id) = &query_id {
+        state.running_queries.lock().map_err(|e| e.to_string())?.insert(id.clone(), handle);
+    }
+
+    let result = run_raw_sql(&state, &sql, confirm.unwrap_or(false), dry_run.unwrap_or(false), &options);
+
+    if let Some(id) = &query_id {
+        state.running_queries.lock().map_err(|e| e.to_string())?.remove(id);
+    }
+    result
+}
+
+/// Abort a running `execute_raw_sql` call. Returns false when no query with
+/// that id is running.
+#[tauri::command]
+pub fn cancel_query(state: State<AppState>, query_id: String) -> Result<bool, String> {
+    let running = state.running_queries.lock().map_err(|e| e.to_string())?;
+    match running.get(&query_id) {
+        Some(handle) => {
+            handle.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn run_raw_sql(
+    state: &AppState,
+    sql: &str,
+    confirm: bool,
+    dry_run: bool,
+    options: &QueryOptions,
+) -> Result<serde_json::Value, String> {
+    let guard = *state.sql_guard.lock().map_err(|e| e.to_string())?;
+    let adapter_lock = state.adapter.lock().map_err(|e| e.to_string())?;
+    let adapter = adapter_lock.as_ref().ok_or("No database adapter initialized")?;
+
+    let statements = guard::classify(sql);
+    let kind = check_statements(&guard, adapter.as_ref(), &statements)?;
+
+    if kind == StatementKind::Read {
+        // Every statement runs; the result of the last one is shown
+        let mut result = None;
+        for statement in &statements {
+            result = Some(adapter.query_with(&statement.sql, &[], options).map_err(|e| e.to_string())?);
+        }
+        let result = result.ok_or("No SQL statements to execute")?;
+        let rows: Vec<serde_json::Value> = result
+            .rows
             .into_iter()
-            .map(|s| s.to_string())
-            .collect();
-        
-        let rows: Vec<serde_json::Value> = stmt
-            .query_map([], |row| {
-                let mut map = serde_json::Map::new();
-                for i in 0..column_count {
-                    let col_name = &column_names[i];
-                    let value: rusqlite::types::Value = row.get(i)?;
-                    let json_value = match value {
-                        rusqlite::types::Value::Null => serde_json::Value::Null,
-                        rusqlite::types::Value::Integer(n) => serde_json::json!(n),
-                        rusqlite::types::Value::Real(f) => serde_json::json!(f),
-                        rusqlite::types::Value::Text(s) => serde_json::json!(s),
-                        rusqlite::types::Value::Blob(b) => {
-                            serde_json::json!(format!("BLOB({} bytes)", b.len()))
-                        }
-                    };
-                    map.insert(col_name.clone(), json_value);
-                }
-                Ok(serde_json::Value::Object(map))
+            .map(|row| {
+                let map: serde_json::Map<String, serde_json::Value> = result
+                    .columns
+                    .iter()
+                    .map(|c| c.name.clone())
+                    .zip(row)
+                    .collect();
+                serde_json::Value::Object(map)
             })
-            .map_err(|e| e.to_string())?
-            .filter_map(|r| r.ok())
             .collect();
-        
-        Ok(serde_json::json!({
+        return Ok(serde_json::json!({
             "rows": rows,
             "rowCount": rows.len()
-        }))
-    } else {
-        // Execute non-SELECT query (INSERT, UPDATE, DELETE, etc.)
-        let changes = conn.execute(&sql, []).map_err(|e| e.to_string())?;
-        
-        Ok(serde_json::json!({
-            "affectedRows": changes,
-            "message": format!("Query executed successfully. {} row(s) affected.", changes)
-        }))
+        }));
+    }
+
+    if guard.needs_confirmation(&statements) && !confirm {
+        // A preview that can't run (e.g. MySQL DDL) is reported as null
+        let preview = guard::run(adapter.as_ref(), &statements, true, options).ok();
+        return Ok(serde_json::json!({
+            "requiresConfirmation": true,
+            "kind": kind,
+            "statements": statements,
+            "dryRun": preview,
+        }));
+    }
+
+    if dry_run {
+        let preview = guard::run(adapter.as_ref(), &statements, true, options).map_err(|e| e.to_string())?;
+        return Ok(serde_json::json!({
+            "kind": kind,
+            "statements": statements,
+            "dryRun": preview,
+        }));
     }
+
+    let result = guard::run(adapter.as_ref(), &statements, false, options).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "affectedRows": result.affected_rows,
+        "message": format!("Query executed successfully. {} row(s) affected.", result.affected_rows)
+    }))
+}
+
+/// Refuse statements the connection may not run, whichever command sent
+/// them; returns the riskiest kind among them
+pub(crate) fn check_statements(
+    guard: &SqlGuard,
+    adapter: &dyn DatabaseAdapter,
+    statements: &[guard::Statement],
+) -> Result<StatementKind, String> {
+    if statements.is_empty() {
+        return Err("No SQL statements to execute".to_string());
+    }
+    guard.check(statements).map_err(|e| e.to_string())?;
+    let kind = guard::kind_of(statements);
+    if adapter.is_read_only() && kind != StatementKind::Read {
+        return Err(format!("The connection is read-only, so {} statements are not allowed", kind));
+    }
+    Ok(kind)
 }
 
 /// Get project type (sql or nosql)
@@ -523,3 +682,71 @@ pub fn set_project_type(
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rebuild_keeps_foreign_keys_and_unique_columns() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE o (id INTEGER PRIMARY KEY);
+                 CREATE TABLE t (
+                     id INTEGER PRIMARY KEY,
+                     email TEXT UNIQUE,
+                     o_id INTEGER REFERENCES o(id),
+                     note TEXT
+                 );
+                 CREATE UNIQUE INDEX idx_t_note ON t (note);",
+            )
+            .unwrap();
+
+        let original = read_table_schema(&db, "t".to_string()).unwrap();
+        let column = |schema: &TableSchema, name: &str| schema.columns.iter().find(|c| c.name == name).cloned().unwrap();
+        assert!(column(&original, "email").is_unique);
+        assert!(!column(&original, "note").is_unique);
+        let fk = column(&original, "o_id").foreign_key.unwrap();
+        assert_eq!((fk.table.as_str(), fk.column.as_str()), ("o", "id"));
+
+        // Making a column NOT NULL rebuilds the table
+        let mut edited = original.columns.clone();
+        edited.iter_mut().find(|c| c.name == "note").unwrap().nullable = false;
+        let migration = generate_alter_table_migration(&original, &edited).unwrap();
+        db.get_connection().unwrap().execute_batch(&migration.up_sql).unwrap();
+
+        let rebuilt = read_table_schema(&db, "t".to_string()).unwrap();
+        assert!(!column(&rebuilt, "note").nullable);
+        assert!(column(&rebuilt, "email").is_unique);
+        assert_eq!(column(&rebuilt, "o_id").foreign_key.unwrap().table, "o");
+        assert_eq!(rebuilt.indexes.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["idx_t_note"]);
+    }
+
+    #[test]
+    fn test_only_a_lone_integer_key_is_auto_increment() {
+        let key = |name: &str| Column {
+            name: name.to_string(),
+            column_type: "INTEGER".to_string(),
+            nullable: false,
+            default_value: None,
+            is_primary_key: true,
+            is_unique: false,
+            foreign_key: None,
+            generated: None,
+        };
+        let table = TableSchema {
+            name: "t".to_string(),
+            columns: Vec::new(),
+            indexes: Vec::new(),
+            check_constraints: Vec::new(),
+            triggers: Vec::new(),
+        };
+        assert!(adapter_schema(&table, &[key("id")]).columns[0].is_auto_increment);
+        let composite = adapter_schema(&table, &[key("a"), key("b")]);
+        assert!(composite.columns.iter().all(|c| !c.is_auto_increment));
+    }
+}
@@ -0,0 +1,176 @@
+//! Bulk Writes
+//!
+//! Batched insert and upsert built on the transaction API. Consecutive rows
+//! with the same column set are sent as one multi-row statement. When a batch
+//! fails it is rolled back to a savepoint and retried row by row, so a few bad
+//! rows are reported individually instead of sinking the whole import.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Instant;
+
+use super::{AdapterError, AdapterResult, DatabaseAdapter, DialectGenerator, SqlValue, Transaction};
+
+/// Default number of rows per multi-row statement
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+const BATCH_SAVEPOINT: &str = "airdb_bulk";
+
+/// Outcome of one multi-row statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    /// Index of the batch's first row in the input
+    pub first_row: usize,
+    /// Number of input rows in the batch
+    pub rows: usize,
+    /// Rows affected as reported by the database. MySQL counts an upsert
+    /// that updates an existing row as two.
+    pub affected: u64,
+}
+
+/// A row that could not be written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowError {
+    /// Index of the row in the input
+    pub row: usize,
+    pub error: String,
+}
+
+/// Result of a bulk insert or upsert
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkResult {
+    pub batches: Vec<BatchResult>,
+    pub rows_affected: u64,
+    pub errors: Vec<RowError>,
+    pub execution_time_ms: u64,
+}
+
+/// Write `rows` in batches inside one transaction. With `conflict_columns`
+/// set the rows are upserted on those columns, otherwise plainly inserted.
+pub fn write_rows<A: DatabaseAdapter + ?Sized>(
+    adapter: &A,
+    table: &str,
+    rows: &[Map<String, Value>],
+    batch_size: usize,
+    conflict_columns: Option<&[String]>,
+) -> AdapterResult<BulkResult> {
+    let start = Instant::now();
+    let gen = DialectGenerator::new(adapter.dialect());
+    let conflict: Option<Vec<&str>> =
+        conflict_columns.map(|cols| cols.iter().map(|c| c.as_str()).collect());
+
+    let mut result = BulkResult::default();
+    let mut tx = adapter.begin()?;
+
+    let mut first = 0;
+    while first < rows.len() {
+        let columns: Vec<&str> = rows[first].keys().map(|k| k.as_str()).collect();
+        let max_rows = (gen.max_bind_params() / columns.len().max(1)).clamp(1, batch_size.max(1));
+
+        let mut end = first + 1;
+        while end < rows.len() && end - first < max_rows && same_columns(&rows[first], &rows[end]) {
+            end += 1;
+        }
+
+        let batch = &rows[first..end];
+        let affected = if columns.is_empty() {
+            for row in first..end {
+                result.errors.push(RowError {
+                    row,
+                    error: "Row has no columns".to_string(),
+                });
+            }
+            0
+        } else {
+            let statement = |count: usize| match &conflict {
+                Some(target) => gen.upsert_rows(table, &columns, count, target),
+                None => gen.insert_rows(table, &columns, count),
+            };
+            write_batch(tx.as_mut(), &statement, &columns, batch, first, &mut result.errors)?
+        };
+
+        result.batches.push(BatchResult {
+            first_row: first,
+            rows: batch.len(),
+            affected,
+        });
+        result.rows_affected += affected;
+        first = end;
+    }
+
+    tx.commit()?;
+    result.execution_time_ms = start.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+/// Run one batch under a savepoint, falling back to one row at a time if it fails
+fn write_batch(
+    tx: &mut dyn Transaction,
+    statement: &dyn Fn(usize) -> String,
+    columns: &[&str],
+    batch: &[Map<String, Value>],
+    first_row: usize,
+    errors: &mut Vec<RowError>,
+) -> AdapterResult<u64> {
+    let params: Vec<SqlValue> = batch.iter().flat_map(|row| row_params(row, columns)).collect();
+
+    match try_execute(tx, &statement(batch.len()), &params)? {
+        Ok(affected) => Ok(affected),
+        Err(e) if batch.len() == 1 => {
+            errors.push(RowError {
+                row: first_row,
+                error: e.to_string(),
+            });
+            Ok(0)
+        }
+        Err(_) => {
+            let single = statement(1);
+            let mut affected = 0;
+            for (i, row) in batch.iter().enumerate() {
+                match try_execute(tx, &single, &row_params(row, columns))? {
+                    Ok(n) => affected += n,
+                    Err(e) => errors.push(RowError {
+                        row: first_row + i,
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            Ok(affected)
+        }
+    }
+}
+
+/// Execute under a savepoint. The outer error is a transaction failure that
+/// aborts the whole write; the inner one is a statement error that was rolled back.
+fn try_execute(
+    tx: &mut dyn Transaction,
+    sql: &str,
+    params: &[SqlValue],
+) -> AdapterResult<Result<u64, AdapterError>> {
+    tx.savepoint(BATCH_SAVEPOINT)?;
+    match tx.execute(sql, params) {
+        Ok(result) => {
+            tx.release_savepoint(BATCH_SAVEPOINT)?;
+            Ok(Ok(result.affected_rows))
+        }
+        Err(e) => {
+            tx.rollback_to_savepoint(BATCH_SAVEPOINT)?;
+            tx.release_savepoint(BATCH_SAVEPOINT)?;
+            Ok(Err(e))
+        }
+    }
+}
+
+fn same_columns(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    a.len() == b.len() && a.keys().all(|k| b.contains_key(k))
+}
+
+fn row_params(row: &Map<String, Value>, columns: &[&str]) -> Vec<SqlValue> {
+    columns
+        .iter()
+        .map(|c| row.get(*c).map(SqlValue::from).unwrap_or(SqlValue::Null))
+        .collect()
+}
@@ -0,0 +1,171 @@
+//! Query Cancellation
+//!
+//! Long-running statements can be bounded by a timeout or aborted from
+//! another thread through a `CancelHandle`. While a statement runs the
+//! adapter arms the handle with its backend's own interrupt: the connection's
+//! interrupt handle on SQLite, a cancel request on Postgres, and
+//! `KILL QUERY` from a second connection on MySQL.
+//!
+//! The interrupt is disarmed before the connection goes back to the pool,
+//! so a late cancel can't abort another caller's statement.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::{AdapterError, AdapterResult};
+
+/// Aborts whatever statement is running on one connection
+pub type Interrupt = Box<dyn Fn() + Send + Sync>;
+
+/// Limits for a single query or statement
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// Abort the statement once it has run this long
+    pub timeout: Option<Duration>,
+    /// Lets another thread abort the statement
+    pub cancel: Option<CancelHandle>,
+}
+
+/// Shared switch that aborts the statement it is armed with.
+///
+/// Cancelling is sticky: a handle cancelled before its statement starts
+/// makes that statement fail straight away.
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+    inner: Arc<CancelState>,
+}
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    interrupt: Mutex<Option<Interrupt>>,
+}
+
+impl fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort the running statement, if any
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        if let Some(interrupt) = self.inner.interrupt.lock().unwrap().as_ref() {
+            interrupt();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn arm(&self, interrupt: Interrupt) {
+        *self.inner.interrupt.lock().unwrap() = Some(interrupt);
+    }
+
+    fn disarm(&self) {
+        *self.inner.interrupt.lock().unwrap() = None;
+    }
+}
+
+/// Run `statement` with `interrupt` armed, so the options' handle and
+/// timeout can abort it. Errors caused by the abort become
+/// `AdapterError::Cancelled`.
+pub fn run<T>(
+    options: &QueryOptions,
+    interrupt: Interrupt,
+    statement: impl FnOnce() -> AdapterResult<T>,
+) -> AdapterResult<T> {
+    let handle = options.cancel.clone().unwrap_or_default();
+    handle.arm(interrupt);
+    // A cancel that came in before arming had nothing to interrupt
+    if handle.is_cancelled() {
+        handle.disarm();
+        return Err(AdapterError::Cancelled("the query was cancelled".to_string()));
+    }
+
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let (finished, watch) = mpsc::channel::<()>();
+    let watchdog = options.timeout.map(|timeout| {
+        let handle = handle.clone();
+        let timed_out = timed_out.clone();
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = watch.recv_timeout(timeout) {
+                timed_out.store(true, Ordering::SeqCst);
+                handle.cancel();
+            }
+        })
+    });
+
+    let result = statement();
+    handle.disarm();
+    drop(finished);
+    if let Some(watchdog) = watchdog {
+        let _ = watchdog.join();
+    }
+
+    match result {
+        Err(_) if timed_out.load(Ordering::SeqCst) => Err(AdapterError::Cancelled(format!(
+            "the query ran longer than {:?}",
+            options.timeout.unwrap_or_default()
+        ))),
+        Err(_) if handle.is_cancelled() => Err(AdapterError::Cancelled("the query was cancelled".to_string())),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::sqlite::SqliteAdapter;
+    use crate::engine::adapter::DatabaseAdapter;
+    use std::time::Instant;
+
+    const ENDLESS: &str = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT count(*) FROM n";
+
+    #[test]
+    fn test_timeout_and_cancel() {
+        let adapter = SqliteAdapter::in_memory().unwrap();
+
+        let options = QueryOptions {
+            timeout: Some(Duration::from_millis(100)),
+            cancel: None,
+        };
+        let start = Instant::now();
+        let err = adapter.query_with(ENDLESS, &[], &options).unwrap_err();
+        assert!(matches!(err, AdapterError::Cancelled(ref m) if m.contains("100ms")));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let handle = CancelHandle::new();
+        let canceller = {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                handle.cancel();
+            })
+        };
+        let options = QueryOptions {
+            timeout: None,
+            cancel: Some(handle.clone()),
+        };
+        let err = adapter.query_with(ENDLESS, &[], &options).unwrap_err();
+        canceller.join().unwrap();
+        assert!(matches!(err, AdapterError::Cancelled(_)));
+
+        // A cancelled handle refuses further work; the connection is fine
+        assert!(adapter.execute_with("SELECT 1", &[], &options).is_err());
+        let result = adapter.query_with("SELECT 1 AS one", &[], &QueryOptions::default()).unwrap();
+        assert_eq!(result.rows.len(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! Streaming Row Cursor
+//!
+//! Pull-based cursor over a query result. A producer thread owns the pooled
+//! connection and walks the driver's row iterator, handing rows over in
+//! fixed-size chunks through a bounded channel. At most one chunk is buffered
+//! ahead of the consumer, and dropping the cursor stops the producer.
+
+use serde_json::Value;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+use super::{AdapterError, AdapterResult, ColumnMeta};
+
+/// Default number of rows per chunk
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+enum CursorMessage {
+    Columns(Vec<ColumnMeta>),
+    Rows(Vec<Vec<Value>>),
+    Error(AdapterError),
+}
+
+/// Producer side of a cursor, handed to the adapter's row loop
+pub struct RowSink {
+    sender: SyncSender<CursorMessage>,
+    chunk_size: usize,
+    buffer: Vec<Vec<Value>>,
+    columns_sent: bool,
+}
+
+impl RowSink {
+    /// Announce the result columns. Returns false once the cursor is gone.
+    pub fn columns(&mut self, columns: Vec<ColumnMeta>) -> bool {
+        self.columns_sent = true;
+        self.sender.send(CursorMessage::Columns(columns)).is_ok()
+    }
+
+    /// Buffer a row, blocking while the consumer is a full chunk behind.
+    /// Returns false once the cursor is gone and the producer should stop.
+    pub fn push(&mut self, row: Vec<Value>) -> bool {
+        self.buffer.push(row);
+        if self.buffer.len() >= self.chunk_size {
+            return self.flush();
+        }
+        true
+    }
+
+    fn flush(&mut self) -> bool {
+        if !self.columns_sent && !self.columns(Vec::new()) {
+            return false;
+        }
+        if self.buffer.is_empty() {
+            return true;
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(self.chunk_size));
+        self.sender.send(CursorMessage::Rows(chunk)).is_ok()
+    }
+}
+
+/// A streaming query result that yields rows in chunks
+pub struct RowCursor {
+    columns: Vec<ColumnMeta>,
+    receiver: Receiver<CursorMessage>,
+    finished: bool,
+}
+
+impl RowCursor {
+    /// Run `producer` on its own thread and wait for it to report columns
+    /// (or fail), so statement errors surface here rather than on first fetch.
+    pub fn spawn<F>(chunk_size: usize, producer: F) -> AdapterResult<Self>
+    where
+        F: FnOnce(&mut RowSink) -> AdapterResult<()> + Send + 'static,
+    {
+        let chunk_size = chunk_size.max(1);
+        let (sender, receiver) = sync_channel(1);
+
+        thread::spawn(move || {
+            let mut sink = RowSink {
+                sender,
+                chunk_size,
+                buffer: Vec::with_capacity(chunk_size),
+                columns_sent: false,
+            };
+            match producer(&mut sink) {
+                Ok(()) => {
+                    sink.flush();
+                }
+                Err(e) => {
+                    let _ = sink.sender.send(CursorMessage::Error(e));
+                }
+            }
+        });
+
+        match receiver.recv() {
+            Ok(CursorMessage::Columns(columns)) => Ok(Self {
+                columns,
+                receiver,
+                finished: false,
+            }),
+            Ok(CursorMessage::Error(e)) => Err(e),
+            Ok(CursorMessage::Rows(_)) => Err(AdapterError::Internal(
+                "Cursor produced rows before columns".to_string(),
+            )),
+            Err(_) => Err(AdapterError::Internal(
+                "Cursor producer exited unexpectedly".to_string(),
+            )),
+        }
+    }
+
+    pub fn columns(&self) -> &[ColumnMeta] {
+        &self.columns
+    }
+
+    /// Fetch the next chunk of rows, or `None` once the result is exhausted
+    pub fn next_chunk(&mut self) -> AdapterResult<Option<Vec<Vec<Value>>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        match self.receiver.recv() {
+            Ok(CursorMessage::Rows(rows)) => Ok(Some(rows)),
+            Ok(CursorMessage::Error(e)) => {
+                self.finished = true;
+                Err(e)
+            }
+            Ok(CursorMessage::Columns(_)) | Err(_) => {
+                self.finished = true;
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Iterator for RowCursor {
+    type Item = AdapterResult<Vec<Vec<Value>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn columns() -> Vec<ColumnMeta> {
+        vec![ColumnMeta {
+            name: "n".to_string(),
+            col_type: "INTEGER".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_chunks() {
+        let cursor = RowCursor::spawn(2, |sink| {
+            sink.columns(columns());
+            for i in 0..5 {
+                sink.push(vec![json!(i)]);
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(cursor.columns()[0].name, "n");
+        let sizes: Vec<usize> = cursor.map(|c| c.unwrap().len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = RowCursor::spawn(10, |_| Err(AdapterError::Query("bad sql".into())));
+        assert!(matches!(err, Err(AdapterError::Query(_))));
+
+        let mut cursor = RowCursor::spawn(1, |sink| {
+            sink.columns(columns());
+            sink.push(vec![json!(1)]);
+            Err(AdapterError::Query("mid-stream".into()))
+        })
+        .unwrap();
+        assert_eq!(cursor.next_chunk().unwrap().unwrap().len(), 1);
+        assert!(cursor.next_chunk().is_err());
+        assert!(cursor.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_backpressure_and_drop() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let mut cursor = RowCursor::spawn(10, move |sink| {
+            sink.columns(columns());
+            for i in 0..1_000_000 {
+                counter.fetch_add(1, Ordering::SeqCst);
+                if !sink.push(vec![json!(i)]) {
+                    break;
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        cursor.next_chunk().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        // One chunk consumed, at most one buffered and one being filled
+        assert!(produced.load(Ordering::SeqCst) <= 30);
+
+        drop(cursor);
+        thread::sleep(Duration::from_millis(50));
+        assert!(produced.load(Ordering::SeqCst) <= 31);
+    }
+}
@@ -6,6 +6,11 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use super::{
+    AdapterError, AdapterResult, CheckConstraint, ColumnSchema, FilterExpr, FilterOp, FilterSpec, ForeignKeyRef,
+    SqlValue, TableSchema, TriggerInfo,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SqlDialect {
@@ -24,6 +29,15 @@ impl fmt::Display for SqlDialect {
     }
 }
 
+/// Split a schema-qualified table name such as `ref.countries` (a table in
+/// an attached SQLite database) into its schema and table
+pub fn split_table(name: &str) -> (Option<&str>, &str) {
+    match name.split_once('.') {
+        Some((schema, table)) => (Some(schema), table),
+        None => (None, name),
+    }
+}
+
 impl SqlDialect {
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
@@ -167,8 +181,194 @@ impl DialectGenerator {
     /// Quote an identifier (table or column name)
     pub fn quote_ident(&self, name: &str) -> String {
         match self.dialect {
-            SqlDialect::Sqlite | SqlDialect::Postgres => format!("\"{}\"", name),
-            SqlDialect::Mysql => format!("`{}`", name),
+            SqlDialect::Sqlite | SqlDialect::Postgres => {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            }
+            SqlDialect::Mysql => format!("`{}`", name.replace('`', "``")),
+        }
+    }
+
+    /// Quote a table name, which may be schema-qualified (see `split_table`)
+    pub fn quote_table(&self, name: &str) -> String {
+        match split_table(name) {
+            (Some(schema), table) => format!("{}.{}", self.quote_ident(schema), self.quote_ident(table)),
+            (None, table) => self.quote_ident(table),
+        }
+    }
+
+    /// Bind parameter placeholder for the 1-based parameter `index`
+    pub fn placeholder(&self, index: usize) -> String {
+        match self.dialect {
+            SqlDialect::Sqlite => format!("?{}", index),
+            SqlDialect::Postgres => format!("${}", index),
+            SqlDialect::Mysql => "?".to_string(),
+        }
+    }
+
+    /// WHERE condition matching every key column, with parameters
+    /// numbered from `first_index`
+    pub fn key_condition(&self, columns: &[&str], first_index: usize) -> String {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{} = {}", self.quote_ident(c), self.placeholder(first_index + i)))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// Compile a filter expression into a parameterized WHERE condition.
+    /// Operands are appended to `params` and numbered after any already there.
+    pub fn filter_sql(&self, expr: &FilterExpr, params: &mut Vec<SqlValue>) -> AdapterResult<String> {
+        match expr {
+            FilterExpr::And { and } => self.filter_group(and, " AND ", "1 = 1", params),
+            FilterExpr::Or { or } => self.filter_group(or, " OR ", "1 = 0", params),
+            FilterExpr::Not { not } => Ok(format!("NOT ({})", self.filter_sql(not, params)?)),
+            FilterExpr::Condition(spec) => self.filter_condition(spec, params),
+        }
+    }
+
+    fn filter_group(
+        &self,
+        exprs: &[FilterExpr],
+        separator: &str,
+        empty: &str,
+        params: &mut Vec<SqlValue>,
+    ) -> AdapterResult<String> {
+        if exprs.is_empty() {
+            return Ok(empty.to_string());
+        }
+        let parts = exprs
+            .iter()
+            .map(|e| self.filter_sql(e, params).map(|sql| format!("({})", sql)))
+            .collect::<AdapterResult<Vec<_>>>()?;
+        Ok(parts.join(separator))
+    }
+
+    fn filter_condition(&self, spec: &FilterSpec, params: &mut Vec<SqlValue>) -> AdapterResult<String> {
+        let column = self.quote_ident(&spec.column);
+        let mut bind = |value: &SqlValue| {
+            params.push(value.clone());
+            self.placeholder(params.len())
+        };
+
+        let sql = match spec.operator {
+            FilterOp::IsNull | FilterOp::IsNotNull => {
+                format!("{} {}", column, spec.operator.to_sql())
+            }
+            // LIKE only exists for text types in PostgreSQL; match other columns by their text form
+            FilterOp::Like | FilterOp::NotLike => match self.dialect {
+                SqlDialect::Postgres => {
+                    format!("{}::text {} {}", column, spec.operator.to_sql(), bind(&spec.value))
+                }
+                _ => format!("{} {} {}", column, spec.operator.to_sql(), bind(&spec.value)),
+            },
+            FilterOp::ILike => match self.dialect {
+                SqlDialect::Postgres => format!("{}::text ILIKE {}", column, bind(&spec.value)),
+                _ => format!("LOWER({}) LIKE LOWER({})", column, bind(&spec.value)),
+            },
+            FilterOp::In | FilterOp::NotIn => {
+                if spec.values.is_empty() {
+                    // Nothing is in an empty set
+                    let matches_nothing = matches!(spec.operator, FilterOp::In);
+                    return Ok(if matches_nothing { "1 = 0" } else { "1 = 1" }.to_string());
+                }
+                let placeholders: Vec<String> = spec.values.iter().map(&mut bind).collect();
+                format!("{} {} ({})", column, spec.operator.to_sql(), placeholders.join(", "))
+            }
+            FilterOp::Between => {
+                let [low, high] = spec.values.as_slice() else {
+                    return Err(AdapterError::Validation(format!(
+                        "BETWEEN on {} needs exactly two values",
+                        spec.column
+                    )));
+                };
+                let low = bind(low);
+                format!("{} BETWEEN {} AND {}", column, low, bind(high))
+            }
+            _ => format!("{} {} {}", column, spec.operator.to_sql(), bind(&spec.value)),
+        };
+        Ok(sql)
+    }
+
+    /// Most bind parameters a single statement may carry
+    pub fn max_bind_params(&self) -> usize {
+        match self.dialect {
+            SqlDialect::Sqlite => 32766,
+            SqlDialect::Postgres | SqlDialect::Mysql => 65535,
+        }
+    }
+
+    /// Multi-row INSERT with `row_count` parameterized VALUES tuples
+    pub fn insert_rows(&self, table: &str, columns: &[&str], row_count: usize) -> String {
+        let cols: Vec<String> = columns.iter().map(|c| self.quote_ident(c)).collect();
+        let tuples: Vec<String> = (0..row_count)
+            .map(|r| {
+                let params: Vec<String> = (0..columns.len())
+                    .map(|c| self.placeholder(r * columns.len() + c + 1))
+                    .collect();
+                format!("({})", params.join(", "))
+            })
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.quote_table(table),
+            cols.join(", "),
+            tuples.join(", ")
+        )
+    }
+
+    /// Multi-row upsert: rows colliding on `conflict_columns` get their
+    /// remaining columns overwritten, or are skipped when nothing is left to update
+    pub fn upsert_rows(
+        &self,
+        table: &str,
+        columns: &[&str],
+        row_count: usize,
+        conflict_columns: &[&str],
+    ) -> String {
+        let insert = self.insert_rows(table, columns, row_count);
+        let updates: Vec<&str> = columns
+            .iter()
+            .filter(|c| !conflict_columns.contains(c))
+            .copied()
+            .collect();
+
+        match self.dialect {
+            SqlDialect::Sqlite | SqlDialect::Postgres => {
+                let target: Vec<String> =
+                    conflict_columns.iter().map(|c| self.quote_ident(c)).collect();
+                if updates.is_empty() {
+                    format!("{} ON CONFLICT ({}) DO NOTHING", insert, target.join(", "))
+                } else {
+                    let sets: Vec<String> = updates
+                        .iter()
+                        .map(|c| format!("{0} = excluded.{0}", self.quote_ident(c)))
+                        .collect();
+                    format!(
+                        "{} ON CONFLICT ({}) DO UPDATE SET {}",
+                        insert,
+                        target.join(", "),
+                        sets.join(", ")
+                    )
+                }
+            }
+            SqlDialect::Mysql => {
+                // MySQL resolves conflicts against every unique key; a self-assignment
+                // turns the duplicate into a no-op when there is nothing to update
+                let sets: Vec<String> = if updates.is_empty() {
+                    conflict_columns
+                        .iter()
+                        .take(1)
+                        .map(|c| format!("{0} = {0}", self.quote_ident(c)))
+                        .collect()
+                } else {
+                    updates
+                        .iter()
+                        .map(|c| format!("{0} = VALUES({0})", self.quote_ident(c)))
+                        .collect()
+                };
+                format!("{} ON DUPLICATE KEY UPDATE {}", insert, sets.join(", "))
+            }
         }
     }
 
@@ -218,11 +418,144 @@ impl DialectGenerator {
         )
     }
 
+    /// Generate CREATE TABLE SQL for an introspected table: columns, primary
+    /// key, foreign keys and CHECK constraints. Indexes and triggers are
+    /// separate statements.
+    pub fn create_table_schema(&self, table: &TableSchema) -> String {
+        let pk: Vec<&ColumnSchema> = table.columns.iter().filter(|c| c.is_primary_key).collect();
+        let inline_pk = pk.len() == 1;
+
+        let mut defs: Vec<String> = table
+            .columns
+            .iter()
+            .map(|col| {
+                // A unique index is created separately, so don't declare it twice
+                let indexed = table
+                    .indexes
+                    .iter()
+                    .any(|i| i.unique && i.columns.len() == 1 && i.columns[0] == col.name);
+                if col.is_unique && indexed {
+                    let mut col = col.clone();
+                    col.is_unique = false;
+                    self.column_definition(&col, inline_pk)
+                } else {
+                    self.column_definition(col, inline_pk)
+                }
+            })
+            .collect();
+
+        if pk.len() > 1 {
+            let cols: Vec<String> = pk.iter().map(|c| self.quote_ident(&c.name)).collect();
+            defs.push(format!("PRIMARY KEY ({})", cols.join(", ")));
+        }
+        for col in &table.columns {
+            if let Some(ref fk) = col.foreign_key {
+                defs.push(self.foreign_key_clause(&col.name, fk));
+            }
+        }
+        for check in &table.check_constraints {
+            defs.push(self.check_clause(check));
+        }
+
+        format!(
+            "CREATE TABLE {} (\n  {}\n);",
+            self.quote_ident(&table.name),
+            defs.join(",\n  ")
+        )
+    }
+
+    /// Column definition for CREATE TABLE or ADD COLUMN. `inline_pk` declares
+    /// a primary key column's PRIMARY KEY on the column itself.
+    pub fn column_definition(&self, col: &ColumnSchema, inline_pk: bool) -> String {
+        let name = self.quote_ident(&col.name);
+        let auto_increment = col.is_auto_increment && col.generated.is_none();
+
+        // SQLite only auto-assigns keys to an INTEGER PRIMARY KEY rowid alias
+        if auto_increment && inline_pk && col.is_primary_key && self.dialect == SqlDialect::Sqlite {
+            return format!("{} INTEGER PRIMARY KEY", name);
+        }
+
+        // Some mapped types carry a default of their own (UUID on Postgres)
+        let mapped = self.map_type(&col.col_type);
+        let (mapped_type, implied_default) = match mapped.split_once(" DEFAULT ") {
+            Some((ty, default)) => (ty.to_string(), Some(default.to_string())),
+            None => (mapped, None),
+        };
+        let col_type = match self.dialect {
+            SqlDialect::Postgres if auto_increment => {
+                let upper = col.col_type.to_uppercase();
+                if upper.contains("BIGINT") || upper == "INT8" {
+                    "BIGSERIAL".to_string()
+                } else if upper.contains("SMALLINT") || upper == "INT2" {
+                    "SMALLSERIAL".to_string()
+                } else {
+                    "SERIAL".to_string()
+                }
+            }
+            SqlDialect::Mysql if auto_increment => format!("{} AUTO_INCREMENT", mapped_type),
+            _ => mapped_type,
+        };
+
+        let mut def = format!("{} {}", name, col_type);
+        if let Some(ref generated) = col.generated {
+            // Postgres only has stored generated columns
+            let stored = generated.stored || self.dialect == SqlDialect::Postgres;
+            def.push_str(&format!(" GENERATED ALWAYS AS ({})", generated.expression));
+            if stored {
+                def.push_str(" STORED");
+            }
+        }
+        if inline_pk && col.is_primary_key {
+            def.push_str(" PRIMARY KEY");
+        }
+        if !col.nullable && !col.is_primary_key {
+            def.push_str(" NOT NULL");
+        }
+        if col.is_unique && !col.is_primary_key {
+            def.push_str(" UNIQUE");
+        }
+        if col.generated.is_none() && !auto_increment {
+            let default = col.default_value.clone().filter(|d| !d.is_empty()).or(implied_default);
+            if let Some(default) = default {
+                def.push_str(&format!(" DEFAULT {}", default));
+            }
+        }
+        def
+    }
+
+    /// Table-level FOREIGN KEY clause. Column-level REFERENCES is ignored
+    /// by MySQL, so constraints are always declared on the table.
+    pub fn foreign_key_clause(&self, column: &str, fk: &ForeignKeyRef) -> String {
+        format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.quote_ident(column),
+            self.quote_ident(&fk.table),
+            self.quote_ident(&fk.column)
+        )
+    }
+
+    /// Table-level CHECK clause, named when the constraint has a name
+    pub fn check_clause(&self, check: &CheckConstraint) -> String {
+        match check.name {
+            Some(ref name) => format!("CONSTRAINT {} CHECK ({})", self.quote_ident(name), check.expression),
+            None => format!("CHECK ({})", check.expression),
+        }
+    }
+
     /// Generate DROP TABLE SQL
     pub fn drop_table(&self, table: &str) -> String {
         format!("DROP TABLE {};", self.quote_ident(table))
     }
 
+    /// Generate ALTER TABLE RENAME TO SQL
+    pub fn rename_table(&self, table: &str, new_name: &str) -> String {
+        format!(
+            "ALTER TABLE {} RENAME TO {};",
+            self.quote_ident(table),
+            self.quote_ident(new_name)
+        )
+    }
+
     /// Generate ALTER TABLE ADD COLUMN SQL
     pub fn add_column(&self, table: &str, col: &ColumnDef) -> String {
         let mapped_type = self.map_type(&col.col_type);
@@ -247,6 +580,177 @@ impl DialectGenerator {
         sql
     }
 
+    /// Generate ALTER TABLE ADD COLUMN SQL for an introspected column
+    pub fn add_column_schema(&self, table: &str, col: &ColumnSchema) -> String {
+        format!(
+            "ALTER TABLE {} ADD COLUMN {};",
+            self.quote_ident(table),
+            self.column_definition(col, false)
+        )
+    }
+
+    /// Generate SQL changing a column's type, nullability and default from
+    /// `from` to `to`. SQLite can't alter columns; its tables are rebuilt
+    /// instead (see `diff`).
+    pub fn alter_column(&self, table: &str, from: &ColumnSchema, to: &ColumnSchema) -> Vec<String> {
+        let table_q = self.quote_ident(table);
+        let col_q = self.quote_ident(&to.name);
+        match self.dialect {
+            SqlDialect::Sqlite => vec![format!(
+                "-- SQLite: ALTER COLUMN requires table rebuild\n-- ALTER TABLE {} ALTER COLUMN {};",
+                table_q, col_q
+            )],
+            SqlDialect::Mysql => vec![format!(
+                "ALTER TABLE {} MODIFY COLUMN {};",
+                table_q,
+                self.column_definition(to, false)
+            )],
+            SqlDialect::Postgres => {
+                let mut stmts = Vec::new();
+                if !from.col_type.eq_ignore_ascii_case(&to.col_type) {
+                    let mapped = self.map_type(&to.col_type);
+                    let mapped = mapped.split(" DEFAULT ").next().unwrap_or_default();
+                    stmts.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};",
+                        table_q, col_q, mapped, col_q, mapped
+                    ));
+                }
+                if from.nullable != to.nullable {
+                    let action = if to.nullable { "DROP" } else { "SET" };
+                    stmts.push(format!("ALTER TABLE {} ALTER COLUMN {} {} NOT NULL;", table_q, col_q, action));
+                }
+                if from.default_value != to.default_value {
+                    stmts.push(match to.default_value {
+                        Some(ref default) if !default.is_empty() => format!(
+                            "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};",
+                            table_q, col_q, default
+                        ),
+                        _ => format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;", table_q, col_q),
+                    });
+                }
+                stmts
+            }
+        }
+    }
+
+    /// Generate SQL replacing a table's primary key
+    pub fn set_primary_key(&self, table: &str, from: &[String], to: &[String]) -> Vec<String> {
+        let table_q = self.quote_ident(table);
+        let cols: Vec<String> = to.iter().map(|c| self.quote_ident(c)).collect();
+        let mut stmts = Vec::new();
+        match self.dialect {
+            SqlDialect::Sqlite => stmts.push(format!(
+                "-- SQLite: changing the primary key requires table rebuild\n-- ALTER TABLE {} ADD PRIMARY KEY ({});",
+                table_q,
+                cols.join(", ")
+            )),
+            SqlDialect::Postgres | SqlDialect::Mysql => {
+                if !from.is_empty() {
+                    stmts.push(match self.dialect {
+                        // Postgres names the constraint <table>_pkey unless told otherwise
+                        SqlDialect::Postgres => format!(
+                            "ALTER TABLE {} DROP CONSTRAINT {};",
+                            table_q,
+                            self.quote_ident(&format!("{}_pkey", table))
+                        ),
+                        _ => format!("ALTER TABLE {} DROP PRIMARY KEY;", table_q),
+                    });
+                }
+                if !to.is_empty() {
+                    stmts.push(format!("ALTER TABLE {} ADD PRIMARY KEY ({});", table_q, cols.join(", ")));
+                }
+            }
+        }
+        stmts
+    }
+
+    /// Generate ALTER TABLE ADD FOREIGN KEY SQL
+    pub fn add_foreign_key(&self, table: &str, column: &str, fk: &ForeignKeyRef) -> String {
+        match self.dialect {
+            SqlDialect::Sqlite => format!(
+                "-- SQLite: ADD FOREIGN KEY requires table rebuild\n-- ALTER TABLE {} ADD {};",
+                self.quote_ident(table),
+                self.foreign_key_clause(column, fk)
+            ),
+            _ => format!(
+                "ALTER TABLE {} ADD {};",
+                self.quote_ident(table),
+                self.foreign_key_clause(column, fk)
+            ),
+        }
+    }
+
+    /// Generate SQL dropping a column's foreign key. Only Postgres names
+    /// unnamed constraints predictably (`<table>_<column>_fkey`).
+    pub fn drop_foreign_key(&self, table: &str, column: &str) -> String {
+        match self.dialect {
+            SqlDialect::Postgres => format!(
+                "ALTER TABLE {} DROP CONSTRAINT {};",
+                self.quote_ident(table),
+                self.quote_ident(&format!("{}_{}_fkey", table, column))
+            ),
+            SqlDialect::Sqlite => format!(
+                "-- SQLite: DROP FOREIGN KEY requires table rebuild\n-- foreign key on {}.{}",
+                self.quote_ident(table),
+                self.quote_ident(column)
+            ),
+            SqlDialect::Mysql => format!(
+                "-- MySQL: look up the foreign key's name in information_schema.KEY_COLUMN_USAGE\n-- ALTER TABLE {} DROP FOREIGN KEY <name>; -- on column {}",
+                self.quote_ident(table),
+                self.quote_ident(column)
+            ),
+        }
+    }
+
+    /// Generate ALTER TABLE ADD CHECK SQL
+    pub fn add_check(&self, table: &str, check: &CheckConstraint) -> String {
+        let sql = format!("ALTER TABLE {} ADD {};", self.quote_ident(table), self.check_clause(check));
+        match self.dialect {
+            SqlDialect::Sqlite => format!("-- SQLite: ADD CHECK requires table rebuild\n-- {}", sql),
+            _ => sql,
+        }
+    }
+
+    /// Generate SQL dropping a CHECK constraint. Unnamed constraints can't
+    /// be addressed, so only a note is emitted for them.
+    pub fn drop_check(&self, table: &str, check: &CheckConstraint) -> String {
+        let table_q = self.quote_ident(table);
+        match (self.dialect, &check.name) {
+            (SqlDialect::Sqlite, _) => format!(
+                "-- SQLite: DROP CHECK requires table rebuild\n-- CHECK ({}) on {}",
+                check.expression, table_q
+            ),
+            (_, None) => format!(
+                "-- Unnamed CHECK ({}) on {} must be dropped by its backend-assigned name",
+                check.expression, table_q
+            ),
+            (SqlDialect::Postgres, Some(name)) => {
+                format!("ALTER TABLE {} DROP CONSTRAINT {};", table_q, self.quote_ident(name))
+            }
+            (SqlDialect::Mysql, Some(name)) => {
+                format!("ALTER TABLE {} DROP CHECK {};", table_q, self.quote_ident(name))
+            }
+        }
+    }
+
+    /// Generate CREATE TRIGGER SQL. Trigger bodies are backend-specific,
+    /// so the stored definition is emitted as-is.
+    pub fn create_trigger(&self, trigger: &TriggerInfo) -> String {
+        format!("{};", trigger.definition.trim().trim_end_matches(';'))
+    }
+
+    /// Generate DROP TRIGGER SQL
+    pub fn drop_trigger(&self, trigger: &TriggerInfo) -> String {
+        match self.dialect {
+            SqlDialect::Postgres => format!(
+                "DROP TRIGGER {} ON {};",
+                self.quote_ident(&trigger.name),
+                self.quote_ident(&trigger.table)
+            ),
+            _ => format!("DROP TRIGGER {};", self.quote_ident(&trigger.name)),
+        }
+    }
+
     /// Generate ALTER TABLE DROP COLUMN SQL (not supported in old SQLite)
     pub fn drop_column(&self, table: &str, column: &str) -> String {
         match self.dialect {
@@ -289,12 +793,14 @@ impl DialectGenerator {
     }
 
     /// Generate DROP INDEX SQL
-    pub fn drop_index(&self, index_name: &str) -> String {
+    pub fn drop_index(&self, table: &str, index_name: &str) -> String {
         match self.dialect {
-            SqlDialect::Mysql => {
-                // MySQL requires table name for DROP INDEX, but we generate basic form
-                format!("DROP INDEX {};", self.quote_ident(index_name))
-            }
+            // MySQL indexes belong to their table
+            SqlDialect::Mysql => format!(
+                "DROP INDEX {} ON {};",
+                self.quote_ident(index_name),
+                self.quote_ident(table)
+            ),
             _ => format!("DROP INDEX {};", self.quote_ident(index_name)),
         }
     }
@@ -303,12 +809,35 @@ impl DialectGenerator {
     pub fn select_paginated(&self, table: &str, limit: usize, offset: usize) -> String {
         format!(
             "SELECT * FROM {} LIMIT {} OFFSET {}",
-            self.quote_ident(table),
+            self.quote_table(table),
             limit,
             offset
         )
     }
 
+    /// Statement that opens a transaction
+    pub fn begin_transaction(&self) -> &'static str {
+        match self.dialect {
+            SqlDialect::Sqlite | SqlDialect::Postgres => "BEGIN",
+            SqlDialect::Mysql => "START TRANSACTION",
+        }
+    }
+
+    /// Generate SAVEPOINT SQL
+    pub fn savepoint(&self, name: &str) -> String {
+        format!("SAVEPOINT {}", self.quote_ident(name))
+    }
+
+    /// Generate RELEASE SAVEPOINT SQL
+    pub fn release_savepoint(&self, name: &str) -> String {
+        format!("RELEASE SAVEPOINT {}", self.quote_ident(name))
+    }
+
+    /// Generate ROLLBACK TO SAVEPOINT SQL
+    pub fn rollback_to_savepoint(&self, name: &str) -> String {
+        format!("ROLLBACK TO SAVEPOINT {}", self.quote_ident(name))
+    }
+
     /// Get the current timestamp expression
     pub fn now_expr(&self) -> &'static str {
         match self.dialect {
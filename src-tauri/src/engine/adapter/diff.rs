@@ -0,0 +1,663 @@
+//! Schema Diff
+//!
+//! Compares two whole-database schemas (the live database against a
+//! snapshot, one branch against another, or one backend against another)
+//! and plans the changes that take the first to the second. Plans are
+//! dialect-neutral and rendered to DDL through `DialectGenerator`.
+//!
+//! SQLite can't alter columns or constraints in place, so on SQLite those
+//! tables are rebuilt: the target table is created under a temporary name,
+//! the rows copied across, and the old table swapped out. A rendered plan
+//! that rebuilds tables turns foreign key enforcement off around itself.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use super::sqlite::SqliteAdapter;
+use super::{
+    AdapterResult, CheckConstraint, ColumnSchema, DatabaseAdapter, DialectGenerator, ForeignKeyRef, IndexInfo,
+    SqlDialect, TableSchema, TriggerInfo,
+};
+
+/// Prefix of the table a SQLite rebuild copies rows into
+const REBUILD_PREFIX: &str = "_airdb_rebuild_";
+
+/// One step of a schema change plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SchemaChange {
+    CreateTable { table: TableSchema },
+    DropTable { table: String },
+    AddColumn { table: String, column: ColumnSchema },
+    DropColumn { table: String, column: String },
+    /// The column's type, nullability or default changed
+    AlterColumn { table: String, from: ColumnSchema, to: ColumnSchema },
+    SetPrimaryKey { table: String, from: Vec<String>, to: Vec<String> },
+    AddForeignKey { table: String, column: String, references: ForeignKeyRef },
+    DropForeignKey { table: String, column: String, references: ForeignKeyRef },
+    AddCheck { table: String, check: CheckConstraint },
+    DropCheck { table: String, check: CheckConstraint },
+    CreateIndex { table: String, index: IndexInfo },
+    DropIndex { table: String, index: IndexInfo },
+    CreateTrigger { trigger: TriggerInfo },
+    DropTrigger { trigger: TriggerInfo },
+}
+
+impl SchemaChange {
+    /// The table this change applies to
+    pub fn table(&self) -> &str {
+        match self {
+            SchemaChange::CreateTable { table } => &table.name,
+            SchemaChange::CreateTrigger { trigger } | SchemaChange::DropTrigger { trigger } => &trigger.table,
+            SchemaChange::DropTable { table }
+            | SchemaChange::AddColumn { table, .. }
+            | SchemaChange::DropColumn { table, .. }
+            | SchemaChange::AlterColumn { table, .. }
+            | SchemaChange::SetPrimaryKey { table, .. }
+            | SchemaChange::AddForeignKey { table, .. }
+            | SchemaChange::DropForeignKey { table, .. }
+            | SchemaChange::AddCheck { table, .. }
+            | SchemaChange::DropCheck { table, .. }
+            | SchemaChange::CreateIndex { table, .. }
+            | SchemaChange::DropIndex { table, .. } => table,
+        }
+    }
+
+    /// Whether applying this change can lose data
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            SchemaChange::DropTable { .. } | SchemaChange::DropColumn { .. } => true,
+            SchemaChange::AlterColumn { from, to, .. } => !same_type(&from.col_type, &to.col_type),
+            _ => false,
+        }
+    }
+
+    /// Position in the plan: dependents are dropped before what they
+    /// depend on, and created after it
+    fn rank(&self) -> u8 {
+        match self {
+            SchemaChange::DropTrigger { .. } => 0,
+            SchemaChange::DropForeignKey { .. } => 1,
+            SchemaChange::DropIndex { .. } => 2,
+            SchemaChange::DropCheck { .. } => 3,
+            SchemaChange::DropTable { .. } => 4,
+            SchemaChange::CreateTable { .. } => 5,
+            SchemaChange::AddColumn { .. } => 6,
+            SchemaChange::AlterColumn { .. } => 7,
+            SchemaChange::SetPrimaryKey { .. } => 8,
+            SchemaChange::DropColumn { .. } => 9,
+            SchemaChange::AddForeignKey { .. } => 10,
+            SchemaChange::AddCheck { .. } => 11,
+            SchemaChange::CreateIndex { .. } => 12,
+            SchemaChange::CreateTrigger { .. } => 13,
+        }
+    }
+
+    /// Whether SQLite has to rebuild the table to make this change
+    fn needs_sqlite_rebuild(&self) -> bool {
+        match self {
+            SchemaChange::AddColumn { column, .. } => {
+                // ADD COLUMN can't add keys, stored columns, or NOT NULL
+                // columns without a default
+                column.is_primary_key
+                    || column.is_unique
+                    || column.generated.as_ref().is_some_and(|g| g.stored)
+                    || (!column.nullable && column.default_value.as_deref().is_none_or(str::is_empty))
+            }
+            SchemaChange::DropColumn { .. }
+            | SchemaChange::AlterColumn { .. }
+            | SchemaChange::SetPrimaryKey { .. }
+            | SchemaChange::AddForeignKey { .. }
+            | SchemaChange::DropForeignKey { .. }
+            | SchemaChange::AddCheck { .. }
+            | SchemaChange::DropCheck { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// DDL for this change on its own
+    fn render(&self, gen: &DialectGenerator) -> Vec<String> {
+        match self {
+            SchemaChange::CreateTable { table } => vec![gen.create_table_schema(table)],
+            SchemaChange::DropTable { table } => vec![gen.drop_table(table)],
+            SchemaChange::AddColumn { table, column } => vec![gen.add_column_schema(table, column)],
+            SchemaChange::DropColumn { table, column } => vec![gen.drop_column(table, column)],
+            SchemaChange::AlterColumn { table, from, to } => gen.alter_column(table, from, to),
+            SchemaChange::SetPrimaryKey { table, from, to } => gen.set_primary_key(table, from, to),
+            SchemaChange::AddForeignKey { table, column, references } => {
+                vec![gen.add_foreign_key(table, column, references)]
+            }
+            SchemaChange::DropForeignKey { table, column, .. } => vec![gen.drop_foreign_key(table, column)],
+            SchemaChange::AddCheck { table, check } => vec![gen.add_check(table, check)],
+            SchemaChange::DropCheck { table, check } => vec![gen.drop_check(table, check)],
+            SchemaChange::CreateIndex { table, index } => vec![create_index(gen, table, index)],
+            SchemaChange::DropIndex { table, index } => vec![gen.drop_index(table, &index.name)],
+            SchemaChange::CreateTrigger { trigger } => vec![gen.create_trigger(trigger)],
+            SchemaChange::DropTrigger { trigger } => vec![gen.drop_trigger(trigger)],
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    /// A one-line summary, e.g. `add column users.email`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaChange::CreateTable { table } => write!(f, "create table {}", table.name),
+            SchemaChange::DropTable { table } => write!(f, "drop table {}", table),
+            SchemaChange::AddColumn { table, column } => write!(f, "add column {}.{}", table, column.name),
+            SchemaChange::DropColumn { table, column } => write!(f, "drop column {}.{}", table, column),
+            SchemaChange::AlterColumn { table, from, to } if !same_type(&from.col_type, &to.col_type) => {
+                write!(f, "alter column {}.{} ({} to {})", table, to.name, from.col_type, to.col_type)
+            }
+            SchemaChange::AlterColumn { table, to, .. } => write!(f, "alter column {}.{}", table, to.name),
+            SchemaChange::SetPrimaryKey { table, to, .. } => {
+                write!(f, "set primary key of {} to ({})", table, to.join(", "))
+            }
+            SchemaChange::AddForeignKey { table, column, references } => {
+                write!(f, "add foreign key {}.{} to {}.{}", table, column, references.table, references.column)
+            }
+            SchemaChange::DropForeignKey { table, column, .. } => write!(f, "drop foreign key {}.{}", table, column),
+            SchemaChange::AddCheck { table, check } => write!(f, "add check on {}: {}", table, check.expression),
+            SchemaChange::DropCheck { table, check } => write!(f, "drop check on {}: {}", table, check.expression),
+            SchemaChange::CreateIndex { table, index } => write!(f, "create index {} on {}", index.name, table),
+            SchemaChange::DropIndex { table, index } => write!(f, "drop index {} on {}", index.name, table),
+            SchemaChange::CreateTrigger { trigger } => {
+                write!(f, "create trigger {} on {}", trigger.name, trigger.table)
+            }
+            SchemaChange::DropTrigger { trigger } => write!(f, "drop trigger {} on {}", trigger.name, trigger.table),
+        }
+    }
+}
+
+/// Ordered plan of changes from one schema to another
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+    /// Both schemas, by table name, for rebuilding SQLite tables
+    #[serde(skip)]
+    from: BTreeMap<String, TableSchema>,
+    #[serde(skip)]
+    to: BTreeMap<String, TableSchema>,
+}
+
+impl SchemaDiff {
+    /// Plan the changes that turn the `from` tables into the `to` tables
+    pub fn between(from: &[TableSchema], to: &[TableSchema]) -> Self {
+        let from: BTreeMap<String, TableSchema> = from.iter().map(|t| (t.name.clone(), t.clone())).collect();
+        let to: BTreeMap<String, TableSchema> = to.iter().map(|t| (t.name.clone(), t.clone())).collect();
+        let mut changes = Vec::new();
+
+        for (name, table) in &to {
+            match from.get(name) {
+                Some(old) => diff_table(old, table, &mut changes),
+                None => {
+                    changes.push(SchemaChange::CreateTable { table: table.clone() });
+                    for index in &table.indexes {
+                        changes.push(SchemaChange::CreateIndex {
+                            table: name.clone(),
+                            index: index.clone(),
+                        });
+                    }
+                    for trigger in &table.triggers {
+                        changes.push(SchemaChange::CreateTrigger { trigger: trigger.clone() });
+                    }
+                }
+            }
+        }
+        for name in from.keys().filter(|name| !to.contains_key(*name)) {
+            changes.push(SchemaChange::DropTable { table: name.clone() });
+        }
+
+        // Tables are created after the tables they reference and dropped
+        // before them; everything else keeps table name order
+        let create_order = dependency_order(&to);
+        let drop_order: Vec<String> = dependency_order(&from).into_iter().rev().collect();
+        changes.sort_by_key(|change| {
+            let position = match change {
+                SchemaChange::CreateTable { table } => create_order.iter().position(|n| *n == table.name),
+                SchemaChange::DropTable { table } => drop_order.iter().position(|n| n == table),
+                _ => None,
+            };
+            (change.rank(), position.unwrap_or(0))
+        });
+
+        Self { changes, from, to }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change in the plan can lose data
+    pub fn is_destructive(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_destructive)
+    }
+
+    /// Render the plan as DDL statements in the generator's dialect
+    pub fn render(&self, gen: &DialectGenerator) -> Vec<String> {
+        let rebuilds: BTreeSet<&str> = if gen.dialect == SqlDialect::Sqlite {
+            self.changes
+                .iter()
+                .filter(|c| c.needs_sqlite_rebuild() && self.from.contains_key(c.table()))
+                .map(SchemaChange::table)
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+
+        // Each table is rebuilt once, in place of the last change that needs it
+        let mut rebuild_at = BTreeMap::new();
+        for (i, change) in self.changes.iter().enumerate() {
+            if rebuilds.contains(change.table()) && change.needs_sqlite_rebuild() {
+                rebuild_at.insert(change.table(), i);
+            }
+        }
+
+        let mut stmts = Vec::new();
+        if !rebuilds.is_empty() {
+            stmts.push("PRAGMA foreign_keys = OFF;".to_string());
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            let table = change.table();
+            if rebuilds.contains(table) {
+                match change {
+                    _ if change.needs_sqlite_rebuild() => {
+                        if rebuild_at.get(table) == Some(&i) {
+                            stmts.extend(self.rebuild_sqlite_table(gen, table));
+                        }
+                        continue;
+                    }
+                    // The rebuild recreates all of the table's indexes and triggers
+                    SchemaChange::CreateIndex { .. } | SchemaChange::CreateTrigger { .. } => continue,
+                    _ => {}
+                }
+            }
+            stmts.extend(change.render(gen));
+        }
+        if !rebuilds.is_empty() {
+            stmts.push("PRAGMA foreign_keys = ON;".to_string());
+        }
+        stmts
+    }
+
+    /// Render the plan as a single SQL script
+    pub fn to_sql(&self, dialect: SqlDialect) -> String {
+        self.render(&DialectGenerator::new(dialect)).join("\n\n")
+    }
+
+    /// Recreate a SQLite table with its target definition, keeping the rows
+    /// of every column that exists before and after
+    fn rebuild_sqlite_table(&self, gen: &DialectGenerator, table: &str) -> Vec<String> {
+        let (Some(old), Some(new)) = (self.from.get(table), self.to.get(table)) else {
+            return Vec::new();
+        };
+        let temp = format!("{}{}", REBUILD_PREFIX, table);
+        let mut staged = new.clone();
+        staged.name = temp.clone();
+
+        let copied: Vec<String> = new
+            .columns
+            .iter()
+            .filter(|c| c.generated.is_none() && old.columns.iter().any(|o| o.name == c.name))
+            .map(|c| gen.quote_ident(&c.name))
+            .collect();
+
+        let mut stmts = vec![gen.create_table_schema(&staged)];
+        if !copied.is_empty() {
+            stmts.push(format!(
+                "INSERT INTO {} ({cols}) SELECT {cols} FROM {};",
+                gen.quote_ident(&temp),
+                gen.quote_ident(table),
+                cols = copied.join(", ")
+            ));
+        }
+        stmts.push(gen.drop_table(table));
+        // Keep SQLite from rewriting references in views and triggers, which
+        // still name the original table
+        stmts.push("PRAGMA legacy_alter_table = ON;".to_string());
+        stmts.push(gen.rename_table(&temp, table));
+        stmts.push("PRAGMA legacy_alter_table = OFF;".to_string());
+        stmts.extend(new.indexes.iter().map(|index| create_index(gen, table, index)));
+        stmts.extend(new.triggers.iter().map(|trigger| gen.create_trigger(trigger)));
+        stmts
+    }
+}
+
+/// Full schema of a SQL script, read back by running it against an
+/// in-memory SQLite database
+pub fn schema_from_sql(sql: &str) -> AdapterResult<Vec<TableSchema>> {
+    let adapter = SqliteAdapter::in_memory()?;
+    adapter.execute_batch(sql)?;
+    adapter.get_schema()
+}
+
+fn diff_table(old: &TableSchema, new: &TableSchema, changes: &mut Vec<SchemaChange>) {
+    let table = &new.name;
+
+    for column in &new.columns {
+        let Some(previous) = old.columns.iter().find(|c| c.name == column.name) else {
+            changes.push(SchemaChange::AddColumn {
+                table: table.clone(),
+                column: column.clone(),
+            });
+            if let Some(ref fk) = column.foreign_key {
+                changes.push(SchemaChange::AddForeignKey {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    references: fk.clone(),
+                });
+            }
+            continue;
+        };
+
+        // A column can't be switched to or from generated in place
+        let generated = |c: &ColumnSchema| c.generated.as_ref().map(|g| (normalize_expr(&g.expression), g.stored));
+        if generated(previous) != generated(column) {
+            changes.push(SchemaChange::DropColumn {
+                table: table.clone(),
+                column: column.name.clone(),
+            });
+            changes.push(SchemaChange::AddColumn {
+                table: table.clone(),
+                column: column.clone(),
+            });
+        } else if !same_type(&previous.col_type, &column.col_type)
+            || previous.nullable != column.nullable
+            || !(same_default(&previous.default_value, &column.default_value)
+                || previous.is_auto_increment && column.is_auto_increment)
+        {
+            changes.push(SchemaChange::AlterColumn {
+                table: table.clone(),
+                from: previous.clone(),
+                to: column.clone(),
+            });
+        }
+
+        let fk_key = |c: &ColumnSchema| c.foreign_key.as_ref().map(|fk| (fk.table.clone(), fk.column.clone()));
+        if fk_key(previous) != fk_key(column) {
+            if let Some(ref fk) = previous.foreign_key {
+                changes.push(SchemaChange::DropForeignKey {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    references: fk.clone(),
+                });
+            }
+            if let Some(ref fk) = column.foreign_key {
+                changes.push(SchemaChange::AddForeignKey {
+                    table: table.clone(),
+                    column: column.name.clone(),
+                    references: fk.clone(),
+                });
+            }
+        }
+    }
+    for column in &old.columns {
+        if !new.columns.iter().any(|c| c.name == column.name) {
+            changes.push(SchemaChange::DropColumn {
+                table: table.clone(),
+                column: column.name.clone(),
+            });
+        }
+    }
+
+    let primary_key = |t: &TableSchema| -> Vec<String> {
+        t.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect()
+    };
+    let (old_pk, new_pk) = (primary_key(old), primary_key(new));
+    if old_pk != new_pk {
+        changes.push(SchemaChange::SetPrimaryKey {
+            table: table.clone(),
+            from: old_pk,
+            to: new_pk,
+        });
+    }
+
+    let check_key = |c: &CheckConstraint| (c.name.clone(), normalize_expr(&c.expression));
+    let old_checks: HashSet<_> = old.check_constraints.iter().map(check_key).collect();
+    let new_checks: HashSet<_> = new.check_constraints.iter().map(check_key).collect();
+    for check in &old.check_constraints {
+        if !new_checks.contains(&check_key(check)) {
+            changes.push(SchemaChange::DropCheck {
+                table: table.clone(),
+                check: check.clone(),
+            });
+        }
+    }
+    for check in &new.check_constraints {
+        if !old_checks.contains(&check_key(check)) {
+            changes.push(SchemaChange::AddCheck {
+                table: table.clone(),
+                check: check.clone(),
+            });
+        }
+    }
+
+    let same_index = |a: &IndexInfo, b: &IndexInfo| a.unique == b.unique && a.columns == b.columns;
+    for index in &old.indexes {
+        if !new.indexes.iter().any(|i| i.name == index.name && same_index(i, index)) {
+            changes.push(SchemaChange::DropIndex {
+                table: table.clone(),
+                index: index.clone(),
+            });
+        }
+    }
+    for index in &new.indexes {
+        if !old.indexes.iter().any(|i| i.name == index.name && same_index(i, index)) {
+            changes.push(SchemaChange::CreateIndex {
+                table: table.clone(),
+                index: index.clone(),
+            });
+        }
+    }
+
+    let same_trigger = |a: &TriggerInfo, b: &TriggerInfo| a.name == b.name && squash(&a.definition) == squash(&b.definition);
+    for trigger in &old.triggers {
+        if !new.triggers.iter().any(|t| same_trigger(t, trigger)) {
+            changes.push(SchemaChange::DropTrigger { trigger: trigger.clone() });
+        }
+    }
+    for trigger in &new.triggers {
+        if !old.triggers.iter().any(|t| same_trigger(t, trigger)) {
+            changes.push(SchemaChange::CreateTrigger { trigger: trigger.clone() });
+        }
+    }
+}
+
+fn create_index(gen: &DialectGenerator, table: &str, index: &IndexInfo) -> String {
+    let columns: Vec<&str> = index.columns.iter().map(String::as_str).collect();
+    gen.create_index(table, &index.name, &columns, index.unique)
+}
+
+/// Table names ordered so referenced tables come before the tables that
+/// reference them. Tables in a reference cycle keep name order.
+pub(super) fn dependency_order(tables: &BTreeMap<String, TableSchema>) -> Vec<String> {
+    fn visit(name: &str, tables: &BTreeMap<String, TableSchema>, seen: &mut HashSet<String>, order: &mut Vec<String>) {
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        if let Some(table) = tables.get(name) {
+            for fk in table.columns.iter().filter_map(|c| c.foreign_key.as_ref()) {
+                if fk.table != name {
+                    visit(&fk.table, tables, seen, order);
+                }
+            }
+            order.push(name.to_string());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    for name in tables.keys() {
+        visit(name, tables, &mut seen, &mut order);
+    }
+    order
+}
+
+/// Collapse runs of whitespace to single spaces
+fn squash(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// An expression with whitespace squashed and redundant outer parentheses
+/// removed, which backends add or drop when they store expressions
+pub(super) fn normalize_expr(expr: &str) -> String {
+    let mut expr = squash(expr);
+    while expr.starts_with('(') && expr.ends_with(')') {
+        // Only strip parentheses that enclose the whole expression
+        let mut depth = 0;
+        let encloses = expr.char_indices().all(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth > 0 || i == expr.len() - 1
+        });
+        if !encloses {
+            break;
+        }
+        expr = expr[1..expr.len() - 1].trim().to_string();
+    }
+    expr
+}
+
+fn same_type(a: &str, b: &str) -> bool {
+    squash(a).eq_ignore_ascii_case(&squash(b))
+}
+
+fn same_default(a: &Option<String>, b: &Option<String>) -> bool {
+    let normalize = |d: &Option<String>| d.as_deref().map(normalize_expr).filter(|d| !d.is_empty());
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "
+        CREATE TABLE owners (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE pets (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER,
+            legacy TEXT
+        );
+        CREATE INDEX idx_pets_name ON pets (name);
+        CREATE TABLE archive (id INTEGER PRIMARY KEY);
+        INSERT INTO owners (name) VALUES ('ann');
+        INSERT INTO pets (name, age, legacy) VALUES ('rex', 3, 'x');";
+
+    const AFTER: &str = "
+        CREATE TABLE owners (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT);
+        CREATE TABLE pets (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL DEFAULT 0 CHECK (age >= 0),
+            owner_id INTEGER REFERENCES owners (id)
+        );
+        CREATE UNIQUE INDEX idx_pets_name ON pets (name);
+        CREATE TABLE visits (id INTEGER PRIMARY KEY, pet_id INTEGER NOT NULL REFERENCES pets (id));
+        CREATE TRIGGER pets_age AFTER UPDATE OF age ON pets BEGIN SELECT 1; END;";
+
+    fn kinds(diff: &SchemaDiff) -> Vec<String> {
+        diff.changes
+            .iter()
+            .map(|c| {
+                let kind = serde_json::to_value(c).unwrap()["kind"].as_str().unwrap().to_string();
+                format!("{}:{}", kind, c.table())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_order() {
+        let before = schema_from_sql(BEFORE).unwrap();
+        let after = schema_from_sql(AFTER).unwrap();
+        let diff = SchemaDiff::between(&before, &after);
+
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                "dropIndex:pets",
+                "dropTable:archive",
+                "createTable:visits",
+                "addColumn:owners",
+                "addColumn:pets",
+                "alterColumn:pets",
+                "dropColumn:pets",
+                "addForeignKey:pets",
+                "addCheck:pets",
+                "createIndex:pets",
+                "createTrigger:pets",
+            ]
+        );
+        assert!(diff.is_destructive());
+        assert_eq!(diff.changes[2].to_string(), "create table visits");
+        assert_eq!(diff.changes[6].to_string(), "drop column pets.legacy");
+        assert!(SchemaDiff::between(&after, &schema_from_sql(AFTER).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let after = schema_from_sql(
+            "CREATE TABLE a (id INTEGER PRIMARY KEY, c_id INTEGER REFERENCES c (id));
+             CREATE TABLE b (id INTEGER PRIMARY KEY);
+             CREATE TABLE c (id INTEGER PRIMARY KEY, b_id INTEGER REFERENCES b (id));",
+        )
+        .unwrap();
+        let created = SchemaDiff::between(&[], &after);
+        assert_eq!(kinds(&created), vec!["createTable:b", "createTable:c", "createTable:a"]);
+        let dropped = SchemaDiff::between(&after, &[]);
+        assert_eq!(kinds(&dropped), vec!["dropTable:a", "dropTable:c", "dropTable:b"]);
+    }
+
+    #[test]
+    fn test_sqlite_rebuild_applies() {
+        let adapter = SqliteAdapter::in_memory().unwrap();
+        adapter.execute_batch(BEFORE).unwrap();
+        let after = schema_from_sql(AFTER).unwrap();
+        let diff = SchemaDiff::between(&adapter.get_schema().unwrap(), &after);
+
+        let sql = diff.to_sql(SqlDialect::Sqlite);
+        assert!(sql.starts_with("PRAGMA foreign_keys = OFF;"));
+        assert!(sql.contains("CREATE TABLE \"_airdb_rebuild_pets\""));
+        adapter.execute_batch(&sql).unwrap();
+
+        // The rebuilt database matches the target, and kept its rows
+        assert!(SchemaDiff::between(&adapter.get_schema().unwrap(), &after).is_empty());
+        let rows = adapter.query("SELECT name, age FROM pets", &[]).unwrap();
+        assert_eq!(rows.rows, vec![vec![serde_json::json!("rex"), serde_json::json!(3)]]);
+    }
+
+    #[test]
+    fn test_render_postgres_and_mysql() {
+        let before = schema_from_sql(BEFORE).unwrap();
+        let after = schema_from_sql(AFTER).unwrap();
+        let diff = SchemaDiff::between(&before, &after);
+
+        let pg = diff.render(&DialectGenerator::new(SqlDialect::Postgres));
+        assert!(pg.contains(&"DROP INDEX \"idx_pets_name\";".to_string()));
+        assert!(pg.contains(&"ALTER TABLE \"pets\" ALTER COLUMN \"age\" SET NOT NULL;".to_string()));
+        assert!(pg.contains(&"ALTER TABLE \"pets\" ALTER COLUMN \"age\" SET DEFAULT 0;".to_string()));
+        assert!(pg.contains(&"ALTER TABLE \"pets\" ADD CHECK (age >= 0);".to_string()));
+        assert!(pg.contains(
+            &"CREATE TABLE \"visits\" (\n  \"id\" SERIAL PRIMARY KEY,\n  \"pet_id\" INTEGER NOT NULL,\n  FOREIGN KEY (\"pet_id\") REFERENCES \"pets\" (\"id\")\n);"
+                .to_string()
+        ));
+        assert!(!pg.iter().any(|s| s.contains("PRAGMA")));
+
+        let mysql = diff.render(&DialectGenerator::new(SqlDialect::Mysql));
+        assert!(mysql.contains(&"DROP INDEX `idx_pets_name` ON `pets`;".to_string()));
+        assert!(mysql.contains(&"ALTER TABLE `pets` MODIFY COLUMN `age` INTEGER NOT NULL DEFAULT 0;".to_string()));
+        assert!(mysql.contains(&"ALTER TABLE `pets` ADD FOREIGN KEY (`owner_id`) REFERENCES `owners` (`id`);".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_expr() {
+        assert_eq!(normalize_expr("((qty  >\n 0))"), "qty > 0");
+        assert_eq!(normalize_expr("(a) + (b)"), "(a) + (b)");
+        assert!(same_default(&Some("(0)".into()), &Some("0".into())));
+        assert!(same_default(&Some(String::new()), &None));
+    }
+}
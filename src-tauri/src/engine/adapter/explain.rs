@@ -0,0 +1,310 @@
+//! Query Plans
+//!
+//! Asks the backend how it would run a statement, without running it, and
+//! normalizes the answer into a tree of plan nodes: `EXPLAIN QUERY PLAN`
+//! on SQLite, `EXPLAIN (FORMAT JSON)` on Postgres and the tabular `EXPLAIN`
+//! on MySQL. Each node records the table it reads, the index it uses (if
+//! any) and whether it reads the whole table.
+//!
+//! Only Postgres reports costs. SQLite reports no estimates at all, and
+//! MySQL only estimated rows.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{AdapterError, AdapterResult, QueryResult, SqlDialect, SqlValue};
+
+/// How the backend plans to run a statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPlan {
+    pub dialect: SqlDialect,
+    pub nodes: Vec<PlanNode>,
+    /// Estimated cost of the whole statement, in the planner's own units
+    pub total_cost: Option<f64>,
+    /// Tables read in full without an index
+    pub full_scans: Vec<String>,
+}
+
+/// One step of a query plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanNode {
+    /// Operation as the backend names it, e.g. `SEARCH`, `Seq Scan`, `ref`
+    pub operation: String,
+    /// The backend's full description of the step
+    pub detail: String,
+    /// Table read by this step. SQLite names it by its alias when the
+    /// query gives it one.
+    pub table: Option<String>,
+    pub index: Option<String>,
+    /// Whether every row of `table` is read without an index
+    pub full_scan: bool,
+    pub estimated_rows: Option<f64>,
+    pub estimated_cost: Option<f64>,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn new(operation: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            operation: operation.into(),
+            detail: detail.into(),
+            table: None,
+            index: None,
+            full_scan: false,
+            estimated_rows: None,
+            estimated_cost: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn collect_full_scans(&self, tables: &mut Vec<String>) {
+        if let (true, Some(table)) = (self.full_scan, &self.table) {
+            if !tables.contains(table) {
+                tables.push(table.clone());
+            }
+        }
+        for child in &self.children {
+            child.collect_full_scans(tables);
+        }
+    }
+}
+
+impl QueryPlan {
+    fn new(dialect: SqlDialect, nodes: Vec<PlanNode>, total_cost: Option<f64>) -> Self {
+        let mut full_scans = Vec::new();
+        for node in &nodes {
+            node.collect_full_scans(&mut full_scans);
+        }
+        Self {
+            dialect,
+            nodes,
+            total_cost,
+            full_scans,
+        }
+    }
+}
+
+/// Plan `sql` on a `dialect` backend, running SQL through `run`
+pub fn explain<F>(dialect: SqlDialect, sql: &str, params: &[SqlValue], mut run: F) -> AdapterResult<QueryPlan>
+where
+    F: FnMut(&str, &[SqlValue]) -> AdapterResult<QueryResult>,
+{
+    let sql = sql.trim().trim_end_matches(';');
+    if sql.is_empty() {
+        return Err(AdapterError::Validation("Nothing to explain".to_string()));
+    }
+    match dialect {
+        SqlDialect::Sqlite => Ok(sqlite_plan(&run(&format!("EXPLAIN QUERY PLAN {}", sql), params)?)),
+        SqlDialect::Postgres => postgres_plan(&run(&format!("EXPLAIN (FORMAT JSON) {}", sql), params)?),
+        SqlDialect::Mysql => Ok(mysql_plan(&run(&format!("EXPLAIN {}", sql), params)?)),
+    }
+}
+
+/// `EXPLAIN QUERY PLAN` rows are `(id, parent, notused, detail)`, with
+/// details like `SCAN users` or `SEARCH posts USING INDEX idx (author_id=?)`
+fn sqlite_plan(result: &QueryResult) -> QueryPlan {
+    let rows: Vec<(i64, i64, PlanNode)> = result
+        .rows
+        .iter()
+        .map(|row| {
+            let int = |i: usize| row.get(i).and_then(Value::as_i64).unwrap_or_default();
+            let detail = row.get(3).and_then(Value::as_str).unwrap_or_default();
+            (int(0), int(1), sqlite_node(detail))
+        })
+        .collect();
+
+    fn children_of(parent: i64, rows: &[(i64, i64, PlanNode)]) -> Vec<PlanNode> {
+        rows.iter()
+            .filter(|(id, p, _)| *p == parent && *id != parent)
+            .map(|(id, _, node)| {
+                let mut node = node.clone();
+                node.children = children_of(*id, rows);
+                node
+            })
+            .collect()
+    }
+    QueryPlan::new(SqlDialect::Sqlite, children_of(0, &rows), None)
+}
+
+fn sqlite_node(detail: &str) -> PlanNode {
+    let words: Vec<&str> = detail.split_whitespace().collect();
+    let operation = match words.first() {
+        Some(&op @ ("SCAN" | "SEARCH")) => op,
+        _ => return PlanNode::new(detail, detail),
+    };
+    let mut node = PlanNode::new(operation, detail);
+
+    // Older SQLite versions say `SCAN TABLE t`
+    let name = match words.get(1) {
+        Some(&"TABLE") => words.get(2),
+        other => other,
+    };
+    // `SCAN CONSTANT ROW`, `SCAN SUBQUERY 1`, `SCAN (subquery-1)` read no table
+    if let Some(name) = name.filter(|n| !matches!(**n, "CONSTANT" | "SUBQUERY") && !n.starts_with('(')) {
+        node.table = Some(name.to_string());
+    }
+
+    let using = |marker: &str| detail.find(marker).map(|at| &detail[at + marker.len()..]);
+    node.index = if let Some(rest) = using("USING COVERING INDEX ").or_else(|| using("USING INDEX ")) {
+        rest.split_whitespace().next().map(str::to_string)
+    } else if detail.contains("PRIMARY KEY") {
+        Some("PRIMARY KEY".to_string())
+    } else {
+        None
+    };
+    node.full_scan =
+        operation == "SCAN" && node.table.is_some() && node.index.is_none() && !detail.contains("VIRTUAL TABLE");
+    node
+}
+
+/// `EXPLAIN (FORMAT JSON)` returns one row holding `[{"Plan": {...}}]`
+fn postgres_plan(result: &QueryResult) -> AdapterResult<QueryPlan> {
+    let raw = result.rows.first().and_then(|row| row.first()).cloned().unwrap_or(Value::Null);
+    // Decoded as JSON, or as text when the driver doesn't know the type
+    let document = match raw {
+        Value::String(text) => serde_json::from_str(&text).unwrap_or(Value::Null),
+        other => other,
+    };
+    let plan = document
+        .get(0)
+        .and_then(|d| d.get("Plan"))
+        .ok_or_else(|| AdapterError::Query("EXPLAIN returned no plan".to_string()))?;
+
+    let root = postgres_node(plan);
+    let total_cost = root.estimated_cost;
+    Ok(QueryPlan::new(SqlDialect::Postgres, vec![root], total_cost))
+}
+
+fn postgres_node(plan: &Value) -> PlanNode {
+    let text = |key: &str| plan.get(key).and_then(Value::as_str).map(str::to_string);
+    let operation = text("Node Type").unwrap_or_else(|| "Unknown".to_string());
+
+    let mut detail = operation.clone();
+    if let Some(relation) = text("Relation Name") {
+        detail.push_str(&format!(" on {}", relation));
+    }
+    if let Some(index) = text("Index Name") {
+        detail.push_str(&format!(" using {}", index));
+    }
+    for condition in ["Index Cond", "Hash Cond", "Merge Cond", "Join Filter", "Filter"] {
+        if let Some(expr) = text(condition) {
+            detail.push_str(&format!(" [{}: {}]", condition, expr));
+        }
+    }
+
+    let mut node = PlanNode::new(operation, detail);
+    node.full_scan = node.operation == "Seq Scan";
+    node.table = text("Relation Name");
+    node.index = text("Index Name");
+    node.estimated_rows = plan.get("Plan Rows").and_then(Value::as_f64);
+    node.estimated_cost = plan.get("Total Cost").and_then(Value::as_f64);
+    node.children = plan
+        .get("Plans")
+        .and_then(Value::as_array)
+        .map(|plans| plans.iter().map(postgres_node).collect())
+        .unwrap_or_default();
+    node
+}
+
+/// Tabular `EXPLAIN`: one row per table read, where access type `ALL` is a
+/// full scan. MySQL doesn't nest the rows, so the plan is flat.
+fn mysql_plan(result: &QueryResult) -> QueryPlan {
+    let position = |name: &str| result.columns.iter().position(|c| c.name.eq_ignore_ascii_case(name));
+    let (table, access, key, rows, extra) =
+        (position("table"), position("type"), position("key"), position("rows"), position("Extra"));
+
+    let nodes = result
+        .rows
+        .iter()
+        .map(|row| {
+            let text = |at: Option<usize>| {
+                at.and_then(|i| row.get(i))
+                    .and_then(|v| match v {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    })
+                    .filter(|s| !s.is_empty())
+            };
+            let operation = text(access).unwrap_or_else(|| "-".to_string());
+            let mut node = PlanNode::new(operation.clone(), String::new());
+            node.table = text(table);
+            node.index = text(key);
+            node.full_scan = operation == "ALL";
+            node.estimated_rows = text(rows).and_then(|r| r.parse().ok());
+
+            let mut detail = format!("{} on {}", operation, node.table.as_deref().unwrap_or("-"));
+            if let Some(index) = &node.index {
+                detail.push_str(&format!(" using {}", index));
+            }
+            if let Some(extra) = text(extra) {
+                detail.push_str(&format!(" [{}]", extra));
+            }
+            node.detail = detail;
+            node
+        })
+        .collect();
+    QueryPlan::new(SqlDialect::Mysql, nodes, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::sqlite::SqliteAdapter;
+    use crate::engine::adapter::DatabaseAdapter;
+    use serde_json::json;
+
+    #[test]
+    fn test_sqlite_plan_flags_full_scans() {
+        let db = SqliteAdapter::in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author_id INTEGER, title TEXT);
+             CREATE INDEX idx_posts_author ON posts(author_id);",
+        )
+        .unwrap();
+
+        let plan = db
+            .explain(
+                "SELECT * FROM users JOIN posts ON posts.author_id = users.id WHERE users.name = ?1",
+                &[SqlValue::Text("x".into())],
+            )
+            .unwrap();
+        assert_eq!(plan.full_scans, vec!["users".to_string()]);
+        let search = plan.nodes.iter().find(|n| n.operation == "SEARCH").unwrap();
+        assert_eq!(search.index.as_deref(), Some("idx_posts_author"));
+        assert!(!search.full_scan);
+
+        let plan = db.explain("SELECT * FROM users WHERE id = 1;", &[]).unwrap();
+        assert!(plan.full_scans.is_empty());
+        assert_eq!(plan.nodes[0].index.as_deref(), Some("PRIMARY KEY"));
+    }
+
+    #[test]
+    fn test_postgres_plan_tree() {
+        let document = json!([{ "Plan": {
+            "Node Type": "Hash Join", "Total Cost": 42.5, "Plan Rows": 10, "Hash Cond": "(p.author_id = u.id)",
+            "Plans": [
+                { "Node Type": "Seq Scan", "Relation Name": "posts", "Total Cost": 20.0, "Plan Rows": 1000 },
+                { "Node Type": "Hash", "Total Cost": 8.2, "Plans": [
+                    { "Node Type": "Index Scan", "Relation Name": "users", "Index Name": "users_pkey",
+                      "Index Cond": "(id < 5)", "Total Cost": 8.1, "Plan Rows": 4 }
+                ]}
+            ]
+        }}]);
+        let result = QueryResult {
+            columns: vec![],
+            rows: vec![vec![document]],
+            execution_time_ms: 0,
+        };
+
+        let plan = postgres_plan(&result).unwrap();
+        assert_eq!(plan.total_cost, Some(42.5));
+        assert_eq!(plan.full_scans, vec!["posts".to_string()]);
+        let index_scan = &plan.nodes[0].children[1].children[0];
+        assert_eq!(index_scan.index.as_deref(), Some("users_pkey"));
+        assert_eq!(index_scan.detail, "Index Scan on users using users_pkey [Index Cond: (id < 5)]");
+    }
+}
@@ -0,0 +1,385 @@
+//! Full-Text Search
+//!
+//! SQLite FTS5 indexes over chosen text columns of a table. Each index is an
+//! external-content FTS5 table named `<table>_fts` that reads row text from
+//! the source table by rowid, kept in sync by insert/update/delete triggers.
+//! Searches are ranked with bm25 and return a highlighted snippet per hit.
+//! Tables of attached databases (`alias.table`) get their index in the
+//! same database.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Instant;
+
+use super::dialect::split_table;
+use super::sqlite::{schema_and_name, sqlite_master};
+use super::{AdapterError, AdapterResult, ColumnMeta, DialectGenerator, QueryResult, SqlDialect, SqlValue, ROWID};
+
+/// Suffix of the FTS5 table that indexes a source table
+pub const FTS_SUFFIX: &str = "_fts";
+
+/// Excludes FTS5 tables and the shadow tables FTS5 creates for them from
+/// a listing of `schema`'s `sqlite_master` aliased as `m`
+pub fn hide_fts_tables(schema: &str) -> String {
+    format!(
+        "NOT EXISTS (
+    SELECT 1 FROM {} v
+    WHERE v.type = 'table' AND v.sql LIKE 'CREATE VIRTUAL TABLE%USING fts5%'
+      AND m.name IN (v.name, v.name || '_data', v.name || '_idx', v.name || '_docsize',
+                     v.name || '_config', v.name || '_content'))",
+        sqlite_master(schema)
+    )
+}
+
+/// Definition of a full-text index over some columns of a table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FtsIndex {
+    pub table: String,
+    pub columns: Vec<String>,
+    /// FTS5 tokenizer spec, e.g. `porter unicode61`; FTS5's default when unset
+    #[serde(default)]
+    pub tokenize: Option<String>,
+}
+
+impl FtsIndex {
+    /// Name of the FTS5 table backing this index
+    pub fn fts_table(&self) -> String {
+        format!("{}{}", self.table, FTS_SUFFIX)
+    }
+
+    fn trigger(&self, event: &str) -> String {
+        format!("{}_{}", self.fts_table(), event)
+    }
+
+    /// Whether `trigger` is one of the triggers keeping an index on
+    /// `table` in sync. They belong to the hidden FTS table, not the schema.
+    pub fn is_sync_trigger(table: &str, trigger: &str) -> bool {
+        trigger
+            .strip_prefix(table)
+            .and_then(|rest| rest.strip_prefix(FTS_SUFFIX))
+            .is_some_and(|event| matches!(event, "_ai" | "_ad" | "_au"))
+    }
+
+    /// DDL creating the FTS5 table and its sync triggers, and indexing the
+    /// rows already in the table
+    pub fn create_sql(&self) -> String {
+        let gen = DialectGenerator::new(SqlDialect::Sqlite);
+        // Triggers live in their table's database and name tables unqualified
+        let (_, name) = split_table(&self.table);
+        let table = gen.quote_ident(name);
+        let fts = gen.quote_ident(&format!("{}{}", name, FTS_SUFFIX));
+        let qualified_fts = gen.quote_table(&self.fts_table());
+        let quoted: Vec<String> = self.columns.iter().map(|c| gen.quote_ident(c)).collect();
+        let columns = quoted.join(", ");
+        let values = |prefix: &str| -> String {
+            quoted
+                .iter()
+                .map(|c| format!("{}.{}", prefix, c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let tokenize = self
+            .tokenize
+            .as_ref()
+            .map(|t| format!(", tokenize = '{}'", t.replace('\'', "''")))
+            .unwrap_or_default();
+
+        let mut sql = format!(
+            "CREATE VIRTUAL TABLE {qualified_fts} USING fts5({columns}, content = '{content}', content_rowid = 'rowid'{tokenize});\n",
+            content = name.replace('\'', "''"),
+        );
+        sql.push_str(&format!(
+            "CREATE TRIGGER {} AFTER INSERT ON {table} BEGIN\n  INSERT INTO {fts}(rowid, {columns}) VALUES (new.rowid, {});\nEND;\n",
+            gen.quote_table(&self.trigger("ai")),
+            values("new"),
+        ));
+        sql.push_str(&format!(
+            "CREATE TRIGGER {} AFTER DELETE ON {table} BEGIN\n  INSERT INTO {fts}({fts}, rowid, {columns}) VALUES ('delete', old.rowid, {});\nEND;\n",
+            gen.quote_table(&self.trigger("ad")),
+            values("old"),
+        ));
+        sql.push_str(&format!(
+            "CREATE TRIGGER {} AFTER UPDATE ON {table} BEGIN\n  INSERT INTO {fts}({fts}, rowid, {columns}) VALUES ('delete', old.rowid, {});\n  INSERT INTO {fts}(rowid, {columns}) VALUES (new.rowid, {});\nEND;\n",
+            gen.quote_table(&self.trigger("au")),
+            values("old"),
+            values("new"),
+        ));
+        sql.push_str(&format!("INSERT INTO {qualified_fts}({fts}) VALUES ('rebuild');\n"));
+        sql
+    }
+
+    /// DDL removing the index and its triggers
+    pub fn drop_sql(&self) -> String {
+        let gen = DialectGenerator::new(SqlDialect::Sqlite);
+        let mut sql = String::new();
+        for event in ["ai", "ad", "au"] {
+            sql.push_str(&format!("DROP TRIGGER IF EXISTS {};\n", gen.quote_table(&self.trigger(event))));
+        }
+        sql.push_str(&format!("DROP TABLE IF EXISTS {};\n", gen.quote_table(&self.fts_table())));
+        sql
+    }
+}
+
+/// A full-text search request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    /// Search terms. Every term must match, and the last one also matches
+    /// as a prefix so results update while typing.
+    pub query: String,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+    /// Pass `query` through as an FTS5 query expression (`AND`, `OR`,
+    /// `NEAR`, `col:term`, ...) instead of treating it as plain terms
+    #[serde(default)]
+    pub raw: bool,
+    /// Markers wrapped around matched terms in snippets
+    #[serde(default = "default_highlight")]
+    pub highlight: (String, String),
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+fn default_highlight() -> (String, String) {
+    ("<mark>".to_string(), "</mark>".to_string())
+}
+
+impl SearchQuery {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            limit: default_limit(),
+            offset: 0,
+            raw: false,
+            highlight: default_highlight(),
+        }
+    }
+
+    /// The FTS5 MATCH expression for this query
+    pub fn match_expression(&self) -> String {
+        if self.raw {
+            return self.query.clone();
+        }
+        let terms: Vec<&str> = self.query.split_whitespace().collect();
+        terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| {
+                let quoted = format!("\"{}\"", term.replace('"', "\"\""));
+                if i + 1 == terms.len() {
+                    format!("{}*", quoted)
+                } else {
+                    quoted
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// One matching row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub row: Map<String, Value>,
+    /// bm25 relevance; lower is a better match
+    pub rank: f64,
+    /// Excerpt of the best-matching column with matches highlighted
+    pub snippet: String,
+}
+
+/// Ranked search results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub hits: Vec<SearchHit>,
+    /// Matching rows across all pages
+    pub total_count: u64,
+    pub columns: Vec<ColumnMeta>,
+    /// Columns that address each row (see `DatabaseAdapter::row_key_columns`)
+    pub row_key: Vec<String>,
+    pub execution_time_ms: u64,
+}
+
+const RANK_COLUMN: &str = "_airdb_rank";
+const SNIPPET_COLUMN: &str = "_airdb_snippet";
+
+/// Look up the full-text index on `table`, running SQL through `run`
+pub fn find_index<F>(table: &str, mut run: F) -> AdapterResult<Option<FtsIndex>>
+where
+    F: FnMut(&str, &[SqlValue]) -> AdapterResult<QueryResult>,
+{
+    let (schema, name) = schema_and_name(table);
+    let fts_table = format!("{}{}", name, FTS_SUFFIX);
+    let found = run(
+        &format!("SELECT sql FROM {} WHERE type = 'table' AND name = ?1", sqlite_master(schema)),
+        &[SqlValue::Text(fts_table.clone())],
+    )?;
+    let Some(sql) = found.rows.first().and_then(|r| r.first()).and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    if !sql.to_lowercase().contains("using fts5") {
+        return Ok(None);
+    }
+
+    let columns = run(
+        "SELECT name FROM pragma_table_info(?1, ?2)",
+        &[SqlValue::Text(fts_table), SqlValue::Text(schema.to_string())],
+    )?
+        .rows
+        .iter()
+        .filter_map(|r| r.first().and_then(|v| v.as_str()).map(String::from))
+        .collect();
+    let tokenize = option_value(sql, "tokenize");
+
+    Ok(Some(FtsIndex {
+        table: table.to_string(),
+        columns,
+        tokenize,
+    }))
+}
+
+/// Run a ranked search over `table`'s index. `key_columns` are included in
+/// each hit so rows can be edited from the results.
+pub fn search<F>(table: &str, key_columns: &[String], query: &SearchQuery, mut run: F) -> AdapterResult<SearchResult>
+where
+    F: FnMut(&str, &[SqlValue]) -> AdapterResult<QueryResult>,
+{
+    let start = Instant::now();
+    let index = find_index(table, &mut run)?
+        .ok_or_else(|| AdapterError::NotFound(format!("Table {} has no full-text index", table)))?;
+
+    let expression = query.match_expression();
+    if expression.trim().is_empty() {
+        return Err(AdapterError::Validation("Search query is empty".to_string()));
+    }
+
+    let gen = DialectGenerator::new(SqlDialect::Sqlite);
+    let table_ident = gen.quote_table(table);
+    let fts_ident = gen.quote_table(&index.fts_table());
+    // The FTS table's hidden column of the same name, which MATCH and the
+    // ranking functions take
+    let fts = format!("f.{}", gen.quote_ident(&format!("{}{}", split_table(table).1, FTS_SUFFIX)));
+    let select_list = if key_columns == [ROWID] {
+        "t.rowid AS rowid, t.*"
+    } else {
+        "t.*"
+    };
+
+    let sql = format!(
+        "SELECT {select_list}, bm25({fts}) AS {RANK_COLUMN}, snippet({fts}, -1, ?2, ?3, '…', 16) AS {SNIPPET_COLUMN}
+         FROM {fts_ident} f JOIN {table_ident} t ON t.rowid = f.rowid
+         WHERE {fts} MATCH ?1
+         ORDER BY {RANK_COLUMN}
+         LIMIT ?4 OFFSET ?5"
+    );
+    let params = [
+        SqlValue::Text(expression.clone()),
+        SqlValue::Text(query.highlight.0.clone()),
+        SqlValue::Text(query.highlight.1.clone()),
+        SqlValue::Integer(query.limit as i64),
+        SqlValue::Integer(query.offset as i64),
+    ];
+    let result = run(&sql, &params).map_err(invalid_query)?;
+
+    let mut hits = Vec::with_capacity(result.rows.len());
+    for values in result.rows {
+        let mut row: Map<String, Value> =
+            result.columns.iter().map(|c| c.name.clone()).zip(values).collect();
+        let rank = row.remove(RANK_COLUMN).and_then(|v| v.as_f64()).unwrap_or_default();
+        let snippet = match row.remove(SNIPPET_COLUMN) {
+            Some(Value::String(s)) => s,
+            _ => String::new(),
+        };
+        hits.push(SearchHit { row, rank, snippet });
+    }
+    let columns = result
+        .columns
+        .into_iter()
+        .filter(|c| c.name != RANK_COLUMN && c.name != SNIPPET_COLUMN)
+        .collect();
+
+    let count = run(
+        &format!("SELECT COUNT(*) FROM {fts_ident} f WHERE {fts} MATCH ?1"),
+        &[SqlValue::Text(expression)],
+    )
+    .map_err(invalid_query)?;
+    let total_count = count
+        .rows
+        .first()
+        .and_then(|r| r.first())
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    Ok(SearchResult {
+        hits,
+        total_count,
+        columns,
+        row_key: key_columns.to_vec(),
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// A malformed MATCH expression is the caller's mistake, not a database failure
+fn invalid_query(error: AdapterError) -> AdapterError {
+    match error {
+        AdapterError::Query(msg) if msg.contains("fts5") => {
+            AdapterError::Validation(format!("Invalid search query: {}", msg))
+        }
+        other => other,
+    }
+}
+
+/// Read a quoted `name = '...'` option out of a `CREATE VIRTUAL TABLE` statement
+fn option_value(sql: &str, name: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets valid in `sql`
+    let lower = sql.to_ascii_lowercase();
+    lower.match_indices(&name.to_ascii_lowercase()).find_map(|(at, _)| {
+        let rest = sql[at + name.len()..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+        // A doubled quote stands for one quote character
+        let mut value = String::new();
+        let mut chars = rest[1..].chars().peekable();
+        loop {
+            match chars.next()? {
+                c if c == quote && chars.peek() == Some(&quote) => {
+                    chars.next();
+                    value.push(quote);
+                }
+                c if c == quote => return Some(value),
+                c => value.push(c),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_expression() {
+        let mut query = SearchQuery::new("quick  \"brown fox");
+        assert_eq!(query.match_expression(), "\"quick\" \"\"\"brown\" \"fox\"*");
+        query.raw = true;
+        assert_eq!(query.match_expression(), "quick  \"brown fox");
+    }
+
+    #[test]
+    fn test_option_value() {
+        let sql = "CREATE VIRTUAL TABLE \"notes_fts\" USING fts5(\"body\", content = 'notes', tokenize = 'porter unicode61')";
+        assert_eq!(option_value(sql, "tokenize").as_deref(), Some("porter unicode61"));
+        assert_eq!(option_value(sql, "content").as_deref(), Some("notes"));
+        assert_eq!(option_value(sql, "prefix"), None);
+
+        let sql = "CREATE VIRTUAL TABLE \"İtems_fts\" USING fts5(\"body\", content = 'O''Brien', TOKENIZE = 'trigram')";
+        assert_eq!(option_value(sql, "content").as_deref(), Some("O'Brien"));
+        assert_eq!(option_value(sql, "tokenize").as_deref(), Some("trigram"));
+        assert_eq!(option_value("tokenize = 'unclosed", "tokenize"), None);
+    }
+}
@@ -0,0 +1,533 @@
+//! Statement Guard
+//!
+//! Classifies raw SQL before it runs, so read-only connections can refuse
+//! anything but reads and destructive statements can be held back for
+//! confirmation. Scripts are split into statements with quotes, comments,
+//! trigger bodies and Postgres dollar-quoted bodies respected.
+//!
+//! Classification is by keyword, not a full parse: a statement is
+//! destructive when it drops or truncates something, or is an `UPDATE` or
+//! `DELETE` without a top-level `WHERE`.
+
+use serde::{Deserialize, Serialize};
+
+use super::{cancel, AdapterError, AdapterResult, DatabaseAdapter, QueryOptions, SqlDialect};
+
+/// What a statement does, in increasing order of risk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementKind {
+    /// Returns rows without changing anything
+    Read,
+    /// Changes rows or session state
+    Write,
+    /// Changes the schema without losing data
+    Ddl,
+    /// Drops or truncates objects, or changes every row of a table
+    Destructive,
+}
+
+impl std::fmt::Display for StatementKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatementKind::Read => write!(f, "read"),
+            StatementKind::Write => write!(f, "write"),
+            StatementKind::Ddl => write!(f, "DDL"),
+            StatementKind::Destructive => write!(f, "destructive"),
+        }
+    }
+}
+
+/// One classified statement of a script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    pub sql: String,
+    pub kind: StatementKind,
+    /// Why the statement is destructive
+    pub reason: Option<String>,
+    /// Table whose rows the statement drops, deletes or rewrites
+    pub table: Option<String>,
+}
+
+/// Per-connection limits on raw SQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlGuard {
+    /// Only allow reads; the connection itself is also opened read-only
+    #[serde(default)]
+    pub read_only: bool,
+    /// Hold destructive statements back until they are confirmed
+    #[serde(default = "default_confirm_destructive")]
+    pub confirm_destructive: bool,
+}
+
+fn default_confirm_destructive() -> bool {
+    true
+}
+
+impl Default for SqlGuard {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            confirm_destructive: true,
+        }
+    }
+}
+
+impl SqlGuard {
+    /// Reject statements a read-only connection may not run
+    pub fn check(&self, statements: &[Statement]) -> AdapterResult<()> {
+        if !self.read_only {
+            return Ok(());
+        }
+        match statements.iter().find(|s| s.kind != StatementKind::Read) {
+            Some(statement) => Err(AdapterError::Validation(format!(
+                "The connection is read-only, so {} statements are not allowed: {}",
+                statement.kind,
+                first_line(&statement.sql)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the statements must be confirmed before they run
+    pub fn needs_confirmation(&self, statements: &[Statement]) -> bool {
+        self.confirm_destructive && kind_of(statements) == StatementKind::Destructive
+    }
+}
+
+/// The riskiest kind among the statements
+pub fn kind_of(statements: &[Statement]) -> StatementKind {
+    statements.iter().map(|s| s.kind).max().unwrap_or(StatementKind::Read)
+}
+
+/// Split a script into statements and classify each
+pub fn classify(sql: &str) -> Vec<Statement> {
+    split_statements(sql)
+        .into_iter()
+        .map(|sql| {
+            let tokens = tokenize(&sql);
+            let (kind, reason, table) = classify_tokens(&tokens);
+            Statement { sql, kind, reason, table }
+        })
+        .collect()
+}
+
+/// Outcome of running statements
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunResult {
+    pub affected_rows: u64,
+    /// Rows held by tables that are dropped or truncated, counted beforehand
+    pub rows_at_risk: u64,
+    /// Whether the changes were rolled back instead of committed
+    pub dry_run: bool,
+}
+
+/// Run statements in one transaction. With `dry_run` set the transaction
+/// is rolled back, so the result shows what the statements would change.
+/// `options` bound the statements as a whole; an aborted run is rolled back.
+pub fn run(
+    adapter: &dyn DatabaseAdapter,
+    statements: &[Statement],
+    dry_run: bool,
+    options: &QueryOptions,
+) -> AdapterResult<RunResult> {
+    if dry_run && adapter.dialect() == SqlDialect::Mysql && kind_of(statements) >= StatementKind::Ddl {
+        return Err(AdapterError::Validation(
+            "MySQL commits schema changes immediately, so they can't be dry-run".to_string(),
+        ));
+    }
+
+    let mut result = RunResult {
+        dry_run,
+        ..Default::default()
+    };
+    let tables = adapter.get_tables()?;
+    let mut counted = Vec::new();
+    for statement in statements.iter().filter(|s| s.kind == StatementKind::Destructive) {
+        if let Some(table) = statement.table.as_ref().filter(|t| tables.contains(t) && !counted.contains(t)) {
+            result.rows_at_risk += adapter.get_row_count(table)?;
+            counted.push(table);
+        }
+    }
+
+    let mut tx = adapter.begin()?;
+    let interrupt = tx.interrupt_handle();
+    result.affected_rows = cancel::run(options, interrupt, || {
+        let mut affected_rows = 0;
+        for statement in statements {
+            let affected = tx.execute(&statement.sql, &[])?.affected_rows;
+            // Some drivers repeat the previous count for statements that change no rows
+            if tokenize(&statement.sql).first().is_some_and(|t| is_dml_keyword(t) || t.is("WITH")) {
+                affected_rows += affected;
+            }
+        }
+        Ok(affected_rows)
+    })?;
+    if dry_run {
+        tx.rollback()?;
+    } else {
+        tx.commit()?;
+    }
+    Ok(result)
+}
+
+fn is_dml_keyword(token: &Token) -> bool {
+    ["INSERT", "UPDATE", "DELETE", "MERGE", "REPLACE"].iter().any(|k| token.is(k))
+}
+
+fn first_line(sql: &str) -> &str {
+    sql.lines().next().unwrap_or_default()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// Keyword or bare identifier
+    Word(String),
+    /// Quoted identifier, unquoted
+    Quoted(String),
+    Symbol(char),
+}
+
+impl Token {
+    fn is(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            Token::Word(w) | Token::Quoted(w) => Some(w),
+            Token::Symbol(_) => None,
+        }
+    }
+}
+
+fn classify_tokens(tokens: &[Token]) -> (StatementKind, Option<String>, Option<String>) {
+    let first = tokens.first().and_then(Token::name).unwrap_or_default().to_ascii_uppercase();
+    let mut depth = 0i32;
+    // Words outside parentheses, where subqueries can't hide a WHERE
+    let top_level: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| {
+            match t {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })
+        .collect();
+    let has = |keyword: &str| top_level.iter().any(|t| t.is(keyword));
+    // The qualified name following `tokens[at]`, skipping `TABLE`, `ONLY`, `IF EXISTS`...
+    let name_after = |at: usize| -> Option<String> {
+        let mut rest = tokens[at + 1..].iter().skip_while(|t| {
+            ["TABLE", "ONLY", "IF", "EXISTS", "FROM", "INTO"]
+                .iter()
+                .any(|k| t.is(k))
+        });
+        let mut name = rest.next()?.name()?.to_string();
+        // Keep the last part of `schema.table`
+        while let (Some(Token::Symbol('.')), Some(part)) = (rest.next(), rest.next()) {
+            name = part.name()?.to_string();
+        }
+        Some(name)
+    };
+    let position = |keyword: &str| tokens.iter().position(|t| t.is(keyword));
+
+    match first.as_str() {
+        "SELECT" | "VALUES" | "SHOW" | "DESCRIBE" | "DESC" | "TABLE" => (StatementKind::Read, None, None),
+        "EXPLAIN" => {
+            // EXPLAIN ANALYZE runs the statement
+            if tokens.get(1).is_some_and(|t| t.is("ANALYZE")) {
+                classify_tokens(&tokens[2..])
+            } else {
+                (StatementKind::Read, None, None)
+            }
+        }
+        "PRAGMA" if is_pragma_read(tokens) => (StatementKind::Read, None, None),
+        // Data-modifying CTEs and the main statement are classified from
+        // their own keyword; a closing parenthesis ends their top level
+        "WITH" => tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| is_dml_keyword(t))
+            .map(|(at, _)| classify_tokens(&tokens[at..]))
+            .max_by_key(|(kind, _, _)| *kind)
+            .unwrap_or((StatementKind::Read, None, None)),
+        "UPDATE" | "DELETE" => {
+            let table = if first == "UPDATE" { name_after(0) } else { position("FROM").and_then(name_after) };
+            if has("WHERE") {
+                (StatementKind::Write, None, table)
+            } else {
+                let reason = format!("{} without WHERE changes every row", first);
+                (StatementKind::Destructive, Some(reason), table)
+            }
+        }
+        "DROP" => {
+            let object = tokens.get(1).and_then(Token::name).unwrap_or_default().to_ascii_uppercase();
+            let table = (object == "TABLE").then(|| name_after(1)).flatten();
+            (StatementKind::Destructive, Some(format!("DROP {} removes it for good", object)), table)
+        }
+        "TRUNCATE" => (
+            StatementKind::Destructive,
+            Some("TRUNCATE deletes every row".to_string()),
+            name_after(0),
+        ),
+        "ALTER" => {
+            if has("DROP") {
+                (StatementKind::Destructive, Some("ALTER ... DROP removes part of the schema".to_string()), None)
+            } else {
+                (StatementKind::Ddl, None, None)
+            }
+        }
+        "CREATE" | "COMMENT" | "RENAME" | "GRANT" | "REVOKE" | "ATTACH" | "DETACH" => (StatementKind::Ddl, None, None),
+        // INSERT, REPLACE, MERGE, COPY, transaction control, SET, VACUUM...
+        // and anything unrecognised
+        _ => (StatementKind::Write, None, None),
+    }
+}
+
+/// Pragmas that take an argument only to say what to report on
+const PRAGMA_QUERIES: &[&str] = &[
+    "TABLE_INFO",
+    "TABLE_XINFO",
+    "TABLE_LIST",
+    "INDEX_INFO",
+    "INDEX_XINFO",
+    "INDEX_LIST",
+    "FOREIGN_KEY_LIST",
+    "FOREIGN_KEY_CHECK",
+    "INTEGRITY_CHECK",
+    "QUICK_CHECK",
+];
+
+/// Whether a PRAGMA only reads: `PRAGMA name` queries the setting, while
+/// `PRAGMA name = value` and `PRAGMA name(value)` both change it, except
+/// for the pragmas whose argument names what to report on
+fn is_pragma_read(tokens: &[Token]) -> bool {
+    if tokens.contains(&Token::Symbol('=')) {
+        return false;
+    }
+    match tokens.iter().position(|t| *t == Token::Symbol('(')) {
+        None => true,
+        // `PRAGMA [schema.]name(...)`
+        Some(at) => tokens[..at]
+            .last()
+            .and_then(Token::name)
+            .is_some_and(|name| PRAGMA_QUERIES.contains(&name.to_ascii_uppercase().as_str())),
+    }
+}
+
+/// Split a script on top-level semicolons
+fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.char_indices().peekable();
+    // Inside `CREATE TRIGGER ... BEGIN ... END` semicolons end body statements
+    let mut block_depth = 0usize;
+    let mut word = String::new();
+
+    let finish = |current: &mut String, statements: &mut Vec<String>| {
+        let statement = current.trim();
+        if !statement.is_empty() && !tokenize(statement).is_empty() {
+            statements.push(statement.to_string());
+        }
+        current.clear();
+    };
+
+    while let Some((at, c)) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+        } else if !word.is_empty() {
+            let upper = word.to_ascii_uppercase();
+            let head = current.trim_start().to_ascii_uppercase();
+            if (upper == "BEGIN" && head.starts_with("CREATE") && head.contains("TRIGGER"))
+                || (upper == "CASE" && block_depth > 0)
+            {
+                block_depth += 1;
+            } else if upper == "END" && block_depth > 0 {
+                block_depth -= 1;
+            }
+            word.clear();
+        }
+
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                while let Some((_, next)) = chars.next() {
+                    current.push(next);
+                    if next == c {
+                        // A doubled quote is an escaped quote
+                        if chars.peek().map(|(_, n)| *n) == Some(c) {
+                            current.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if sql[at..].starts_with("--") => {
+                current.push(c);
+                for (_, next) in chars.by_ref() {
+                    current.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if sql[at..].starts_with("/*") => {
+                let end = sql[at + 2..].find("*/").map(|e| at + 2 + e + 2).unwrap_or(sql.len());
+                current.push_str(&sql[at..end]);
+                while chars.peek().is_some_and(|(i, _)| *i < end) {
+                    chars.next();
+                }
+            }
+            '$' => match dollar_tag(&sql[at..]) {
+                Some(tag) => {
+                    let body_start = at + tag.len();
+                    let end = sql[body_start..]
+                        .find(tag)
+                        .map(|e| body_start + e + tag.len())
+                        .unwrap_or(sql.len());
+                    current.push_str(&sql[at..end]);
+                    while chars.peek().is_some_and(|(i, _)| *i < end) {
+                        chars.next();
+                    }
+                }
+                None => current.push(c),
+            },
+            ';' if block_depth == 0 => finish(&mut current, &mut statements),
+            _ => current.push(c),
+        }
+    }
+    finish(&mut current, &mut statements);
+    statements
+}
+
+/// A Postgres dollar-quote opener such as `$$` or `$body$` at the start of `s`
+fn dollar_tag(s: &str) -> Option<&str> {
+    let end = s[1..].find('$')? + 2;
+    let tag = &s[..end];
+    tag[1..end - 1]
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_')
+        .then_some(tag)
+        .filter(|t| !t[1..t.len() - 1].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Words, quoted identifiers and symbols of one statement, without
+/// comments or string literals
+fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if sql[at..].starts_with("--") => {
+                while chars.next_if(|(_, n)| *n != '\n').is_some() {}
+            }
+            '/' if sql[at..].starts_with("/*") => {
+                let end = sql[at + 2..].find("*/").map(|e| at + 2 + e + 2).unwrap_or(sql.len());
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+            }
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut text = String::new();
+                while let Some((_, next)) = chars.next() {
+                    if next == close {
+                        if close != ']' && chars.peek().map(|(_, n)| *n) == Some(close) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    text.push(next);
+                }
+                // String literals carry no structure; quoted identifiers may name a table
+                if c != '\'' {
+                    tokens.push(Token::Quoted(text));
+                }
+            }
+            '$' if dollar_tag(&sql[at..]).is_some() => {
+                let tag = dollar_tag(&sql[at..]).unwrap_or_default();
+                let body_start = at + tag.len();
+                let end = sql[body_start..]
+                    .find(tag)
+                    .map(|e| body_start + e + tag.len())
+                    .unwrap_or(sql.len());
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.next_if(|(_, n)| n.is_alphanumeric() || *n == '_' || *n == '$') {
+                    word.push(next);
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => tokens.push(Token::Symbol(c)),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::sqlite::SqliteAdapter;
+
+    fn kinds(sql: &str) -> Vec<StatementKind> {
+        classify(sql).iter().map(|s| s.kind).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        use StatementKind::*;
+        assert_eq!(kinds("SELECT 1; -- trailing ; comment"), vec![Read]);
+        assert_eq!(kinds("WITH x AS (SELECT 1) SELECT * FROM x"), vec![Read]);
+        assert_eq!(kinds("PRAGMA table_info(users); PRAGMA query_only = OFF"), vec![Read, Write]);
+        assert_eq!(kinds("PRAGMA query_only(0); PRAGMA main.journal_mode(DELETE)"), vec![Write, Write]);
+        assert_eq!(kinds("PRAGMA main.index_list(\"users\"); PRAGMA journal_mode"), vec![Read, Read]);
+        assert_eq!(kinds("INSERT INTO t VALUES ('a;b')"), vec![Write]);
+        assert_eq!(kinds("UPDATE t SET a = 1 WHERE id = 2"), vec![Write]);
+        assert_eq!(kinds("UPDATE t SET a = (SELECT b FROM u WHERE u.id = 1)"), vec![Destructive]);
+        assert_eq!(kinds("delete from \"Users\""), vec![Destructive]);
+        assert_eq!(kinds("CREATE TABLE t (id INT); ALTER TABLE t ADD COLUMN a INT"), vec![Ddl, Ddl]);
+        assert_eq!(kinds("ALTER TABLE t DROP COLUMN a"), vec![Destructive]);
+        assert_eq!(kinds("DROP TABLE IF EXISTS public.t; TRUNCATE t"), vec![Destructive, Destructive]);
+        assert_eq!(kinds("WITH gone AS (DELETE FROM t RETURNING *) SELECT count(*) FROM gone"), vec![Destructive]);
+        assert_eq!(kinds("EXPLAIN ANALYZE DELETE FROM t"), vec![Destructive]);
+
+        let trigger = "CREATE TRIGGER touch AFTER UPDATE ON t BEGIN UPDATE t SET at = 1; END; SELECT 1";
+        assert_eq!(kinds(trigger), vec![Ddl, Read]);
+        let function = "CREATE FUNCTION f() RETURNS int AS $$ BEGIN DELETE FROM t; RETURN 1; END $$ LANGUAGE plpgsql";
+        assert_eq!(kinds(function), vec![Ddl]);
+
+        let drop = &classify("DROP TABLE IF EXISTS public.\"Users\"")[0];
+        assert_eq!(drop.table.as_deref(), Some("Users"));
+        assert_eq!(classify("DELETE FROM orders")[0].table.as_deref(), Some("orders"));
+    }
+
+    #[test]
+    fn test_guard_and_dry_run() {
+        let db = SqliteAdapter::in_memory().unwrap();
+        db.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, a TEXT); INSERT INTO t (a) VALUES ('x'), ('y');")
+            .unwrap();
+
+        let statements = classify("DELETE FROM t; DROP TABLE t");
+        let read_only = SqlGuard { read_only: true, ..Default::default() };
+        assert!(read_only.check(&statements).is_err());
+        assert!(read_only.check(&classify("SELECT * FROM t")).is_ok());
+        assert!(SqlGuard::default().needs_confirmation(&statements));
+        assert!(!SqlGuard::default().needs_confirmation(&classify("DELETE FROM t WHERE id = 1")));
+
+        let result = run(&db, &statements, true, &QueryOptions::default()).unwrap();
+        assert_eq!((result.affected_rows, result.rows_at_risk), (2, 2));
+        assert_eq!(db.get_row_count("t").unwrap(), 2);
+
+        run(&db, &classify("DELETE FROM t WHERE id = 1"), false, &QueryOptions::default()).unwrap();
+        assert_eq!(db.get_row_count("t").unwrap(), 1);
+    }
+}
@@ -0,0 +1,344 @@
+//! DDL Introspection Helpers
+//!
+//! Reads schema details back out of `CREATE` statements for objects that
+//! have no catalog of their own, chiefly SQLite's CHECK constraints and
+//! generated columns, which only exist in the stored table SQL. Trigger
+//! and view statements from any backend can be picked apart the same way.
+
+use super::{CheckConstraint, GeneratedColumn};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Word,
+    /// `"ident"`, `` `ident` `` or `[ident]`
+    Quoted,
+    /// `'string literal'`
+    Str,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: Kind,
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl Token<'_> {
+    fn is_word(&self, keyword: &str) -> bool {
+        self.kind == Kind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        self.kind == Kind::Punct && self.text == punct
+    }
+
+    /// The identifier this token names, with quoting removed
+    fn ident(&self) -> String {
+        match self.kind {
+            Kind::Quoted => {
+                let inner = &self.text[1..self.text.len() - 1];
+                match self.text.as_bytes()[0] {
+                    b'"' => inner.replace("\"\"", "\""),
+                    b'`' => inner.replace("``", "`"),
+                    _ => inner.to_string(),
+                }
+            }
+            _ => self.text.to_string(),
+        }
+    }
+}
+
+/// Split SQL into tokens, dropping whitespace and comments
+fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let kind = match c {
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            b'\'' | b'"' | b'`' | b'[' => {
+                let close = if c == b'[' { b']' } else { c };
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == close {
+                        // A doubled quote is an escaped quote, not the end
+                        if close != b']' && bytes.get(i + 1) == Some(&close) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                if c == b'\'' {
+                    Kind::Str
+                } else {
+                    Kind::Quoted
+                }
+            }
+            _ if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80 => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$' || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                Kind::Word
+            }
+            _ => {
+                i += 1;
+                Kind::Punct
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &sql[start..i],
+            start,
+            end: i,
+        });
+    }
+
+    tokens
+}
+
+/// Index of the `)` closing the `(` at `open`
+fn matching_paren(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_punct("(") {
+            depth += 1;
+        } else if token.is_punct(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Index just past an optional `IF NOT EXISTS` and a possibly
+/// schema-qualified object name starting at `at`
+fn skip_object_name(tokens: &[Token], mut at: usize) -> usize {
+    if tokens.get(at).is_some_and(|t| t.is_word("IF")) {
+        at += 3;
+    }
+    at += 1;
+    while tokens.get(at).is_some_and(|t| t.is_punct(".")) {
+        at += 2;
+    }
+    at
+}
+
+/// The column and constraint definitions inside a `CREATE TABLE` body
+fn table_definitions<'a>(tokens: &'a [Token<'a>]) -> Vec<&'a [Token<'a>]> {
+    let Some(open) = tokens.iter().position(|t| t.is_punct("(")) else {
+        return Vec::new();
+    };
+    let Some(close) = matching_paren(tokens, open) else {
+        return Vec::new();
+    };
+
+    let mut definitions = Vec::new();
+    let mut depth = 0;
+    let mut begin = open + 1;
+    for i in open + 1..close {
+        let token = &tokens[i];
+        if token.is_punct("(") {
+            depth += 1;
+        } else if token.is_punct(")") {
+            depth -= 1;
+        } else if depth == 0 && token.is_punct(",") {
+            definitions.push(&tokens[begin..i]);
+            begin = i + 1;
+        }
+    }
+    definitions.push(&tokens[begin..close]);
+    definitions.retain(|d| !d.is_empty());
+    definitions
+}
+
+/// Every CHECK constraint in a `CREATE TABLE` statement, whether it was
+/// declared on a column or on the table
+pub fn check_constraints(create_sql: &str) -> Vec<CheckConstraint> {
+    let tokens = tokenize(create_sql);
+    let mut checks = Vec::new();
+
+    for i in 0..tokens.len() {
+        if !tokens[i].is_word("CHECK") || !tokens.get(i + 1).is_some_and(|t| t.is_punct("(")) {
+            continue;
+        }
+        let Some(close) = matching_paren(&tokens, i + 1) else {
+            continue;
+        };
+        let name = match i.checked_sub(2) {
+            Some(at) if tokens[at].is_word("CONSTRAINT") => Some(tokens[at + 1].ident()),
+            _ => None,
+        };
+        checks.push(CheckConstraint {
+            name,
+            expression: create_sql[tokens[i + 1].end..tokens[close].start].trim().to_string(),
+        });
+    }
+
+    checks
+}
+
+/// Generated columns declared in a `CREATE TABLE` statement, by column name
+pub fn generated_columns(create_sql: &str) -> Vec<(String, GeneratedColumn)> {
+    const CONSTRAINT_KEYWORDS: [&str; 5] = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"];
+
+    let tokens = tokenize(create_sql);
+    let mut generated = Vec::new();
+
+    for definition in table_definitions(&tokens) {
+        if CONSTRAINT_KEYWORDS.iter().any(|k| definition[0].is_word(k)) {
+            continue;
+        }
+        let mut depth = 0;
+        let mut found = None;
+        for (i, token) in definition.iter().enumerate() {
+            if token.is_punct("(") {
+                depth += 1;
+            } else if token.is_punct(")") {
+                depth -= 1;
+            } else if depth == 0 && token.is_word("AS") && definition.get(i + 1).is_some_and(|t| t.is_punct("(")) {
+                found = matching_paren(definition, i + 1).map(|close| (i + 1, close));
+                break;
+            }
+        }
+        let Some((open, close)) = found else {
+            continue;
+        };
+        let stored = definition[close + 1..].iter().any(|t| t.is_word("STORED"));
+        generated.push((
+            definition[0].ident(),
+            GeneratedColumn {
+                expression: create_sql[definition[open].end..definition[close].start].trim().to_string(),
+                stored,
+            },
+        ));
+    }
+
+    generated
+}
+
+/// Timing and firing events from a `CREATE TRIGGER` statement. SQLite
+/// triggers declared without a timing fire `BEFORE`.
+pub fn trigger_header(create_sql: &str) -> (String, Vec<String>) {
+    const EVENTS: [&str; 4] = ["INSERT", "UPDATE", "DELETE", "TRUNCATE"];
+
+    let tokens = tokenize(create_sql);
+    let mut timing = "BEFORE".to_string();
+    let mut events = Vec::new();
+
+    let Some(keyword) = tokens.iter().position(|t| t.is_word("TRIGGER")) else {
+        return (timing, events);
+    };
+    for token in &tokens[skip_object_name(&tokens, keyword + 1)..] {
+        if token.is_word("ON") {
+            break;
+        }
+        if token.is_word("BEFORE") || token.is_word("AFTER") {
+            timing = token.text.to_uppercase();
+        } else if token.is_word("INSTEAD") {
+            timing = "INSTEAD OF".to_string();
+        } else if let Some(event) = EVENTS.iter().find(|e| token.is_word(e)) {
+            events.push(event.to_string());
+        }
+    }
+
+    (timing, events)
+}
+
+/// The SELECT statement of a `CREATE VIEW` statement
+pub fn view_body(create_sql: &str) -> String {
+    let tokens = tokenize(create_sql);
+    let body = tokens.iter().position(|t| t.is_word("VIEW")).and_then(|keyword| {
+        let mut at = skip_object_name(&tokens, keyword + 1);
+        if tokens.get(at).is_some_and(|t| t.is_punct("(")) {
+            at = matching_paren(&tokens, at)? + 1;
+        }
+        tokens.get(at).filter(|t| t.is_word("AS")).map(|t| &create_sql[t.end..])
+    });
+    body.unwrap_or(create_sql).trim().trim_end_matches(';').trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = r#"CREATE TABLE "order items" (
+        id INTEGER PRIMARY KEY,
+        qty INTEGER NOT NULL CHECK (qty > 0),
+        price REAL DEFAULT (CAST('0' AS REAL)), -- unit price, (not "checked")
+        total REAL GENERATED ALWAYS AS (qty * price) STORED,
+        label TEXT AS (upper(name || ',')),
+        name TEXT,
+        CONSTRAINT "price positive" CHECK (price >= 0 AND (price < 1e6))
+    )"#;
+
+    #[test]
+    fn test_check_constraints() {
+        assert_eq!(
+            check_constraints(TABLE),
+            vec![
+                CheckConstraint {
+                    name: None,
+                    expression: "qty > 0".into(),
+                },
+                CheckConstraint {
+                    name: Some("price positive".into()),
+                    expression: "price >= 0 AND (price < 1e6)".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generated_columns() {
+        let generated = generated_columns(TABLE);
+        assert_eq!(generated.len(), 2);
+        assert_eq!(generated[0].0, "total");
+        assert_eq!(generated[0].1.expression, "qty * price");
+        assert!(generated[0].1.stored);
+        assert_eq!(generated[1].0, "label");
+        assert_eq!(generated[1].1.expression, "upper(name || ',')");
+        assert!(!generated[1].1.stored);
+    }
+
+    #[test]
+    fn test_trigger_header_and_view_body() {
+        let (timing, events) = trigger_header(
+            "CREATE TRIGGER IF NOT EXISTS \"after\" AFTER UPDATE OF qty, price ON items BEGIN SELECT 1; END",
+        );
+        assert_eq!((timing.as_str(), events), ("AFTER", vec!["UPDATE".to_string()]));
+        let (timing, events) =
+            trigger_header("CREATE TRIGGER audit BEFORE INSERT OR DELETE ON public.items FOR EACH ROW EXECUTE FUNCTION log()");
+        assert_eq!((timing.as_str(), events.len()), ("BEFORE", 2));
+        assert_eq!(trigger_header("CREATE TRIGGER t INSTEAD OF INSERT ON v BEGIN END").0, "INSTEAD OF");
+
+        assert_eq!(
+            view_body("CREATE VIEW IF NOT EXISTS main.big (n, c) AS\n  SELECT name, COUNT(*) AS c FROM items;"),
+            "SELECT name, COUNT(*) AS c FROM items"
+        );
+    }
+}
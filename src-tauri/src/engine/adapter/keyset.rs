@@ -0,0 +1,323 @@
+//! Keyset Pagination
+//!
+//! Pages through a table by seeking past the last row seen instead of using
+//! OFFSET, so deep pages cost the same as the first. Rows are ordered by the
+//! optional sort column followed by the row key columns, which makes the order
+//! total. Cursors are opaque tokens that carry the boundary row's values.
+//!
+//! NULLs in the sort column sort after all other values ascending (and before
+//! them descending) on every backend, via an explicit `CASE ... IS NULL` term.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    AdapterError, AdapterResult, ColumnMeta, DialectGenerator, FilterExpr, QueryResult,
+    SortDirection, SortSpec, SqlDialect, SqlValue, ROWID,
+};
+
+/// How `DataPage.total_count` is computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    /// Run `COUNT(*)` over the filtered rows
+    #[default]
+    Exact,
+    /// Use planner or table statistics when the backend has them
+    Estimate,
+    /// Don't count at all
+    Skip,
+}
+
+/// One page request in keyset mode
+pub struct KeysetQuery<'a> {
+    pub table: &'a str,
+    pub limit: usize,
+    pub sort: Option<&'a SortSpec>,
+    pub filter: Option<&'a FilterExpr>,
+    pub cursor: Option<&'a str>,
+    /// Columns that uniquely identify a row (see `DatabaseAdapter::row_key_columns`)
+    pub key_columns: &'a [String],
+}
+
+/// Rows of one keyset page plus cursors to its neighbours
+pub struct KeysetPage {
+    pub rows: Vec<Map<String, Value>>,
+    pub columns: Vec<ColumnMeta>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+/// Decoded cursor. The sort column and direction are recorded so a cursor
+/// can't silently be applied to a differently ordered listing.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    #[serde(rename = "s")]
+    sort: Option<String>,
+    #[serde(rename = "d")]
+    desc: bool,
+    /// Page backwards from the boundary row instead of forwards
+    #[serde(rename = "b")]
+    before: bool,
+    /// Boundary row values: the sort column (if any), then each key column
+    #[serde(rename = "v")]
+    values: Vec<Value>,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(token: &str) -> AdapterResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| AdapterError::Validation("Invalid pagination cursor".to_string()))
+    }
+}
+
+/// An ORDER BY term and the boundary value it is compared against
+struct Term {
+    expr: String,
+    desc: bool,
+    value: Option<SqlValue>,
+}
+
+/// Fetch one page, running SQL through `run`
+pub fn fetch_page<F>(gen: &DialectGenerator, query: &KeysetQuery, mut run: F) -> AdapterResult<KeysetPage>
+where
+    F: FnMut(&str, &[SqlValue]) -> AdapterResult<QueryResult>,
+{
+    if query.key_columns.is_empty() {
+        return Err(AdapterError::Validation(format!(
+            "Table {} has no row key to paginate by",
+            query.table
+        )));
+    }
+
+    let sort_column = query.sort.map(|s| s.column.clone());
+    let desc = matches!(query.sort, Some(SortSpec { direction: SortDirection::Desc, .. }));
+
+    let cursor = query.cursor.map(Cursor::decode).transpose()?;
+    if let Some(c) = &cursor {
+        let expected = query.key_columns.len() + usize::from(sort_column.is_some());
+        if c.sort != sort_column || c.desc != desc || c.values.len() != expected {
+            return Err(AdapterError::Validation(
+                "Cursor does not match the requested sort".to_string(),
+            ));
+        }
+    }
+    let before = cursor.as_ref().is_some_and(|c| c.before);
+
+    // Paging backwards walks the reversed order, then flips the rows back
+    let terms = order_terms(gen, sort_column.as_deref(), query.key_columns, desc, cursor.as_ref());
+    let walk_desc = |term_desc: bool| term_desc != before;
+
+    let mut params = Vec::new();
+    let mut conditions = Vec::new();
+    if let Some(filter) = query.filter {
+        conditions.push(format!("({})", gen.filter_sql(filter, &mut params)?));
+    }
+    if cursor.is_some() {
+        conditions.push(format!("({})", seek_condition(gen, &terms, &walk_desc, &mut params)));
+    }
+
+    let select_list = if query.key_columns == [ROWID] && gen.dialect == SqlDialect::Sqlite {
+        "rowid AS rowid, *"
+    } else {
+        "*"
+    };
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+    let order_clause: Vec<String> = terms
+        .iter()
+        .map(|t| format!("{} {}", t.expr, if walk_desc(t.desc) { "DESC" } else { "ASC" }))
+        .collect();
+
+    let sql = format!(
+        "SELECT {} FROM {}{} ORDER BY {} LIMIT {}",
+        select_list,
+        gen.quote_table(query.table),
+        where_clause,
+        order_clause.join(", "),
+        query.limit + 1
+    );
+    let result = run(&sql, &params)?;
+
+    let mut rows: Vec<Map<String, Value>> = result
+        .rows
+        .into_iter()
+        .map(|values| result.columns.iter().map(|c| c.name.clone()).zip(values).collect())
+        .collect();
+    let has_more = rows.len() > query.limit;
+    rows.truncate(query.limit);
+    if before {
+        rows.reverse();
+    }
+
+    let make_cursor = |before: bool, values: Vec<Value>| {
+        Cursor {
+            sort: sort_column.clone(),
+            desc,
+            before,
+            values,
+        }
+        .encode()
+    };
+    let boundary = |row: &Map<String, Value>| -> Vec<Value> {
+        sort_column
+            .iter()
+            .chain(query.key_columns.iter())
+            .map(|c| row.get(c).cloned().unwrap_or(Value::Null))
+            .collect()
+    };
+    // With an empty page, the neighbours are found from the cursor's own boundary
+    let anchor = || cursor.as_ref().map(|c| c.values.clone()).unwrap_or_default();
+
+    let (next_cursor, prev_cursor) = if before {
+        let next = Some(make_cursor(false, rows.last().map(boundary).unwrap_or_else(anchor)));
+        let prev = if has_more {
+            rows.first().map(|r| make_cursor(true, boundary(r)))
+        } else {
+            None
+        };
+        (next, prev)
+    } else {
+        let next = if has_more {
+            rows.last().map(|r| make_cursor(false, boundary(r)))
+        } else {
+            None
+        };
+        let prev = cursor
+            .as_ref()
+            .map(|_| make_cursor(true, rows.first().map(boundary).unwrap_or_else(anchor)));
+        (next, prev)
+    };
+
+    Ok(KeysetPage {
+        rows,
+        columns: result.columns,
+        next_cursor,
+        prev_cursor,
+    })
+}
+
+/// Exact row count of the filtered table, running SQL through `run`
+pub fn count_rows<F>(
+    gen: &DialectGenerator,
+    table: &str,
+    filter: Option<&FilterExpr>,
+    mut run: F,
+) -> AdapterResult<u64>
+where
+    F: FnMut(&str, &[SqlValue]) -> AdapterResult<QueryResult>,
+{
+    let mut params = Vec::new();
+    let where_clause = match filter {
+        Some(f) => format!(" WHERE {}", gen.filter_sql(f, &mut params)?),
+        None => String::new(),
+    };
+    let sql = format!("SELECT COUNT(*) FROM {}{}", gen.quote_table(table), where_clause);
+    let result = run(&sql, &params)?;
+
+    let value = result.rows.first().and_then(|r| r.first());
+    value
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .ok_or_else(|| AdapterError::Query("COUNT(*) returned no value".to_string()))
+}
+
+fn order_terms(
+    gen: &DialectGenerator,
+    sort_column: Option<&str>,
+    key_columns: &[String],
+    desc: bool,
+    cursor: Option<&Cursor>,
+) -> Vec<Term> {
+    let mut values = cursor.map(|c| c.values.iter().map(SqlValue::from).collect::<Vec<_>>());
+    let mut next_value = || values.as_mut().map(|v| v.remove(0));
+
+    let mut terms = Vec::new();
+    if let Some(column) = sort_column {
+        let quoted = gen.quote_ident(column);
+        let value = next_value();
+        let is_null = matches!(value, Some(SqlValue::Null));
+        terms.push(Term {
+            expr: format!("CASE WHEN {} IS NULL THEN 1 ELSE 0 END", quoted),
+            desc,
+            value: value.as_ref().map(|_| SqlValue::Integer(i64::from(is_null))),
+        });
+        // Within the NULL group every sort value is equal, so only the key decides
+        terms.push(Term {
+            expr: quoted,
+            desc,
+            value: if is_null { None } else { value },
+        });
+    }
+    for column in key_columns {
+        terms.push(Term {
+            expr: gen.quote_ident(column),
+            desc,
+            value: next_value(),
+        });
+    }
+    terms
+}
+
+/// Lexicographic "comes after the boundary row" over the order terms:
+/// `t0 > v0 OR (t0 = v0 AND t1 > v1) OR ...`, with `<` for descending terms
+fn seek_condition(
+    gen: &DialectGenerator,
+    terms: &[Term],
+    walk_desc: &dyn Fn(bool) -> bool,
+    params: &mut Vec<SqlValue>,
+) -> String {
+    let comparable: Vec<&Term> = terms.iter().filter(|t| t.value.is_some()).collect();
+    let mut bind = |value: &SqlValue| {
+        params.push(value.clone());
+        gen.placeholder(params.len())
+    };
+
+    let mut alternatives = Vec::new();
+    for (i, term) in comparable.iter().enumerate() {
+        let mut parts: Vec<String> = comparable[..i]
+            .iter()
+            .map(|t| format!("{} = {}", t.expr, bind(t.value.as_ref().unwrap_or(&SqlValue::Null))))
+            .collect();
+        let op = if walk_desc(term.desc) { "<" } else { ">" };
+        parts.push(format!(
+            "{} {} {}",
+            term.expr,
+            op,
+            bind(term.value.as_ref().unwrap_or(&SqlValue::Null))
+        ));
+        alternatives.push(format!("({})", parts.join(" AND ")));
+    }
+    alternatives.join(" OR ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor {
+            sort: Some("name".into()),
+            desc: true,
+            before: false,
+            values: vec![Value::from("x"), Value::from(3)],
+        };
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded.sort.as_deref(), Some("name"));
+        assert!(decoded.desc && !decoded.before);
+        assert_eq!(decoded.values, cursor.values);
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+}
@@ -1,12 +1,37 @@
 //! Database Adapter Layer
 //!
 //! Provides a trait-based abstraction over multiple database backends.
-//! Currently supports SQLite with PostgreSQL and MySQL planned.
+//! Supports SQLite, PostgreSQL, and MySQL/MariaDB.
 
 pub mod sqlite;
+pub mod postgres;
+pub mod mysql;
 pub mod dialect;
+pub mod cursor;
+pub mod bulk;
+pub mod keyset;
+pub mod fts;
+pub mod introspect;
+pub mod diff;
+pub mod transpile;
+pub mod transfer;
+pub mod explain;
+pub mod guard;
+pub mod cancel;
+pub mod tuning;
 
 pub use dialect::{SqlDialect, DialectGenerator};
+pub use cursor::RowCursor;
+pub use bulk::BulkResult;
+pub use keyset::CountMode;
+pub use fts::{FtsIndex, SearchQuery, SearchResult};
+pub use diff::{SchemaChange, SchemaDiff};
+pub use transpile::SchemaExport;
+pub use transfer::{TransferOptions, TransferState};
+pub use explain::{PlanNode, QueryPlan};
+pub use guard::{SqlGuard, StatementKind};
+pub use cancel::{CancelHandle, Interrupt, QueryOptions};
+pub use tuning::{ConnectionTuning, Synchronous};
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -19,12 +44,30 @@ pub trait DatabaseAdapter: Send + Sync {
     /// Which SQL dialect this adapter uses
     fn dialect(&self) -> SqlDialect;
 
+    /// Whether the connection was opened read-only, so the backend itself
+    /// refuses writes (see `guard`)
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
     /// Execute a query that returns rows (SELECT, PRAGMA, etc.)
     fn query(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult>;
 
+    /// Execute a query and stream its rows in chunks of `chunk_size`
+    /// instead of collecting the whole result in memory
+    fn query_cursor(&self, sql: &str, params: &[SqlValue], chunk_size: usize) -> AdapterResult<RowCursor>;
+
+    /// Like `query`, but bounded by `options.timeout` and abortable through
+    /// `options.cancel`
+    fn query_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<QueryResult>;
+
     /// Execute a statement that modifies data (INSERT, UPDATE, DELETE, CREATE, etc.)
     fn execute(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult>;
 
+    /// Like `execute`, but bounded by `options.timeout` and abortable
+    /// through `options.cancel`
+    fn execute_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<ExecResult>;
+
     /// Execute multiple statements as a batch
     fn execute_batch(&self, sql: &str) -> AdapterResult<()>;
 
@@ -34,12 +77,27 @@ pub trait DatabaseAdapter: Send + Sync {
     /// Get detailed schema for a table
     fn get_table_schema(&self, table: &str) -> AdapterResult<TableSchema>;
 
+    /// Get detailed schema for every user table, e.g. to diff against
+    /// another database (see `diff`)
+    fn get_schema(&self) -> AdapterResult<Vec<TableSchema>> {
+        self.get_tables()?.iter().map(|t| self.get_table_schema(t)).collect()
+    }
+
     /// Get indexes for a table
     fn get_table_indexes(&self, table: &str) -> AdapterResult<Vec<IndexInfo>>;
 
     /// Get foreign key relationships for a table
     fn get_foreign_keys(&self, table: &str) -> AdapterResult<Vec<ForeignKeyInfo>>;
 
+    /// Get all user views with their defining queries
+    fn get_views(&self) -> AdapterResult<Vec<ViewInfo>>;
+
+    /// Get the triggers defined on a table
+    fn get_triggers(&self, table: &str) -> AdapterResult<Vec<TriggerInfo>>;
+
+    /// Get the CHECK constraints of a table
+    fn get_check_constraints(&self, table: &str) -> AdapterResult<Vec<CheckConstraint>>;
+
     /// Get row count for a table
     fn get_row_count(&self, table: &str) -> AdapterResult<u64>;
 
@@ -50,29 +108,229 @@ pub trait DatabaseAdapter: Send + Sync {
         limit: usize,
         offset: usize,
         sort: Option<&SortSpec>,
-        filters: &[FilterSpec],
+        filter: Option<&FilterExpr>,
     ) -> AdapterResult<DataPage>;
 
+    /// Query one page of rows by seeking past a cursor instead of using OFFSET.
+    /// Rows are ordered by `sort` (if any) and then the row key, and the page
+    /// carries opaque cursors to its neighbours. Pass `cursor: None` for the
+    /// first page.
+    fn query_rows_keyset(
+        &self,
+        table: &str,
+        limit: usize,
+        sort: Option<&SortSpec>,
+        filter: Option<&FilterExpr>,
+        cursor: Option<&str>,
+        count: CountMode,
+    ) -> AdapterResult<DataPage> {
+        let start = std::time::Instant::now();
+        let gen = DialectGenerator::new(self.dialect());
+        let row_key = self.row_key_columns(table)?;
+
+        let query = keyset::KeysetQuery {
+            table,
+            limit,
+            sort,
+            filter,
+            cursor,
+            key_columns: &row_key,
+        };
+        let page = keyset::fetch_page(&gen, &query, |sql, params| self.query(sql, params))?;
+
+        let (total_count, count_is_estimate) = match count {
+            CountMode::Exact => (
+                Some(keyset::count_rows(&gen, table, filter, |sql, params| self.query(sql, params))?),
+                false,
+            ),
+            CountMode::Estimate => {
+                let estimate = self.estimate_count(table, filter)?;
+                (estimate, estimate.is_some())
+            }
+            CountMode::Skip => (None, false),
+        };
+
+        Ok(DataPage {
+            rows: page.rows,
+            total_count,
+            count_is_estimate,
+            columns: page.columns,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            row_key,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Cheap approximate row count from table statistics or the query
+    /// planner, or `None` when the backend can't estimate this query
+    fn estimate_count(&self, _table: &str, _filter: Option<&FilterExpr>) -> AdapterResult<Option<u64>> {
+        Ok(None)
+    }
+
+    /// The full-text index on a table, if it has one. Only SQLite tables
+    /// can be indexed (see `fts`).
+    fn search_index(&self, table: &str) -> AdapterResult<Option<FtsIndex>> {
+        if self.dialect() != SqlDialect::Sqlite {
+            return Ok(None);
+        }
+        fts::find_index(table, |sql, params| self.query(sql, params))
+    }
+
+    /// Ranked full-text search over a table's index, with a highlighted
+    /// snippet for each hit
+    fn search(&self, table: &str, query: &SearchQuery) -> AdapterResult<SearchResult> {
+        if self.dialect() != SqlDialect::Sqlite {
+            return Err(AdapterError::Query(format!(
+                "Full-text search is not supported for {}",
+                self.dialect()
+            )));
+        }
+        let row_key = self.row_key_columns(table)?;
+        fts::search(table, &row_key, query, |sql, params| self.query(sql, params))
+    }
+
+    /// How the backend would run a statement, without running it (see `explain`)
+    fn explain(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryPlan> {
+        explain::explain(self.dialect(), sql, params, |sql, params| self.query(sql, params))
+    }
+
     /// Insert a row and return the new ID
     fn insert_row(&self, table: &str, data: &serde_json::Map<String, serde_json::Value>) -> AdapterResult<i64>;
 
-    /// Update a row by primary key
-    fn update_row(&self, table: &str, id: i64, data: &serde_json::Map<String, serde_json::Value>) -> AdapterResult<u64>;
+    /// Insert many rows using multi-row statements of up to `batch_size` rows,
+    /// all in one transaction. Rows that fail are reported rather than aborting.
+    fn insert_rows(
+        &self,
+        table: &str,
+        rows: &[serde_json::Map<String, serde_json::Value>],
+        batch_size: usize,
+    ) -> AdapterResult<BulkResult> {
+        bulk::write_rows(self, table, rows, batch_size, None)
+    }
+
+    /// Like `insert_rows`, but rows that collide on `conflict_columns`
+    /// (the primary key when empty) update the existing row instead
+    fn upsert_rows(
+        &self,
+        table: &str,
+        rows: &[serde_json::Map<String, serde_json::Value>],
+        conflict_columns: &[String],
+        batch_size: usize,
+    ) -> AdapterResult<BulkResult> {
+        let conflict = if conflict_columns.is_empty() {
+            self.primary_key(table)?
+        } else {
+            conflict_columns.to_vec()
+        };
+        if conflict.is_empty() {
+            return Err(AdapterError::Validation(format!(
+                "Upsert into {} needs conflict columns or a primary key",
+                table
+            )));
+        }
+        bulk::write_rows(self, table, rows, batch_size, Some(&conflict))
+    }
 
-    /// Delete a row by primary key
-    fn delete_row(&self, table: &str, id: i64) -> AdapterResult<u64>;
+    /// Update the row addressed by `key`
+    fn update_row(&self, table: &str, key: &RowKey, data: &serde_json::Map<String, serde_json::Value>) -> AdapterResult<u64>;
+
+    /// Delete the row addressed by `key`
+    fn delete_row(&self, table: &str, key: &RowKey) -> AdapterResult<u64>;
+
+    /// Primary key columns of a table, discovered from its schema
+    fn primary_key(&self, table: &str) -> AdapterResult<Vec<String>> {
+        let schema = self.get_table_schema(table)?;
+        Ok(schema
+            .columns
+            .into_iter()
+            .filter(|c| c.is_primary_key)
+            .map(|c| c.name)
+            .collect())
+    }
+
+    /// Columns that address a single row: the primary key, or `rowid`
+    /// for SQLite tables declared without one
+    fn row_key_columns(&self, table: &str) -> AdapterResult<Vec<String>> {
+        let pk = self.primary_key(table)?;
+        if !pk.is_empty() {
+            return Ok(pk);
+        }
+        match self.dialect() {
+            SqlDialect::Sqlite => Ok(vec![ROWID.to_string()]),
+            _ => Err(AdapterError::Validation(format!(
+                "Table {} has no primary key, so its rows cannot be addressed",
+                table
+            ))),
+        }
+    }
+
+    /// Build a row key from a client-supplied id: an object mapping every
+    /// key column to its value, or a bare value for single-column keys
+    fn resolve_row_key(&self, table: &str, id: &serde_json::Value) -> AdapterResult<RowKey> {
+        let columns = self.row_key_columns(table)?;
+        let key = match id {
+            serde_json::Value::Object(map) => RowKey(map.clone()),
+            value => match columns.as_slice() {
+                [column] => RowKey::single(column, value.clone()),
+                _ => {
+                    return Err(AdapterError::Validation(format!(
+                        "Table {} has a composite key ({}); pass an object with each column",
+                        table,
+                        columns.join(", ")
+                    )))
+                }
+            },
+        };
+        key.validate(table, &columns)?;
+        Ok(key)
+    }
 
     /// Get database file size or connection info
     fn get_database_size(&self) -> AdapterResult<u64>;
 
     /// Test the connection is alive
     fn test_connection(&self) -> AdapterResult<()>;
+
+    /// Start a transaction bound to a single pooled connection
+    fn begin(&self) -> AdapterResult<Box<dyn Transaction>>;
+}
+
+/// An open transaction holding one connection until it is finished.
+///
+/// Dropping a transaction without calling `commit` rolls it back.
+pub trait Transaction: Send {
+    /// Execute a query that returns rows inside the transaction
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult>;
+
+    /// Execute a statement that modifies data inside the transaction
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult>;
+
+    /// Create a named savepoint; savepoints may be nested
+    fn savepoint(&mut self, name: &str) -> AdapterResult<()>;
+
+    /// Discard a savepoint, keeping the changes made since it was created
+    fn release_savepoint(&mut self, name: &str) -> AdapterResult<()>;
+
+    /// Undo all changes made since a savepoint was created
+    fn rollback_to_savepoint(&mut self, name: &str) -> AdapterResult<()>;
+
+    /// Commit the transaction and return the connection to the pool
+    fn commit(self: Box<Self>) -> AdapterResult<()>;
+
+    /// Roll back the transaction and return the connection to the pool
+    fn rollback(self: Box<Self>) -> AdapterResult<()>;
+
+    /// Aborts the statement currently running in this transaction, for use
+    /// with `cancel::run`
+    fn interrupt_handle(&self) -> Interrupt;
 }
 
 /// SQL value for parameterized queries
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SqlValue {
+    #[default]
     Null,
     Integer(i64),
     Real(f64),
@@ -81,6 +339,21 @@ pub enum SqlValue {
     Blob(Vec<u8>),
 }
 
+impl From<&serde_json::Value> for SqlValue {
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => SqlValue::Null,
+            serde_json::Value::Bool(b) => SqlValue::Bool(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => SqlValue::Integer(i),
+                None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => SqlValue::Text(s.clone()),
+            other => SqlValue::Text(other.to_string()),
+        }
+    }
+}
+
 /// Result from a SELECT-type query
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
@@ -112,6 +385,10 @@ pub struct TableSchema {
     pub columns: Vec<ColumnSchema>,
     pub indexes: Vec<IndexInfo>,
     pub row_count: Option<u64>,
+    #[serde(default)]
+    pub check_constraints: Vec<CheckConstraint>,
+    #[serde(default)]
+    pub triggers: Vec<TriggerInfo>,
 }
 
 /// Column schema information
@@ -126,6 +403,17 @@ pub struct ColumnSchema {
     pub is_unique: bool,
     pub is_auto_increment: bool,
     pub foreign_key: Option<ForeignKeyRef>,
+    /// Set for generated (computed) columns, which can't be written to
+    #[serde(default)]
+    pub generated: Option<GeneratedColumn>,
+}
+
+/// How a generated column is computed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeneratedColumn {
+    pub expression: String,
+    /// Whether the value is stored on write rather than computed on read
+    pub stored: bool,
 }
 
 /// Foreign key reference on a column
@@ -143,6 +431,36 @@ pub struct IndexInfo {
     pub unique: bool,
 }
 
+/// CHECK constraint on a table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckConstraint {
+    pub name: Option<String>,
+    /// The checked expression, without the surrounding `CHECK (...)`
+    pub expression: String,
+}
+
+/// View information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewInfo {
+    pub name: String,
+    /// The view's SELECT statement
+    pub definition: String,
+    pub columns: Vec<String>,
+}
+
+/// Trigger information
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    pub name: String,
+    pub table: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`
+    pub timing: String,
+    /// Firing events, e.g. `["INSERT", "UPDATE"]`
+    pub events: Vec<String>,
+    /// Full `CREATE TRIGGER` statement in the backend's own dialect
+    pub definition: String,
+}
+
 /// Foreign key relationship information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForeignKeyInfo {
@@ -178,12 +496,17 @@ impl fmt::Display for SortDirection {
     }
 }
 
-/// Filter specification
+/// A single filter condition on one column
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterSpec {
     pub column: String,
     pub operator: FilterOp,
-    pub value: String,
+    /// Operand for single-value operators
+    #[serde(default)]
+    pub value: SqlValue,
+    /// Operands for IN / NOT IN, or the lower and upper bound for BETWEEN
+    #[serde(default)]
+    pub values: Vec<SqlValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -196,6 +519,13 @@ pub enum FilterOp {
     Gte,
     Lte,
     Like,
+    NotLike,
+    /// Case-insensitive LIKE
+    #[serde(rename = "ilike")]
+    ILike,
+    In,
+    NotIn,
+    Between,
     IsNull,
     IsNotNull,
 }
@@ -210,13 +540,92 @@ impl FilterOp {
             FilterOp::Gte => ">=",
             FilterOp::Lte => "<=",
             FilterOp::Like => "LIKE",
+            FilterOp::NotLike => "NOT LIKE",
+            FilterOp::ILike => "ILIKE",
+            FilterOp::In => "IN",
+            FilterOp::NotIn => "NOT IN",
+            FilterOp::Between => "BETWEEN",
             FilterOp::IsNull => "IS NULL",
             FilterOp::IsNotNull => "IS NOT NULL",
         }
     }
     
+    /// Whether the operator takes the single `value` operand
     pub fn needs_value(&self) -> bool {
-        !matches!(self, FilterOp::IsNull | FilterOp::IsNotNull)
+        !matches!(
+            self,
+            FilterOp::IsNull | FilterOp::IsNotNull | FilterOp::In | FilterOp::NotIn | FilterOp::Between
+        )
+    }
+}
+
+/// Composable filter: conditions combined with nested and/or/not groups.
+/// Serialized as `{"and": [...]}`, `{"or": [...]}`, `{"not": {...}}` or a bare condition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterExpr {
+    And { and: Vec<FilterExpr> },
+    Or { or: Vec<FilterExpr> },
+    Not { not: Box<FilterExpr> },
+    Condition(FilterSpec),
+}
+
+impl FilterExpr {
+    /// AND together a flat list of conditions
+    pub fn all(specs: Vec<FilterSpec>) -> Self {
+        FilterExpr::And {
+            and: specs.into_iter().map(FilterExpr::Condition).collect(),
+        }
+    }
+
+    /// Every column referenced anywhere in the expression
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            FilterExpr::And { and: exprs } | FilterExpr::Or { or: exprs } => {
+                exprs.iter().flat_map(|e| e.columns()).collect()
+            }
+            FilterExpr::Not { not } => not.columns(),
+            FilterExpr::Condition(spec) => vec![spec.column.as_str()],
+        }
+    }
+}
+
+/// SQLite's implicit row id, used to address rows of tables without a primary key
+pub const ROWID: &str = "rowid";
+
+/// Addresses a single row by the values of its key columns
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RowKey(pub serde_json::Map<String, serde_json::Value>);
+
+impl RowKey {
+    pub fn single(column: &str, value: serde_json::Value) -> Self {
+        let mut map = serde_json::Map::new();
+        map.insert(column.to_string(), value);
+        Self(map)
+    }
+
+    pub fn columns(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+
+    pub fn values(&self) -> Vec<SqlValue> {
+        self.0.values().map(SqlValue::from).collect()
+    }
+
+    /// Check that the key names exactly the given key columns
+    pub fn validate(&self, table: &str, key_columns: &[String]) -> AdapterResult<()> {
+        let matches = self.0.len() == key_columns.len()
+            && key_columns.iter().all(|c| self.0.contains_key(c));
+        if matches {
+            Ok(())
+        } else {
+            Err(AdapterError::Validation(format!(
+                "Row key for {} must give exactly these columns: {}",
+                table,
+                key_columns.join(", ")
+            )))
+        }
     }
 }
 
@@ -225,9 +634,22 @@ impl FilterOp {
 #[serde(rename_all = "camelCase")]
 pub struct DataPage {
     pub rows: Vec<serde_json::Map<String, serde_json::Value>>,
-    pub total_count: u64,
+    /// Matching rows across all pages; `None` when counting was skipped
+    pub total_count: Option<u64>,
+    /// Whether `total_count` is an estimate rather than an exact count
+    #[serde(default)]
+    pub count_is_estimate: bool,
     pub columns: Vec<ColumnMeta>,
     pub execution_time_ms: u64,
+    /// Columns that address each row (see `DatabaseAdapter::row_key_columns`)
+    #[serde(default)]
+    pub row_key: Vec<String>,
+    /// Keyset cursor for the following page, if there is one
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    /// Keyset cursor for the preceding page, if there is one
+    #[serde(default)]
+    pub prev_cursor: Option<String>,
 }
 
 /// Database adapter errors
@@ -239,6 +661,8 @@ pub enum AdapterError {
     NotFound(String),
     Validation(String),
     Internal(String),
+    /// The statement was cancelled or ran past its timeout
+    Cancelled(String),
 }
 
 impl fmt::Display for AdapterError {
@@ -250,6 +674,7 @@ impl fmt::Display for AdapterError {
             AdapterError::NotFound(e) => write!(f, "Not found: {}", e),
             AdapterError::Validation(e) => write!(f, "Validation error: {}", e),
             AdapterError::Internal(e) => write!(f, "Internal error: {}", e),
+            AdapterError::Cancelled(e) => write!(f, "Cancelled: {}", e),
         }
     }
 }
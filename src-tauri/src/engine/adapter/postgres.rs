@@ -0,0 +1,1551 @@
+//! PostgreSQL Adapter
+//!
+//! Implements DatabaseAdapter for PostgreSQL using the synchronous `postgres`
+//! client + r2d2 connection pooling. Introspection goes through
+//! information_schema and pg_catalog for the connection's current schema.
+
+use bytes::{BufMut, BytesMut};
+use postgres::types::{FromSql, IsNull, Kind, ToSql, Type};
+use postgres::GenericClient;
+use postgres_native_tls::MakeTlsConnector;
+use r2d2::{Pool, PooledConnection};
+use r2d2_postgres::PostgresConnectionManager;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use super::{
+    cancel, AdapterError, AdapterResult, CheckConstraint, ColumnMeta, ColumnSchema, DataPage, DatabaseAdapter,
+    ExecResult, FilterExpr, ForeignKeyInfo, ForeignKeyRef, GeneratedColumn, IndexInfo, Interrupt, QueryOptions,
+    QueryResult, RowCursor, RowKey, SortSpec, SqlValue, TableSchema, Transaction, TriggerInfo, ViewInfo,
+};
+use super::dialect::{DialectGenerator, SqlDialect};
+use super::tuning::ConnectionTuning;
+use super::introspect;
+use crate::engine::connections::SslMode;
+
+type PgManager = PostgresConnectionManager<MakeTlsConnector>;
+type DbPool = Pool<PgManager>;
+type DbConn = PooledConnection<PgManager>;
+
+type BoxedError = Box<dyn Error + Sync + Send>;
+
+pub struct PostgresAdapter {
+    pool: DbPool,
+    gen: DialectGenerator,
+    read_only: bool,
+    /// Cancel requests go over a fresh connection with the same TLS setup
+    tls: MakeTlsConnector,
+}
+
+impl PostgresAdapter {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        host: &str,
+        port: u16,
+        database: &str,
+        username: &str,
+        password: &str,
+        ssl_mode: &SslMode,
+        read_only: bool,
+        tuning: &ConnectionTuning,
+    ) -> AdapterResult<Self> {
+        let mut config = postgres::Config::new();
+        config
+            .host(host)
+            .port(port)
+            .dbname(database)
+            .user(username)
+            .application_name("airdb");
+        if !password.is_empty() {
+            config.password(password);
+        }
+
+        Self::from_pg_config(config, ssl_mode, read_only, tuning)
+    }
+
+    fn from_pg_config(
+        mut config: postgres::Config,
+        ssl_mode: &SslMode,
+        read_only: bool,
+        tuning: &ConnectionTuning,
+    ) -> AdapterResult<Self> {
+        tuning.validate()?;
+        if read_only {
+            // Every transaction, including single statements, refuses writes
+            config.options("-c default_transaction_read_only=on");
+        }
+        config
+            .connect_timeout(Duration::from_secs(10))
+            .ssl_mode(match ssl_mode {
+                SslMode::Disable => postgres::config::SslMode::Disable,
+                SslMode::Prefer => postgres::config::SslMode::Prefer,
+                SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => {
+                    postgres::config::SslMode::Require
+                }
+            });
+
+        let tls = Self::tls_connector(ssl_mode)?;
+        let manager = PostgresConnectionManager::new(config, tls.clone());
+        let pool = Pool::builder()
+            .max_size(tuning.pool_size)
+            .connection_timeout(Duration::from_secs(10))
+            .build(manager)
+            .map_err(|e| AdapterError::Connection(e.to_string()))?;
+
+        Ok(Self {
+            pool,
+            gen: DialectGenerator::new(SqlDialect::Postgres),
+            read_only,
+            tls,
+        })
+    }
+
+    /// Build a TLS connector matching libpq's sslmode semantics:
+    /// `prefer`/`require` encrypt without verifying, `verify-ca` checks the
+    /// certificate chain, `verify-full` also checks the host name.
+    fn tls_connector(ssl_mode: &SslMode) -> AdapterResult<MakeTlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        match ssl_mode {
+            SslMode::Disable | SslMode::Prefer | SslMode::Require => {
+                builder
+                    .danger_accept_invalid_certs(true)
+                    .danger_accept_invalid_hostnames(true);
+            }
+            SslMode::VerifyCa => {
+                builder.danger_accept_invalid_hostnames(true);
+            }
+            SslMode::VerifyFull => {}
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| AdapterError::Connection(e.to_string()))?;
+        Ok(MakeTlsConnector::new(connector))
+    }
+
+    fn get_conn(&self) -> AdapterResult<DbConn> {
+        self.pool
+            .get()
+            .map_err(|e| AdapterError::Connection(e.to_string()))
+    }
+
+    /// Ask the server to cancel whatever statement is running on `conn`
+    fn interrupt_for(conn: &postgres::Client, tls: &MakeTlsConnector) -> Interrupt {
+        let token = conn.cancel_token();
+        let tls = tls.clone();
+        Box::new(move || {
+            let _ = token.cancel_query(tls.clone());
+        })
+    }
+
+    /// Primary key columns of a table, in key order
+    fn primary_key_columns(&self, conn: &mut DbConn, table: &str) -> AdapterResult<Vec<String>> {
+        let rows = conn
+            .query(
+                "SELECT a.attname::text
+                 FROM pg_index i
+                 JOIN pg_class t ON t.oid = i.indrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 CROSS JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, ord)
+                 JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+                 WHERE i.indisprimary AND n.nspname = current_schema() AND t.relname = $1
+                 ORDER BY k.ord",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+        Ok(rows.iter().map(|r| r.get(0)).collect())
+    }
+
+    /// Run a row-returning query on a specific connection
+    fn query_on(
+        conn: &mut postgres::Client,
+        sql: &str,
+        params: &[SqlValue],
+    ) -> AdapterResult<QueryResult> {
+        let start = Instant::now();
+
+        let (stmt, columns) = prepare_decodable(conn, sql)?;
+
+        let pg_params: Vec<PgParam> = params.iter().map(PgParam).collect();
+        let rows = conn
+            .query(&stmt, &param_refs(&pg_params))
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        Ok(QueryResult {
+            columns,
+            rows: rows.iter().map(Self::row_to_json).collect::<AdapterResult<_>>()?,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Run a data-modifying statement on a specific connection
+    fn execute_on(
+        conn: &mut postgres::Client,
+        sql: &str,
+        params: &[SqlValue],
+    ) -> AdapterResult<ExecResult> {
+        let start = Instant::now();
+
+        let pg_params: Vec<PgParam> = params.iter().map(PgParam).collect();
+        let affected = conn
+            .execute(sql, &param_refs(&pg_params))
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        // PostgreSQL has no connection-level last insert id; use RETURNING instead
+        Ok(ExecResult {
+            affected_rows: affected,
+            last_insert_id: None,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Decode a result row into JSON values, one per column
+    fn row_to_json(row: &postgres::Row) -> AdapterResult<Vec<Value>> {
+        (0..row.len())
+            .map(|i| {
+                row.try_get::<_, PgJson>(i).map(|v| v.0).map_err(|e| {
+                    // The decoding error itself is the source of "error deserializing column"
+                    let cause = e.source().map_or_else(|| e.to_string(), |cause| cause.to_string());
+                    AdapterError::Query(format!("Column {}: {}", row.columns()[i].name(), cause))
+                })
+            })
+            .collect()
+    }
+
+    fn fk_action(code: &str) -> String {
+        match code {
+            "r" => "RESTRICT",
+            "c" => "CASCADE",
+            "n" => "SET NULL",
+            "d" => "SET DEFAULT",
+            _ => "NO ACTION",
+        }
+        .to_string()
+    }
+}
+
+/// Prepare a row-returning statement whose columns `PgJson` can all decode.
+/// Columns of types without a binary decoder (INTERVAL, INET, MONEY,
+/// geometric types...) are cast to text by wrapping the query; the columns
+/// are still reported with their own types.
+fn prepare_decodable(
+    conn: &mut impl GenericClient,
+    sql: &str,
+) -> AdapterResult<(postgres::Statement, Vec<ColumnMeta>)> {
+    let stmt = conn.prepare(sql).map_err(|e| AdapterError::Query(e.to_string()))?;
+    let columns: Vec<ColumnMeta> = stmt
+        .columns()
+        .iter()
+        .map(|c| ColumnMeta {
+            name: c.name().to_string(),
+            col_type: c.type_().name().to_uppercase(),
+        })
+        .collect();
+
+    let casts: Vec<bool> = stmt.columns().iter().map(|c| !has_decoder(c.type_())).collect();
+    if !casts.contains(&true) {
+        return Ok((stmt, columns));
+    }
+    // Statements that can't be wrapped (SHOW, EXPLAIN, data-modifying WITH)
+    // keep their own; their undecodable values are reported as errors
+    match conn.prepare(&text_cast_sql(sql, &casts)) {
+        Ok(wrapped) => Ok((wrapped, columns)),
+        Err(_) => Ok((stmt, columns)),
+    }
+}
+
+/// `sql` wrapped in a CTE that renames its columns by position, so
+/// duplicate and unnamed columns survive, casting those marked in `casts`
+fn text_cast_sql(sql: &str, casts: &[bool]) -> String {
+    let names: Vec<String> = (0..casts.len()).map(|i| format!("c{}", i)).collect();
+    let select: Vec<String> = names
+        .iter()
+        .zip(casts)
+        .map(|(name, &cast)| if cast { format!("{}::text", name) } else { name.clone() })
+        .collect();
+    // The newline ends a trailing line comment before the closing parenthesis
+    format!(
+        "WITH _airdb_q({}) AS (\n{}\n) SELECT {} FROM _airdb_q",
+        names.join(", "),
+        sql.trim().trim_end_matches(';'),
+        select.join(", ")
+    )
+}
+
+/// Whether `PgJson` decodes values of this type from the binary format
+fn has_decoder(ty: &Type) -> bool {
+    match *ty {
+        Type::BOOL
+        | Type::INT2
+        | Type::INT4
+        | Type::INT8
+        | Type::OID
+        | Type::FLOAT4
+        | Type::FLOAT8
+        | Type::NUMERIC
+        | Type::JSON
+        | Type::JSONB
+        | Type::UUID
+        | Type::DATE
+        | Type::TIME
+        | Type::TIMESTAMP
+        | Type::TIMESTAMPTZ
+        | Type::BYTEA
+        | Type::TEXT
+        | Type::VARCHAR
+        | Type::BPCHAR
+        | Type::NAME
+        | Type::UNKNOWN => true,
+        _ => match ty.kind() {
+            // Enum labels are sent as their text
+            Kind::Enum(_) => true,
+            Kind::Array(inner) => has_decoder(inner),
+            _ => false,
+        },
+    }
+}
+
+/// Borrow a slice of parameters in the form the postgres client expects
+fn param_refs<'a>(params: &'a [PgParam<'a>]) -> Vec<&'a (dyn ToSql + Sync)> {
+    params.iter().map(|p| p as &(dyn ToSql + Sync)).collect()
+}
+
+impl DatabaseAdapter for PostgresAdapter {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Postgres
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn query(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult> {
+        let mut conn = self.get_conn()?;
+        Self::query_on(&mut conn, sql, params)
+    }
+
+    fn query_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<QueryResult> {
+        let mut conn = self.get_conn()?;
+        let interrupt = Self::interrupt_for(&conn, &self.tls);
+        cancel::run(options, interrupt, || Self::query_on(&mut conn, sql, params))
+    }
+
+    fn query_cursor(&self, sql: &str, params: &[SqlValue], chunk_size: usize) -> AdapterResult<RowCursor> {
+        let mut conn = self.get_conn()?;
+        let sql = sql.to_string();
+        let params = params.to_vec();
+
+        // Portals only live inside a transaction; rows are pulled from the
+        // server one chunk at a time rather than sent all at once.
+        RowCursor::spawn(chunk_size, move |sink| {
+            let mut tx = conn
+                .transaction()
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+            let (stmt, columns) = prepare_decodable(&mut tx, &sql)?;
+            if !sink.columns(columns) {
+                return Ok(());
+            }
+
+            let pg_params: Vec<PgParam> = params.iter().map(PgParam).collect();
+            let portal = tx
+                .bind(&stmt, &param_refs(&pg_params))
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+            let fetch_size = chunk_size.clamp(1, i32::MAX as usize) as i32;
+            loop {
+                let rows = tx
+                    .query_portal(&portal, fetch_size)
+                    .map_err(|e| AdapterError::Query(e.to_string()))?;
+                let exhausted = rows.len() < fetch_size as usize;
+                for row in &rows {
+                    if !sink.push(Self::row_to_json(row)?) {
+                        return Ok(());
+                    }
+                }
+                if exhausted {
+                    break;
+                }
+            }
+
+            tx.commit().map_err(|e| AdapterError::Query(e.to_string()))
+        })
+    }
+
+    fn execute(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult> {
+        let mut conn = self.get_conn()?;
+        Self::execute_on(&mut conn, sql, params)
+    }
+
+    fn execute_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<ExecResult> {
+        let mut conn = self.get_conn()?;
+        let interrupt = Self::interrupt_for(&conn, &self.tls);
+        cancel::run(options, interrupt, || Self::execute_on(&mut conn, sql, params))
+    }
+
+    fn execute_batch(&self, sql: &str) -> AdapterResult<()> {
+        let mut conn = self.get_conn()?;
+        conn.batch_execute(sql)
+            .map_err(|e| AdapterError::Query(e.to_string()))
+    }
+
+    fn get_tables(&self) -> AdapterResult<Vec<String>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT table_name::text FROM information_schema.tables
+                 WHERE table_schema = current_schema()
+                   AND table_type = 'BASE TABLE'
+                   AND table_name NOT LIKE '\\_airdb\\_%'
+                 ORDER BY table_name",
+                &[],
+            )
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        Ok(rows.iter().map(|r| r.get(0)).collect())
+    }
+
+    fn get_table_schema(&self, table: &str) -> AdapterResult<TableSchema> {
+        let mut conn = self.get_conn()?;
+
+        let rows = conn
+            .query(
+                "SELECT a.attname::text,
+                        format_type(a.atttypid, a.atttypmod),
+                        NOT a.attnotnull,
+                        pg_get_expr(d.adbin, d.adrelid),
+                        EXISTS (
+                            SELECT 1 FROM pg_index i
+                            WHERE i.indrelid = c.oid AND i.indisprimary AND a.attnum = ANY(i.indkey)
+                        ),
+                        a.attidentity <> '' OR COALESCE(pg_get_expr(d.adbin, d.adrelid) LIKE 'nextval(%', false),
+                        a.attgenerated::text
+                 FROM pg_attribute a
+                 JOIN pg_class c ON c.oid = a.attrelid
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+                 WHERE n.nspname = current_schema() AND c.relname = $1
+                   AND a.attnum > 0 AND NOT a.attisdropped
+                 ORDER BY a.attnum",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        if rows.is_empty() {
+            return Err(AdapterError::NotFound(format!("Table {} not found", table)));
+        }
+
+        let mut columns: Vec<ColumnSchema> = rows
+            .iter()
+            .map(|row| {
+                // pg_attrdef holds the generation expression of generated
+                // columns ('s' stored, 'v' virtual), not a default
+                let expression: Option<String> = row.get(3);
+                let kind: String = row.get(6);
+                let (default_value, generated) = match (kind.as_str(), expression) {
+                    ("s" | "v", Some(expression)) => (
+                        None,
+                        Some(GeneratedColumn {
+                            expression,
+                            stored: kind == "s",
+                        }),
+                    ),
+                    (_, expression) => (expression, None),
+                };
+                ColumnSchema {
+                    name: row.get(0),
+                    col_type: row.get::<_, String>(1).to_uppercase(),
+                    nullable: row.get(2),
+                    default_value,
+                    is_primary_key: row.get(4),
+                    is_unique: false,
+                    is_auto_increment: row.get(5),
+                    foreign_key: None,
+                    generated,
+                }
+            })
+            .collect();
+        drop(conn);
+
+        let fks = self.get_foreign_keys(table)?;
+        for fk in &fks {
+            if let Some(col) = columns.iter_mut().find(|c| c.name == fk.from_column) {
+                col.foreign_key = Some(ForeignKeyRef {
+                    table: fk.to_table.clone(),
+                    column: fk.to_column.clone(),
+                });
+            }
+        }
+
+        let indexes = self.get_table_indexes(table)?;
+        for idx in &indexes {
+            if idx.unique && idx.columns.len() == 1 {
+                if let Some(col) = columns.iter_mut().find(|c| c.name == idx.columns[0]) {
+                    col.is_unique = true;
+                }
+            }
+        }
+
+        let row_count = self.get_row_count(table).ok();
+
+        Ok(TableSchema {
+            name: table.to_string(),
+            columns,
+            indexes,
+            row_count,
+            check_constraints: self.get_check_constraints(table)?,
+            triggers: self.get_triggers(table)?,
+        })
+    }
+
+    fn get_table_indexes(&self, table: &str) -> AdapterResult<Vec<IndexInfo>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT ic.relname::text, ix.indisunique,
+                        array_agg(a.attname::text ORDER BY k.ord)
+                 FROM pg_index ix
+                 JOIN pg_class t ON t.oid = ix.indrelid
+                 JOIN pg_class ic ON ic.oid = ix.indexrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 CROSS JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord)
+                 JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+                 WHERE n.nspname = current_schema() AND t.relname = $1 AND NOT ix.indisprimary
+                 GROUP BY ic.relname, ix.indisunique
+                 ORDER BY ic.relname",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| IndexInfo {
+                name: row.get(0),
+                unique: row.get(1),
+                columns: row.get(2),
+            })
+            .collect())
+    }
+
+    fn get_foreign_keys(&self, table: &str) -> AdapterResult<Vec<ForeignKeyInfo>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT con.conname::text, a.attname::text, ft.relname::text, fa.attname::text,
+                        con.confdeltype::text, con.confupdtype::text
+                 FROM pg_constraint con
+                 JOIN pg_class t ON t.oid = con.conrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 JOIN pg_class ft ON ft.oid = con.confrelid
+                 CROSS JOIN LATERAL unnest(con.conkey, con.confkey) AS k(attnum, fattnum)
+                 JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                 JOIN pg_attribute fa ON fa.attrelid = con.confrelid AND fa.attnum = k.fattnum
+                 WHERE con.contype = 'f' AND n.nspname = current_schema() AND t.relname = $1
+                 ORDER BY con.conname",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ForeignKeyInfo {
+                name: row.get(0),
+                from_table: table.to_string(),
+                from_column: row.get(1),
+                to_table: row.get(2),
+                to_column: row.get(3),
+                on_delete: Self::fk_action(row.get(4)),
+                on_update: Self::fk_action(row.get(5)),
+            })
+            .collect())
+    }
+
+    fn get_views(&self) -> AdapterResult<Vec<ViewInfo>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT c.relname::text, pg_get_viewdef(c.oid, true),
+                        array_agg(a.attname::text ORDER BY a.attnum)
+                 FROM pg_class c
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+                 WHERE c.relkind = 'v' AND n.nspname = current_schema()
+                 GROUP BY c.oid, c.relname
+                 ORDER BY c.relname",
+                &[],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ViewInfo {
+                name: row.get(0),
+                definition: row.get::<_, String>(1).trim().trim_end_matches(';').to_string(),
+                columns: row.get(2),
+            })
+            .collect())
+    }
+
+    fn get_triggers(&self, table: &str) -> AdapterResult<Vec<TriggerInfo>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT t.tgname::text, pg_get_triggerdef(t.oid, true)
+                 FROM pg_trigger t
+                 JOIN pg_class c ON c.oid = t.tgrelid
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 WHERE NOT t.tgisinternal AND n.nspname = current_schema() AND c.relname = $1
+                 ORDER BY t.tgname",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let definition: String = row.get(1);
+                let (timing, events) = introspect::trigger_header(&definition);
+                TriggerInfo {
+                    name: row.get(0),
+                    table: table.to_string(),
+                    timing,
+                    events,
+                    definition,
+                }
+            })
+            .collect())
+    }
+
+    fn get_check_constraints(&self, table: &str) -> AdapterResult<Vec<CheckConstraint>> {
+        let mut conn = self.get_conn()?;
+        let rows = conn
+            .query(
+                "SELECT con.conname::text, pg_get_constraintdef(con.oid, true)
+                 FROM pg_constraint con
+                 JOIN pg_class t ON t.oid = con.conrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 WHERE con.contype = 'c' AND n.nspname = current_schema() AND t.relname = $1
+                 ORDER BY con.conname",
+                &[&table],
+            )
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+
+        // pg_get_constraintdef gives `CHECK (expr)`, with NOT VALID or
+        // NO INHERIT after it when set
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let definition: String = row.get(1);
+                let expression = introspect::check_constraints(&definition)
+                    .pop()
+                    .map(|c| c.expression)
+                    .unwrap_or(definition);
+                CheckConstraint {
+                    name: Some(row.get(0)),
+                    expression,
+                }
+            })
+            .collect())
+    }
+
+    fn get_row_count(&self, table: &str) -> AdapterResult<u64> {
+        let mut conn = self.get_conn()?;
+        let count: i64 = conn
+            .query_one(
+                &format!("SELECT COUNT(*) FROM {}", self.gen.quote_ident(table)),
+                &[],
+            )
+            .map_err(|e| AdapterError::Query(e.to_string()))?
+            .get(0);
+        Ok(count as u64)
+    }
+
+    fn query_rows(
+        &self,
+        table: &str,
+        limit: usize,
+        offset: usize,
+        sort: Option<&SortSpec>,
+        filter: Option<&FilterExpr>,
+    ) -> AdapterResult<DataPage> {
+        let mut conn = self.get_conn()?;
+        let start = Instant::now();
+
+        // Build WHERE clause
+        let mut params: Vec<SqlValue> = Vec::new();
+        let where_clause = match filter {
+            Some(f) => format!(" WHERE {}", self.gen.filter_sql(f, &mut params)?),
+            None => String::new(),
+        };
+
+        let order_clause = match sort {
+            Some(s) => format!(" ORDER BY {} {}", self.gen.quote_ident(&s.column), s.direction),
+            None => String::new(),
+        };
+
+        let quoted_table = self.gen.quote_ident(table);
+        let pg_params: Vec<PgParam> = params.iter().map(PgParam).collect();
+
+        let count_sql = format!("SELECT COUNT(*) FROM {}{}", quoted_table, where_clause);
+        let total_count: i64 = conn
+            .query_one(&count_sql, &param_refs(&pg_params))
+            .map_err(|e| AdapterError::Query(e.to_string()))?
+            .get(0);
+
+        params.push(SqlValue::Integer(limit as i64));
+        params.push(SqlValue::Integer(offset as i64));
+        let data_sql = format!(
+            "SELECT * FROM {}{}{} LIMIT ${} OFFSET ${}",
+            quoted_table,
+            where_clause,
+            order_clause,
+            params.len() - 1,
+            params.len()
+        );
+
+        let (stmt, columns) = prepare_decodable(&mut *conn, &data_sql)?;
+
+        let pg_params: Vec<PgParam> = params.iter().map(PgParam).collect();
+        let rows = conn
+            .query(&stmt, &param_refs(&pg_params))
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        let rows = rows
+            .iter()
+            .map(|row| Ok(columns.iter().map(|c| c.name.clone()).zip(Self::row_to_json(row)?).collect()))
+            .collect::<AdapterResult<_>>()?;
+
+        let row_key = self.primary_key_columns(&mut conn, table)?;
+
+        Ok(DataPage {
+            rows,
+            total_count: Some(total_count as u64),
+            count_is_estimate: false,
+            columns,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            row_key,
+            next_cursor: None,
+            prev_cursor: None,
+        })
+    }
+
+    fn insert_row(
+        &self,
+        table: &str,
+        data: &serde_json::Map<String, Value>,
+    ) -> AdapterResult<i64> {
+        let mut conn = self.get_conn()?;
+        let pk_columns = self.primary_key_columns(&mut conn, table)?;
+
+        let returning = match pk_columns.as_slice() {
+            [pk] => format!(" RETURNING {}", self.gen.quote_ident(pk)),
+            _ => String::new(),
+        };
+
+        let sql = if data.is_empty() {
+            format!("INSERT INTO {} DEFAULT VALUES{}", self.gen.quote_ident(table), returning)
+        } else {
+            let col_names: Vec<String> = data.keys().map(|c| self.gen.quote_ident(c)).collect();
+            let placeholders: Vec<String> = (1..=data.len()).map(|i| format!("${}", i)).collect();
+            format!(
+                "INSERT INTO {} ({}) VALUES ({}){}",
+                self.gen.quote_ident(table),
+                col_names.join(", "),
+                placeholders.join(", "),
+                returning
+            )
+        };
+
+        let values: Vec<SqlValue> = data.values().map(SqlValue::from).collect();
+        let pg_params: Vec<PgParam> = values.iter().map(PgParam).collect();
+        let rows = conn
+            .query(&sql, &param_refs(&pg_params))
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        // Only integer keys can be reported as an id; other key types yield 0
+        let id = match rows.first() {
+            Some(row) => Self::row_to_json(row)?.into_iter().next().and_then(|v| v.as_i64()).unwrap_or(0),
+            None => 0,
+        };
+        Ok(id)
+    }
+
+    fn update_row(
+        &self,
+        table: &str,
+        key: &RowKey,
+        data: &serde_json::Map<String, Value>,
+    ) -> AdapterResult<u64> {
+        key.validate(table, &self.row_key_columns(table)?)?;
+        let mut conn = self.get_conn()?;
+
+        let set_clauses: Vec<String> = data
+            .keys()
+            .enumerate()
+            .map(|(i, k)| format!("{} = ${}", self.gen.quote_ident(k), i + 1))
+            .collect();
+
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.gen.quote_ident(table),
+            set_clauses.join(", "),
+            self.gen.key_condition(&key.columns(), data.len() + 1)
+        );
+
+        let mut values: Vec<SqlValue> = data.values().map(SqlValue::from).collect();
+        values.extend(key.values());
+        Ok(Self::execute_on(&mut conn, &sql, &values)?.affected_rows)
+    }
+
+    fn delete_row(&self, table: &str, key: &RowKey) -> AdapterResult<u64> {
+        key.validate(table, &self.row_key_columns(table)?)?;
+        let mut conn = self.get_conn()?;
+
+        let sql = format!(
+            "DELETE FROM {} WHERE {}",
+            self.gen.quote_ident(table),
+            self.gen.key_condition(&key.columns(), 1)
+        );
+        Ok(Self::execute_on(&mut conn, &sql, &key.values())?.affected_rows)
+    }
+
+    fn primary_key(&self, table: &str) -> AdapterResult<Vec<String>> {
+        let mut conn = self.get_conn()?;
+        self.primary_key_columns(&mut conn, table)
+    }
+
+    fn estimate_count(&self, table: &str, filter: Option<&FilterExpr>) -> AdapterResult<Option<u64>> {
+        // The planner's row estimate for the filtered scan
+        let mut params = Vec::new();
+        let where_clause = match filter {
+            Some(f) => format!(" WHERE {}", self.gen.filter_sql(f, &mut params)?),
+            None => String::new(),
+        };
+        let sql = format!(
+            "EXPLAIN (FORMAT JSON) SELECT 1 FROM {}{}",
+            self.gen.quote_ident(table),
+            where_clause
+        );
+        let result = self.query(&sql, &params)?;
+        let plan = result.rows.first().and_then(|r| r.first()).cloned().unwrap_or_default();
+        Ok(plan
+            .pointer("/0/Plan/Plan Rows")
+            .and_then(|v| v.as_f64())
+            .map(|rows| rows.max(0.0) as u64))
+    }
+
+    fn get_database_size(&self) -> AdapterResult<u64> {
+        let mut conn = self.get_conn()?;
+        let size: i64 = conn
+            .query_one("SELECT pg_database_size(current_database())", &[])
+            .map_err(|e| AdapterError::Query(e.to_string()))?
+            .get(0);
+        Ok(size as u64)
+    }
+
+    fn test_connection(&self) -> AdapterResult<()> {
+        let mut conn = self.get_conn()?;
+        conn.batch_execute("SELECT 1")
+            .map_err(|e| AdapterError::Connection(e.to_string()))
+    }
+
+    fn begin(&self) -> AdapterResult<Box<dyn Transaction>> {
+        let mut conn = self.get_conn()?;
+        conn.batch_execute(self.gen.begin_transaction())
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+        Ok(Box::new(PostgresTransaction {
+            conn,
+            gen: DialectGenerator::new(SqlDialect::Postgres),
+            finished: false,
+            tls: self.tls.clone(),
+        }))
+    }
+}
+
+/// A transaction on one pooled PostgreSQL connection.
+///
+/// Statements are issued on the plain client rather than through
+/// `postgres::Transaction` so the connection can be owned without borrowing.
+pub struct PostgresTransaction {
+    conn: DbConn,
+    gen: DialectGenerator,
+    finished: bool,
+    tls: MakeTlsConnector,
+}
+
+impl PostgresTransaction {
+    fn run(&mut self, sql: &str) -> AdapterResult<()> {
+        self.conn
+            .batch_execute(sql)
+            .map_err(|e| AdapterError::Query(e.to_string()))
+    }
+}
+
+impl Transaction for PostgresTransaction {
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult> {
+        PostgresAdapter::query_on(&mut self.conn, sql, params)
+    }
+
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult> {
+        PostgresAdapter::execute_on(&mut self.conn, sql, params)
+    }
+
+    fn savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        let sql = self.gen.savepoint(name);
+        self.run(&sql)
+    }
+
+    fn release_savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        let sql = self.gen.release_savepoint(name);
+        self.run(&sql)
+    }
+
+    fn rollback_to_savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        let sql = self.gen.rollback_to_savepoint(name);
+        self.run(&sql)
+    }
+
+    fn commit(mut self: Box<Self>) -> AdapterResult<()> {
+        // Only marked finished once it worked, so a failed COMMIT is
+        // rolled back on drop rather than left open on a pooled connection
+        self.run("COMMIT")?;
+        self.finished = true;
+        Ok(())
+    }
+
+    fn rollback(mut self: Box<Self>) -> AdapterResult<()> {
+        self.run("ROLLBACK")?;
+        self.finished = true;
+        Ok(())
+    }
+
+    fn interrupt_handle(&self) -> Interrupt {
+        PostgresAdapter::interrupt_for(&self.conn, &self.tls)
+    }
+}
+
+impl Drop for PostgresTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.conn.batch_execute("ROLLBACK");
+        }
+    }
+}
+
+/// A parameter bound with whatever wire type the server inferred for its
+/// placeholder, so loosely typed values (e.g. filter strings) can be compared
+/// against integer, numeric, uuid or timestamp columns.
+#[derive(Debug)]
+struct PgParam<'a>(&'a SqlValue);
+
+impl PgParam<'_> {
+    fn as_text(&self) -> String {
+        match self.0 {
+            SqlValue::Null => String::new(),
+            SqlValue::Integer(i) => i.to_string(),
+            SqlValue::Real(f) => f.to_string(),
+            SqlValue::Text(s) => s.clone(),
+            SqlValue::Bool(b) => b.to_string(),
+            SqlValue::Blob(b) => String::from_utf8_lossy(b).to_string(),
+        }
+    }
+
+    fn as_i64(&self) -> Result<i64, BoxedError> {
+        match self.0 {
+            SqlValue::Integer(i) => Ok(*i),
+            SqlValue::Bool(b) => Ok(*b as i64),
+            SqlValue::Real(f) if f.fract() == 0.0 => Ok(*f as i64),
+            _ => Ok(self.as_text().trim().parse::<i64>()?),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64, BoxedError> {
+        match self.0 {
+            SqlValue::Integer(i) => Ok(*i as f64),
+            SqlValue::Real(f) => Ok(*f),
+            _ => Ok(self.as_text().trim().parse::<f64>()?),
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool, BoxedError> {
+        match self.0 {
+            SqlValue::Bool(b) => Ok(*b),
+            SqlValue::Integer(i) => Ok(*i != 0),
+            _ => match self.as_text().trim().to_lowercase().as_str() {
+                "true" | "t" | "1" | "yes" | "y" | "on" => Ok(true),
+                "false" | "f" | "0" | "no" | "n" | "off" => Ok(false),
+                other => Err(format!("invalid boolean: {}", other).into()),
+            },
+        }
+    }
+}
+
+impl ToSql for PgParam<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxedError> {
+        if matches!(self.0, SqlValue::Null) {
+            return Ok(IsNull::Yes);
+        }
+
+        match *ty {
+            Type::BOOL => self.as_bool()?.to_sql(ty, out),
+            Type::INT2 => i16::try_from(self.as_i64()?)?.to_sql(ty, out),
+            Type::INT4 => i32::try_from(self.as_i64()?)?.to_sql(ty, out),
+            Type::INT8 => self.as_i64()?.to_sql(ty, out),
+            Type::OID => u32::try_from(self.as_i64()?)?.to_sql(ty, out),
+            Type::FLOAT4 => (self.as_f64()? as f32).to_sql(ty, out),
+            Type::FLOAT8 => self.as_f64()?.to_sql(ty, out),
+            Type::NUMERIC => {
+                encode_numeric(self.as_text().trim(), out)?;
+                Ok(IsNull::No)
+            }
+            Type::BYTEA => match self.0 {
+                SqlValue::Blob(b) => b.to_sql(ty, out),
+                _ => self.as_text().as_bytes().to_sql(ty, out),
+            },
+            Type::JSON | Type::JSONB => {
+                let value = match self.0 {
+                    SqlValue::Text(s) => {
+                        serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.clone()))
+                    }
+                    SqlValue::Integer(i) => json!(i),
+                    SqlValue::Real(f) => json!(f),
+                    SqlValue::Bool(b) => json!(b),
+                    _ => json!(self.as_text()),
+                };
+                value.to_sql(ty, out)
+            }
+            Type::UUID => uuid::Uuid::parse_str(self.as_text().trim())?.to_sql(ty, out),
+            Type::DATE => chrono::NaiveDate::parse_from_str(self.as_text().trim(), "%Y-%m-%d")?
+                .to_sql(ty, out),
+            Type::TIME => self.as_text().trim().parse::<chrono::NaiveTime>()?.to_sql(ty, out),
+            Type::TIMESTAMP => parse_timestamp(&self.as_text())?.naive_utc().to_sql(ty, out),
+            Type::TIMESTAMPTZ => parse_timestamp(&self.as_text())?.to_sql(ty, out),
+            // Text-like types (text, varchar, char, name, enums, citext...) share
+            // the plain UTF-8 binary representation.
+            _ => {
+                out.put_slice(self.as_text().as_bytes());
+                Ok(IsNull::No)
+            }
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    postgres::types::to_sql_checked!();
+}
+
+/// Parse an RFC 3339 timestamp, or a naive `YYYY-MM-DD HH:MM:SS[.f]` one as UTC
+fn parse_timestamp(s: &str) -> Result<chrono::DateTime<chrono::Utc>, BoxedError> {
+    let s = s.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&chrono::Utc));
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(s, fmt) {
+            return Ok(dt.and_utc());
+        }
+    }
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// A column value decoded from any PostgreSQL type into JSON
+struct PgJson(Value);
+
+impl<'a> FromSql<'a> for PgJson {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxedError> {
+        let value = match *ty {
+            Type::BOOL => json!(bool::from_sql(ty, raw)?),
+            Type::INT2 => json!(i16::from_sql(ty, raw)?),
+            Type::INT4 => json!(i32::from_sql(ty, raw)?),
+            Type::INT8 => json!(i64::from_sql(ty, raw)?),
+            Type::OID => json!(u32::from_sql(ty, raw)?),
+            Type::FLOAT4 => json!(f32::from_sql(ty, raw)?),
+            Type::FLOAT8 => json!(f64::from_sql(ty, raw)?),
+            // Kept as a string so no precision is lost on the way to the UI
+            Type::NUMERIC => json!(decode_numeric(raw)?),
+            Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+            Type::UUID => json!(uuid::Uuid::from_sql(ty, raw)?.to_string()),
+            Type::DATE => json!(chrono::NaiveDate::from_sql(ty, raw)?.to_string()),
+            Type::TIME => json!(chrono::NaiveTime::from_sql(ty, raw)?.to_string()),
+            Type::TIMESTAMP => json!(chrono::NaiveDateTime::from_sql(ty, raw)?.to_string()),
+            Type::TIMESTAMPTZ => {
+                json!(chrono::DateTime::<chrono::Utc>::from_sql(ty, raw)?.to_rfc3339())
+            }
+            Type::BYTEA => json!(format!("BLOB({} bytes)", raw.len())),
+            _ => match ty.kind() {
+                Kind::Array(_) => Value::Array(
+                    Vec::<PgJson>::from_sql(ty, raw)?
+                        .into_iter()
+                        .map(|v| v.0)
+                        .collect(),
+                ),
+                _ if has_decoder(ty) => json!(std::str::from_utf8(raw)?),
+                // Queries cast these to text (see `prepare_decodable`)
+                _ => return Err(format!("{} values can't be decoded; cast the column to text", ty.name()).into()),
+            },
+        };
+        Ok(PgJson(value))
+    }
+
+    fn from_sql_null(_ty: &Type) -> Result<Self, BoxedError> {
+        Ok(PgJson(Value::Null))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+
+/// Encode a decimal string in PostgreSQL's binary NUMERIC format
+/// (base-10000 digit groups with a weight, sign and display scale).
+fn encode_numeric(s: &str, out: &mut BytesMut) -> Result<(), BoxedError> {
+    if s.eq_ignore_ascii_case("nan") {
+        out.put_i16(0);
+        out.put_i16(0);
+        out.put_u16(NUMERIC_NAN);
+        out.put_u16(0);
+        return Ok(());
+    }
+
+    let (sign, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (NUMERIC_NEG, rest),
+        None => (NUMERIC_POS, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit())
+    {
+        return Err(format!("invalid numeric: {}", s).into());
+    }
+
+    let int_part = int_part.trim_start_matches('0');
+    let int_padded = format!("{}{}", "0".repeat((4 - int_part.len() % 4) % 4), int_part);
+    let frac_padded = format!("{}{}", frac_part, "0".repeat((4 - frac_part.len() % 4) % 4));
+
+    let mut groups: Vec<i16> = int_padded
+        .as_bytes()
+        .chunks(4)
+        .chain(frac_padded.as_bytes().chunks(4))
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or("0").parse::<i16>().unwrap_or(0))
+        .collect();
+    let mut weight = (int_padded.len() / 4) as i16 - 1;
+
+    while groups.first() == Some(&0) {
+        groups.remove(0);
+        weight -= 1;
+    }
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        weight = 0;
+    }
+
+    out.put_i16(groups.len() as i16);
+    out.put_i16(weight);
+    out.put_u16(if groups.is_empty() { NUMERIC_POS } else { sign });
+    out.put_u16(frac_part.len() as u16);
+    for group in groups {
+        out.put_i16(group);
+    }
+    Ok(())
+}
+
+/// Decode PostgreSQL's binary NUMERIC format into a decimal string
+fn decode_numeric(raw: &[u8]) -> Result<String, BoxedError> {
+    let read_u16 = |i: usize| -> Result<u16, BoxedError> {
+        raw.get(i..i + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| "truncated numeric".into())
+    };
+
+    let ndigits = read_u16(0)? as usize;
+    let weight = read_u16(2)? as i16 as i32;
+    let sign = read_u16(4)?;
+    let dscale = read_u16(6)? as usize;
+    let digits = (0..ndigits)
+        .map(|i| read_u16(8 + i * 2))
+        .collect::<Result<Vec<u16>, _>>()?;
+
+    match sign {
+        NUMERIC_NAN => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let digit_at = |i: i32| -> u16 {
+        if i >= 0 {
+            digits.get(i as usize).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    };
+
+    let mut result = String::new();
+    if sign == NUMERIC_NEG {
+        result.push('-');
+    }
+
+    if weight < 0 {
+        result.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                result.push_str(&digit_at(i).to_string());
+            } else {
+                result.push_str(&format!("{:04}", digit_at(i)));
+            }
+        }
+    }
+
+    if dscale > 0 {
+        let mut frac = String::new();
+        let mut i = weight + 1;
+        while frac.len() < dscale {
+            frac.push_str(&format!("{:04}", digit_at(i)));
+            i += 1;
+        }
+        frac.truncate(dscale);
+        result.push('.');
+        result.push_str(&frac);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::{CountMode, FilterOp, FilterSpec, SortDirection};
+
+    /// Connection string for a throwaway database, e.g.
+    /// `host=localhost user=postgres dbname=airdb_test`.
+    /// Tests that need a live server are skipped when it is unset.
+    fn test_adapter() -> Option<PostgresAdapter> {
+        let url = std::env::var("AIRDB_TEST_POSTGRES").ok()?;
+        let config: postgres::Config = url.parse().expect("invalid AIRDB_TEST_POSTGRES");
+        Some(PostgresAdapter::from_pg_config(config, &SslMode::Disable, false, &ConnectionTuning::default()).unwrap())
+    }
+
+    fn roundtrip(s: &str) -> String {
+        let mut buf = BytesMut::new();
+        encode_numeric(s, &mut buf).unwrap();
+        decode_numeric(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_numeric_roundtrip() {
+        assert_eq!(roundtrip("123.45"), "123.45");
+        assert_eq!(roundtrip("-0.0001"), "-0.0001");
+        assert_eq!(roundtrip("10000"), "10000");
+        assert_eq!(roundtrip("0"), "0");
+        assert_eq!(roundtrip("12345678.900"), "12345678.900");
+        assert!(encode_numeric("12a", &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn test_crud_and_introspection() {
+        let Some(adapter) = test_adapter() else { return };
+
+        adapter
+            .execute_batch(
+                "DROP TABLE IF EXISTS pg_test_items, pg_test_owners;
+                 CREATE TABLE pg_test_owners (id SERIAL PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+                 CREATE TABLE pg_test_items (
+                     id SERIAL PRIMARY KEY,
+                     owner_id INTEGER REFERENCES pg_test_owners(id) ON DELETE CASCADE,
+                     price NUMERIC(10, 2),
+                     tags TEXT[],
+                     created_at TIMESTAMPTZ DEFAULT NOW()
+                 );
+                 CREATE INDEX idx_pg_test_items_owner ON pg_test_items (owner_id);",
+            )
+            .unwrap();
+
+        let tables = adapter.get_tables().unwrap();
+        assert!(tables.contains(&"pg_test_items".to_string()));
+
+        let schema = adapter.get_table_schema("pg_test_items").unwrap();
+        let id = schema.columns.iter().find(|c| c.name == "id").unwrap();
+        assert!(id.is_primary_key && id.is_auto_increment);
+        let owner = schema.columns.iter().find(|c| c.name == "owner_id").unwrap();
+        assert_eq!(owner.foreign_key.as_ref().unwrap().table, "pg_test_owners");
+        assert_eq!(schema.indexes[0].columns, vec!["owner_id"]);
+
+        let fks = adapter.get_foreign_keys("pg_test_items").unwrap();
+        assert_eq!(fks[0].on_delete, "CASCADE");
+
+        let mut owner_row = serde_json::Map::new();
+        owner_row.insert("name".into(), json!("alice"));
+        let owner_id = adapter.insert_row("pg_test_owners", &owner_row).unwrap();
+
+        let mut item = serde_json::Map::new();
+        item.insert("owner_id".into(), json!(owner_id));
+        item.insert("price".into(), json!("19.99"));
+        let item_id = adapter.insert_row("pg_test_items", &item).unwrap();
+        assert!(item_id > 0);
+
+        let filter = FilterExpr::all(vec![FilterSpec {
+            column: "owner_id".into(),
+            operator: FilterOp::Eq,
+            value: SqlValue::Text(owner_id.to_string()),
+            values: vec![],
+        }]);
+        let page = adapter.query_rows("pg_test_items", 10, 0, None, Some(&filter)).unwrap();
+        assert_eq!(page.total_count, Some(1));
+        assert_eq!(page.rows[0]["price"], json!("19.99"));
+
+        let mut update = serde_json::Map::new();
+        update.insert("price".into(), json!(5));
+        let key = RowKey::single("id", json!(item_id));
+        assert_eq!(adapter.update_row("pg_test_items", &key, &update).unwrap(), 1);
+
+        let result = adapter
+            .query("SELECT price FROM pg_test_items WHERE id = $1", &[SqlValue::Integer(item_id)])
+            .unwrap();
+        assert_eq!(result.rows[0][0], json!("5.00"));
+
+        assert_eq!(adapter.delete_row("pg_test_items", &key).unwrap(), 1);
+        assert_eq!(adapter.get_row_count("pg_test_items").unwrap(), 0);
+
+        adapter.execute_batch("DROP TABLE pg_test_items, pg_test_owners").unwrap();
+    }
+
+    #[test]
+    fn test_text_cast_sql() {
+        assert_eq!(
+            text_cast_sql("SELECT a, b FROM t -- trailing;\n;", &[false, true]),
+            "WITH _airdb_q(c0, c1) AS (\nSELECT a, b FROM t -- trailing;\n\n) SELECT c0, c1::text FROM _airdb_q"
+        );
+        assert!(has_decoder(&Type::TEXT_ARRAY));
+        assert!(!has_decoder(&Type::INTERVAL) && !has_decoder(&Type::INET_ARRAY));
+    }
+
+    #[test]
+    fn test_types_without_binary_decoder() {
+        let Some(adapter) = test_adapter() else { return };
+
+        let sql = "SELECT INTERVAL '1 day 2 hours' AS a, '10.0.0.1/8'::inet AS a, 12.5::money,
+                          '12:30:00+02'::timetz AS t, point(1, 2) AS p, ARRAY['::1'::inet] AS ips, 'x' AS s
+                   WHERE $1::int > 0";
+        let result = adapter.query(sql, &[SqlValue::Integer(1)]).unwrap();
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "a", "money", "t", "p", "ips", "s"]);
+        assert_eq!(result.columns[0].col_type, "INTERVAL");
+        assert_eq!(
+            result.rows[0],
+            vec![
+                json!("1 day 02:00:00"),
+                json!("10.0.0.1/8"),
+                json!("$12.50"),
+                json!("12:30:00+02"),
+                json!("(1,2)"),
+                json!("{::1}"),
+                json!("x")
+            ]
+        );
+
+        // Statements that can't be wrapped report the value instead of garbling it
+        adapter.execute_batch("DROP TABLE IF EXISTS pg_test_ips; CREATE TABLE pg_test_ips (ip INET)").unwrap();
+        let err = adapter
+            .query("WITH x AS (INSERT INTO pg_test_ips VALUES ('::1') RETURNING ip) SELECT ip FROM x", &[])
+            .unwrap_err();
+        assert!(err.to_string().contains("Column ip: inet values can't be decoded"), "{}", err);
+        adapter.execute_batch("DROP TABLE pg_test_ips").unwrap();
+    }
+
+    #[test]
+    fn test_transaction_savepoints() {
+        let Some(adapter) = test_adapter() else { return };
+
+        adapter
+            .execute_batch(
+                "DROP TABLE IF EXISTS pg_test_tx;
+                 CREATE TABLE pg_test_tx (id SERIAL PRIMARY KEY, name TEXT NOT NULL);",
+            )
+            .unwrap();
+
+        let mut tx = adapter.begin().unwrap();
+        tx.execute("INSERT INTO pg_test_tx (name) VALUES ($1)", &[SqlValue::Text("keep".into())])
+            .unwrap();
+        tx.savepoint("sp1").unwrap();
+        tx.execute("INSERT INTO pg_test_tx (name) VALUES ('discard')", &[]).unwrap();
+        tx.rollback_to_savepoint("sp1").unwrap();
+        tx.commit().unwrap();
+        assert_eq!(adapter.get_row_count("pg_test_tx").unwrap(), 1);
+
+        {
+            let mut tx = adapter.begin().unwrap();
+            tx.execute("DELETE FROM pg_test_tx", &[]).unwrap();
+        }
+        assert_eq!(adapter.get_row_count("pg_test_tx").unwrap(), 1);
+
+        adapter.execute_batch("DROP TABLE pg_test_tx").unwrap();
+    }
+
+    #[test]
+    fn test_composite_and_uuid_keys() {
+        let Some(adapter) = test_adapter() else { return };
+
+        adapter
+            .execute_batch(
+                "DROP TABLE IF EXISTS pg_test_lines, pg_test_docs;
+                 CREATE TABLE pg_test_lines (
+                     order_id INTEGER, line_no INTEGER, qty INTEGER,
+                     PRIMARY KEY (order_id, line_no)
+                 );
+                 INSERT INTO pg_test_lines VALUES (1, 1, 5), (1, 2, 7);
+                 CREATE TABLE pg_test_docs (id UUID PRIMARY KEY, title TEXT);
+                 INSERT INTO pg_test_docs VALUES ('6f1c1f4e-9a43-4a1e-8d7b-0c7e5b8f2a11', 'a');",
+            )
+            .unwrap();
+
+        assert_eq!(adapter.row_key_columns("pg_test_lines").unwrap(), vec!["order_id", "line_no"]);
+        let key = adapter
+            .resolve_row_key("pg_test_lines", &json!({"order_id": 1, "line_no": 2}))
+            .unwrap();
+        let mut update = serde_json::Map::new();
+        update.insert("qty".into(), json!(9));
+        assert_eq!(adapter.update_row("pg_test_lines", &key, &update).unwrap(), 1);
+        assert!(adapter.resolve_row_key("pg_test_lines", &json!(1)).is_err());
+        assert!(adapter.resolve_row_key("pg_test_lines", &json!({"order_id": 1})).is_err());
+
+        let key = adapter
+            .resolve_row_key("pg_test_docs", &json!("6f1c1f4e-9a43-4a1e-8d7b-0c7e5b8f2a11"))
+            .unwrap();
+        assert_eq!(adapter.delete_row("pg_test_docs", &key).unwrap(), 1);
+
+        let page = adapter.query_rows("pg_test_lines", 10, 0, None, None).unwrap();
+        assert_eq!(page.row_key, vec!["order_id", "line_no"]);
+
+        adapter.execute_batch("DROP TABLE pg_test_lines, pg_test_docs").unwrap();
+    }
+
+    #[test]
+    fn test_bulk_upsert() {
+        let Some(adapter) = test_adapter() else { return };
+
+        adapter
+            .execute_batch(
+                "DROP TABLE IF EXISTS pg_test_bulk;
+                 CREATE TABLE pg_test_bulk (sku TEXT PRIMARY KEY, qty INTEGER NOT NULL CHECK (qty >= 0));",
+            )
+            .unwrap();
+
+        let rows: Vec<serde_json::Map<String, Value>> = [("a", 1), ("b", -1), ("c", 3)]
+            .iter()
+            .map(|(sku, qty)| {
+                let mut row = serde_json::Map::new();
+                row.insert("sku".into(), json!(sku));
+                row.insert("qty".into(), json!(qty));
+                row
+            })
+            .collect();
+        let result = adapter.insert_rows("pg_test_bulk", &rows, 10).unwrap();
+        assert_eq!(result.rows_affected, 2);
+        assert_eq!(result.errors[0].row, 1);
+
+        let mut row = serde_json::Map::new();
+        row.insert("sku".into(), json!("a"));
+        row.insert("qty".into(), json!(10));
+        let result = adapter.upsert_rows("pg_test_bulk", &[row], &[], 10).unwrap();
+        assert_eq!(result.rows_affected, 1);
+        let qty = adapter.query("SELECT qty FROM pg_test_bulk WHERE sku = 'a'", &[]).unwrap();
+        assert_eq!(qty.rows[0][0], json!(10));
+
+        adapter.execute_batch("DROP TABLE pg_test_bulk").unwrap();
+    }
+
+    #[test]
+    fn test_query_cursor() {
+        let Some(adapter) = test_adapter() else { return };
+
+        let cursor = adapter
+            .query_cursor(
+                "SELECT n, 'row ' || n AS label FROM generate_series(1, $1) AS n",
+                &[SqlValue::Integer(25)],
+                10,
+            )
+            .unwrap();
+        assert_eq!(cursor.columns()[1].name, "label");
+        let chunks: Vec<_> = cursor.map(|c| c.unwrap()).collect();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![10, 10, 5]);
+        assert_eq!(chunks[2][4], vec![json!(25), json!("row 25")]);
+
+        // Abandoning a cursor early must leave the connection usable
+        let mut cursor = adapter
+            .query_cursor("SELECT * FROM generate_series(1, 100000)", &[], 100)
+            .unwrap();
+        cursor.next_chunk().unwrap();
+        drop(cursor);
+        adapter.test_connection().unwrap();
+    }
+
+    #[test]
+    fn test_keyset_pagination() {
+        let Some(adapter) = test_adapter() else { return };
+
+        adapter
+            .execute_batch(
+                "DROP TABLE IF EXISTS pg_test_pages;
+                 CREATE TABLE pg_test_pages (region TEXT, id UUID, score NUMERIC(6, 2), PRIMARY KEY (region, id));
+                 INSERT INTO pg_test_pages
+                 SELECT CASE WHEN n % 2 = 0 THEN 'eu' ELSE 'us' END,
+                        md5(n::text)::uuid,
+                        CASE WHEN n % 5 = 0 THEN NULL ELSE (n % 7)::numeric END
+                 FROM generate_series(1, 23) AS n;",
+            )
+            .unwrap();
+
+        let sort = SortSpec {
+            column: "score".into(),
+            direction: SortDirection::Desc,
+        };
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = adapter
+                .query_rows_keyset("pg_test_pages", 5, Some(&sort), None, cursor.as_deref(), CountMode::Estimate)
+                .unwrap();
+            assert!(page.count_is_estimate);
+            seen.extend(page.rows.iter().map(|r| (r["region"].clone(), r["id"].clone())));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        seen.sort_by_key(|k| k.1.to_string());
+        seen.dedup();
+        assert_eq!(seen.len(), 23);
+
+        let expected = adapter
+            .query(
+                "SELECT id::text FROM pg_test_pages ORDER BY score DESC NULLS FIRST, region DESC, id DESC LIMIT 5 OFFSET 5",
+                &[],
+            )
+            .unwrap();
+        let first = adapter
+            .query_rows_keyset("pg_test_pages", 5, Some(&sort), None, None, CountMode::Exact)
+            .unwrap();
+        assert_eq!(first.total_count, Some(23));
+        let second = adapter
+            .query_rows_keyset("pg_test_pages", 5, Some(&sort), None, first.next_cursor.as_deref(), CountMode::Skip)
+            .unwrap();
+        let ids: Vec<Value> = second.rows.iter().map(|r| r["id"].clone()).collect();
+        assert_eq!(ids, expected.rows.into_iter().map(|r| r[0].clone()).collect::<Vec<_>>());
+
+        let back = adapter
+            .query_rows_keyset("pg_test_pages", 5, Some(&sort), None, second.prev_cursor.as_deref(), CountMode::Skip)
+            .unwrap();
+        assert_eq!(back.rows, first.rows);
+
+        adapter.execute_batch("DROP TABLE pg_test_pages").unwrap();
+    }
+}
@@ -11,11 +11,14 @@ use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use super::{
-    AdapterError, AdapterResult, ColumnMeta, ColumnSchema, DataPage, DatabaseAdapter,
-    ExecResult, FilterSpec, ForeignKeyInfo, ForeignKeyRef, IndexInfo, QueryResult, SortSpec,
-    SqlValue, TableSchema,
+    cancel, AdapterError, AdapterResult, CheckConstraint, ColumnMeta, ColumnSchema, DataPage, DatabaseAdapter,
+    ExecResult, FilterExpr, ForeignKeyInfo, ForeignKeyRef, IndexInfo, Interrupt, QueryOptions, QueryResult,
+    SortSpec, RowCursor, RowKey, SqlValue, TableSchema, Transaction, TriggerInfo, ViewInfo, ROWID,
 };
-use super::dialect::SqlDialect;
+use super::dialect::{split_table, DialectGenerator, SqlDialect};
+use super::fts::{hide_fts_tables, FtsIndex};
+use super::introspect;
+use super::tuning::ConnectionTuning;
 
 type DbPool = Pool<SqliteConnectionManager>;
 type DbConn = PooledConnection<SqliteConnectionManager>;
@@ -23,30 +26,61 @@ type DbConn = PooledConnection<SqliteConnectionManager>;
 pub struct SqliteAdapter {
     pool: DbPool,
     db_path: PathBuf,
+    read_only: bool,
 }
 
 impl SqliteAdapter {
     pub fn new(db_path: &Path) -> AdapterResult<Self> {
+        Self::with_tuning(db_path, &ConnectionTuning::default())
+    }
+
+    /// Open with the given pool size and pragmas; the pragmas are applied to
+    /// every connection the pool opens
+    pub fn with_tuning(db_path: &Path, tuning: &ConnectionTuning) -> AdapterResult<Self> {
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
 
-        let manager = SqliteConnectionManager::file(db_path);
+        let init_sql = tuning.sqlite_init_sql()?;
+        let manager = SqliteConnectionManager::file(db_path).with_init(move |conn| conn.execute_batch(&init_sql));
         let pool = Pool::builder()
-            .max_size(10)
+            .max_size(tuning.pool_size)
             .build(manager)
             .map_err(|e| AdapterError::Connection(e.to_string()))?;
 
         let adapter = Self {
             pool,
             db_path: db_path.to_path_buf(),
+            read_only: false,
         };
         adapter.init_schema()?;
         Ok(adapter)
     }
 
+    /// Open an existing database that can't be written through this
+    /// adapter: the file is opened read-only and `query_only` is set
+    pub fn open_read_only(db_path: &Path, tuning: &ConnectionTuning) -> AdapterResult<Self> {
+        use rusqlite::OpenFlags;
+
+        let init_sql = format!("{}PRAGMA query_only = ON;", tuning.sqlite_init_sql()?);
+        let manager = SqliteConnectionManager::file(db_path)
+            .with_flags(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+            .with_init(move |conn| conn.execute_batch(&init_sql));
+        let pool = Pool::builder()
+            .max_size(tuning.pool_size)
+            .build(manager)
+            .map_err(|e| AdapterError::Connection(e.to_string()))?;
+
+        Ok(Self {
+            pool,
+            db_path: db_path.to_path_buf(),
+            read_only: true,
+        })
+    }
+
     pub fn in_memory() -> AdapterResult<Self> {
-        let manager = SqliteConnectionManager::memory();
+        let init_sql = ConnectionTuning::default().sqlite_init_sql()?;
+        let manager = SqliteConnectionManager::memory().with_init(move |conn| conn.execute_batch(&init_sql));
         let pool = Pool::builder()
             .max_size(1)
             .build(manager)
@@ -55,6 +89,7 @@ impl SqliteAdapter {
         let adapter = Self {
             pool,
             db_path: PathBuf::from(":memory:"),
+            read_only: false,
         };
         adapter.init_schema()?;
         Ok(adapter)
@@ -77,8 +112,6 @@ impl SqliteAdapter {
         let _: String = conn
             .query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))
             .map_err(|e| AdapterError::Schema(e.to_string()))?;
-        conn.execute_batch("PRAGMA foreign_keys=ON")
-            .map_err(|e| AdapterError::Schema(e.to_string()))?;
 
         Ok(())
     }
@@ -89,6 +122,12 @@ impl SqliteAdapter {
             .map_err(|e| AdapterError::Connection(e.to_string()))
     }
 
+    /// Interrupt whatever statement is running on `conn`
+    fn interrupt_for(conn: &rusqlite::Connection) -> Interrupt {
+        let handle = conn.get_interrupt_handle();
+        Box::new(move || handle.interrupt())
+    }
+
     /// Get the raw connection for legacy code compatibility
     pub fn get_connection(&self) -> AdapterResult<DbConn> {
         self.get_conn()
@@ -128,30 +167,20 @@ impl SqliteAdapter {
         Ok(())
     }
 
-    /// Convert a rusqlite ValueRef to serde_json Value
-    fn value_ref_to_json(val: rusqlite::types::ValueRef<'_>) -> Value {
-        match val {
-            rusqlite::types::ValueRef::Null => Value::Null,
-            rusqlite::types::ValueRef::Integer(i) => json!(i),
-            rusqlite::types::ValueRef::Real(f) => json!(f),
-            rusqlite::types::ValueRef::Text(t) => {
-                json!(String::from_utf8_lossy(t).to_string())
-            }
-            rusqlite::types::ValueRef::Blob(b) => {
-                json!(format!("BLOB({} bytes)", b.len()))
-            }
-        }
-    }
-
-}
-
-impl DatabaseAdapter for SqliteAdapter {
-    fn dialect(&self) -> SqlDialect {
-        SqlDialect::Sqlite
+    /// Convert adapter parameters into boxed rusqlite parameters
+    fn to_sql_params(params: &[SqlValue]) -> Vec<Box<dyn rusqlite::types::ToSql>> {
+        params
+            .iter()
+            .map(|p| Box::new(p.clone()) as Box<dyn rusqlite::types::ToSql>)
+            .collect()
     }
 
-    fn query(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult> {
-        let conn = self.get_conn()?;
+    /// Run a row-returning query on a specific connection
+    fn query_on(
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: &[SqlValue],
+    ) -> AdapterResult<QueryResult> {
         let start = Instant::now();
 
         let mut stmt = conn
@@ -167,21 +196,7 @@ impl DatabaseAdapter for SqliteAdapter {
             })
             .collect();
 
-        // Build params for rusqlite
-        let param_values: Vec<Box<dyn rusqlite::types::ToSql>> = params
-            .iter()
-            .map(|p| -> Box<dyn rusqlite::types::ToSql> {
-                match p {
-                    SqlValue::Null => Box::new(rusqlite::types::Null),
-                    SqlValue::Integer(i) => Box::new(*i),
-                    SqlValue::Real(f) => Box::new(*f),
-                    SqlValue::Text(s) => Box::new(s.clone()),
-                    SqlValue::Bool(b) => Box::new(*b as i64),
-                    SqlValue::Blob(b) => Box::new(b.clone()),
-                }
-            })
-            .collect();
-
+        let param_values = Self::to_sql_params(params);
         let param_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|p| p.as_ref()).collect();
 
@@ -195,8 +210,8 @@ impl DatabaseAdapter for SqliteAdapter {
                 Ok(vals)
             })
             .map_err(|e| AdapterError::Query(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
+            .collect::<Result<_, _>>()
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
 
         Ok(QueryResult {
             columns,
@@ -205,24 +220,15 @@ impl DatabaseAdapter for SqliteAdapter {
         })
     }
 
-    fn execute(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult> {
-        let conn = self.get_conn()?;
+    /// Run a data-modifying statement on a specific connection
+    fn execute_on(
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: &[SqlValue],
+    ) -> AdapterResult<ExecResult> {
         let start = Instant::now();
 
-        let param_values: Vec<Box<dyn rusqlite::types::ToSql>> = params
-            .iter()
-            .map(|p| -> Box<dyn rusqlite::types::ToSql> {
-                match p {
-                    SqlValue::Null => Box::new(rusqlite::types::Null),
-                    SqlValue::Integer(i) => Box::new(*i),
-                    SqlValue::Real(f) => Box::new(*f),
-                    SqlValue::Text(s) => Box::new(s.clone()),
-                    SqlValue::Bool(b) => Box::new(*b as i64),
-                    SqlValue::Blob(b) => Box::new(b.clone()),
-                }
-            })
-            .collect();
-
+        let param_values = Self::to_sql_params(params);
         let param_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|p| p.as_ref()).collect();
 
@@ -239,6 +245,121 @@ impl DatabaseAdapter for SqliteAdapter {
         })
     }
 
+    /// Convert a rusqlite ValueRef to serde_json Value
+    fn value_ref_to_json(val: rusqlite::types::ValueRef<'_>) -> Value {
+        match val {
+            rusqlite::types::ValueRef::Null => Value::Null,
+            rusqlite::types::ValueRef::Integer(i) => json!(i),
+            rusqlite::types::ValueRef::Real(f) => json!(f),
+            rusqlite::types::ValueRef::Text(t) => {
+                json!(String::from_utf8_lossy(t).to_string())
+            }
+            rusqlite::types::ValueRef::Blob(b) => {
+                json!(format!("BLOB({} bytes)", b.len()))
+            }
+        }
+    }
+
+    /// Approximate row count of a whole table: the count recorded by the
+    /// last ANALYZE, else the largest rowid (exact until rows are deleted)
+    pub(crate) fn estimate_rows(conn: &rusqlite::Connection, table: &str) -> Option<u64> {
+        let (schema, name) = schema_and_name(table);
+        let analyzed: Option<String> = conn
+            .query_row(
+                &format!(
+                    "SELECT stat FROM {}.sqlite_stat1 WHERE tbl = ?1 ORDER BY idx IS NOT NULL LIMIT 1",
+                    quote(schema)
+                ),
+                [name],
+                |row| row.get(0),
+            )
+            .ok();
+        if let Some(rows) = analyzed.and_then(|s| s.split_whitespace().next()?.parse().ok()) {
+            return Some(rows);
+        }
+
+        let max_rowid: Option<i64> = conn
+            .query_row(&format!("SELECT MAX(rowid) FROM {}", quote_table(table)), [], |row| row.get(0))
+            .ok()
+            .flatten();
+        max_rowid.map(|n| n.max(0) as u64)
+    }
+
+}
+
+impl DatabaseAdapter for SqliteAdapter {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Sqlite
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn query(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult> {
+        let conn = self.get_conn()?;
+        Self::query_on(&conn, sql, params)
+    }
+
+    fn query_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<QueryResult> {
+        let conn = self.get_conn()?;
+        cancel::run(options, Self::interrupt_for(&conn), || Self::query_on(&conn, sql, params))
+    }
+
+    fn query_cursor(&self, sql: &str, params: &[SqlValue], chunk_size: usize) -> AdapterResult<RowCursor> {
+        let conn = self.get_conn()?;
+        let sql = sql.to_string();
+        let params = params.to_vec();
+
+        RowCursor::spawn(chunk_size, move |sink| {
+            let mut stmt = conn
+                .prepare(&sql)
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+            let columns: Vec<ColumnMeta> = stmt
+                .column_names()
+                .iter()
+                .map(|name| ColumnMeta {
+                    name: name.to_string(),
+                    col_type: String::from("TEXT"),
+                })
+                .collect();
+            let column_count = columns.len();
+            if !sink.columns(columns) {
+                return Ok(());
+            }
+
+            let param_values = Self::to_sql_params(&params);
+            let param_refs: Vec<&dyn rusqlite::types::ToSql> =
+                param_values.iter().map(|p| p.as_ref()).collect();
+
+            let mut rows = stmt
+                .query(param_refs.as_slice())
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+            while let Some(row) = rows.next().map_err(|e| AdapterError::Query(e.to_string()))? {
+                let vals = (0..column_count)
+                    .map(|i| {
+                        Self::value_ref_to_json(row.get_ref(i).unwrap_or(rusqlite::types::ValueRef::Null))
+                    })
+                    .collect();
+                if !sink.push(vals) {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn execute(&self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult> {
+        let conn = self.get_conn()?;
+        Self::execute_on(&conn, sql, params)
+    }
+
+    fn execute_with(&self, sql: &str, params: &[SqlValue], options: &QueryOptions) -> AdapterResult<ExecResult> {
+        let conn = self.get_conn()?;
+        cancel::run(options, Self::interrupt_for(&conn), || Self::execute_on(&conn, sql, params))
+    }
+
     fn execute_batch(&self, sql: &str) -> AdapterResult<()> {
         let conn = self.get_conn()?;
         conn.execute_batch(sql)
@@ -247,15 +368,24 @@ impl DatabaseAdapter for SqliteAdapter {
 
     fn get_tables(&self) -> AdapterResult<Vec<String>> {
         let conn = self.get_conn()?;
-        let mut stmt = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_airdb_%' ORDER BY name")
-            .map_err(|e| AdapterError::Query(e.to_string()))?;
-
-        let tables = stmt
-            .query_map([], |row| row.get(0))
-            .map_err(|e| AdapterError::Query(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
+        let mut tables = Vec::new();
+        // Tables of attached databases follow the main ones, as `alias.table`
+        for schema in Self::schemas(&conn)? {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT name FROM {} m WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_airdb_%' AND {} ORDER BY name",
+                    sqlite_master(&schema),
+                    hide_fts_tables(&schema)
+                ))
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+            let names: Vec<String> = stmt
+                .query_map([], |row| row.get(0))
+                .map_err(|e| AdapterError::Query(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect();
+            tables.extend(names.into_iter().map(|name| qualify(&schema, name)));
+        }
 
         Ok(tables)
     }
@@ -263,21 +393,24 @@ impl DatabaseAdapter for SqliteAdapter {
     fn get_table_schema(&self, table: &str) -> AdapterResult<TableSchema> {
         let conn = self.get_conn()?;
 
-        // Get columns via PRAGMA table_info
+        // Get columns via PRAGMA table_xinfo, which unlike table_info also
+        // lists generated columns (hidden = 2 or 3)
+        let (schema, name) = schema_and_name(table);
         let mut stmt = conn
-            .prepare(&format!("PRAGMA table_info('{}')", table))
+            .prepare("SELECT * FROM pragma_table_xinfo(?1, ?2)")
             .map_err(|e| AdapterError::Schema(e.to_string()))?;
 
         let columns: Vec<ColumnSchema> = stmt
-            .query_map([], |row| {
+            .query_map([name, schema], |row| {
                 let name: String = row.get(1)?;
                 let col_type: String = row.get(2)?;
                 let not_null: i32 = row.get(3)?;
                 let default: Option<String> = row.get(4)?;
                 let pk: i32 = row.get(5)?;
+                let hidden: i32 = row.get(6)?;
 
                 let is_auto = pk > 0 && col_type.to_uppercase() == "INTEGER";
-                Ok(ColumnSchema {
+                Ok((hidden, ColumnSchema {
                     name,
                     col_type,
                     nullable: not_null == 0,
@@ -286,15 +419,29 @@ impl DatabaseAdapter for SqliteAdapter {
                     is_unique: false,
                     is_auto_increment: is_auto,
                     foreign_key: None,
-                })
+                    generated: None,
+                }))
             })
             .map_err(|e| AdapterError::Schema(e.to_string()))?
             .filter_map(|r| r.ok())
+            // Hidden columns of virtual tables aren't part of the schema
+            .filter(|(hidden, _)| *hidden != 1)
+            .map(|(_, column)| column)
             .collect();
+        drop(stmt);
+
+        // Generated columns and CHECK constraints only exist in the table SQL
+        let create_sql = Self::table_sql(&conn, table)?;
+        let mut columns = columns;
+        for (name, generated) in introspect::generated_columns(&create_sql) {
+            if let Some(col) = columns.iter_mut().find(|c| c.name == name) {
+                col.generated = Some(generated);
+            }
+        }
+        drop(conn);
 
         // Get foreign keys
         let fks = self.get_foreign_keys(table)?;
-        let mut columns = columns;
         for fk in &fks {
             if let Some(col) = columns.iter_mut().find(|c| c.name == fk.from_column) {
                 col.foreign_key = Some(ForeignKeyRef {
@@ -324,18 +471,21 @@ impl DatabaseAdapter for SqliteAdapter {
             columns,
             indexes,
             row_count,
+            check_constraints: introspect::check_constraints(&create_sql),
+            triggers: self.get_triggers(table)?,
         })
     }
 
     fn get_table_indexes(&self, table: &str) -> AdapterResult<Vec<IndexInfo>> {
         let conn = self.get_conn()?;
 
+        let (schema, name) = schema_and_name(table);
         let mut idx_stmt = conn
-            .prepare(&format!("PRAGMA index_list('{}')", table))
+            .prepare("SELECT * FROM pragma_index_list(?1, ?2)")
             .map_err(|e| AdapterError::Schema(e.to_string()))?;
 
         let indexes: Vec<IndexInfo> = idx_stmt
-            .query_map([], |row| {
+            .query_map([name, schema], |row| {
                 let name: String = row.get(1)?;
                 let unique: i32 = row.get(2)?;
                 Ok((name, unique > 0))
@@ -344,7 +494,7 @@ impl DatabaseAdapter for SqliteAdapter {
             .filter_map(|r| r.ok())
             .filter(|(name, _)| !name.starts_with("sqlite_"))
             .map(|(name, unique)| {
-                let columns = self.get_index_columns_inner(&conn, &name);
+                let columns = self.get_index_columns_inner(&conn, schema, &name);
                 IndexInfo {
                     name,
                     columns,
@@ -359,12 +509,13 @@ impl DatabaseAdapter for SqliteAdapter {
     fn get_foreign_keys(&self, table: &str) -> AdapterResult<Vec<ForeignKeyInfo>> {
         let conn = self.get_conn()?;
 
+        let (schema, name) = schema_and_name(table);
         let mut stmt = conn
-            .prepare(&format!("PRAGMA foreign_key_list('{}')", table))
+            .prepare("SELECT * FROM pragma_foreign_key_list(?1, ?2)")
             .map_err(|e| AdapterError::Schema(e.to_string()))?;
 
         let fks: Vec<ForeignKeyInfo> = stmt
-            .query_map([], |row| {
+            .query_map([name, schema], |row| {
                 let _id: i32 = row.get(0)?;
                 let _seq: i32 = row.get(1)?;
                 let to_table: String = row.get(2)?;
@@ -377,7 +528,8 @@ impl DatabaseAdapter for SqliteAdapter {
                     name: None,
                     from_table: table.to_string(),
                     from_column,
-                    to_table,
+                    // References never leave the table's own database
+                    to_table: qualify(schema, to_table),
                     to_column,
                     on_delete,
                     on_update,
@@ -390,11 +542,83 @@ impl DatabaseAdapter for SqliteAdapter {
         Ok(fks)
     }
 
+    fn get_views(&self) -> AdapterResult<Vec<ViewInfo>> {
+        let conn = self.get_conn()?;
+        let mut views = Vec::new();
+        for schema in Self::schemas(&conn)? {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT name, sql FROM {} WHERE type='view' AND name NOT LIKE '_airdb_%' ORDER BY name",
+                    sqlite_master(&schema)
+                ))
+                .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+            let found: Vec<(String, String)> = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| AdapterError::Query(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            for (name, sql) in found {
+                let columns = conn
+                    .prepare("SELECT name FROM pragma_table_info(?1, ?2)")
+                    .and_then(|mut stmt| {
+                        let columns = stmt
+                            .query_map([name.as_str(), schema.as_str()], |row| row.get(0))?
+                            .collect::<Result<Vec<String>, _>>();
+                        columns
+                    })
+                    .map_err(|e| AdapterError::Schema(e.to_string()))?;
+                views.push(ViewInfo {
+                    definition: introspect::view_body(&sql),
+                    name: qualify(&schema, name),
+                    columns,
+                });
+            }
+        }
+        Ok(views)
+    }
+
+    fn get_triggers(&self, table: &str) -> AdapterResult<Vec<TriggerInfo>> {
+        let conn = self.get_conn()?;
+        let (schema, table_name) = schema_and_name(table);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT name, sql FROM {} WHERE type='trigger' AND tbl_name = ?1 ORDER BY name",
+                sqlite_master(schema)
+            ))
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+        let triggers = stmt
+            .query_map([table_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| AdapterError::Query(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter(|(name, _)| !FtsIndex::is_sync_trigger(table_name, name))
+            .map(|(name, sql)| {
+                let (timing, events) = introspect::trigger_header(&sql);
+                TriggerInfo {
+                    name,
+                    table: table.to_string(),
+                    timing,
+                    events,
+                    definition: sql,
+                }
+            })
+            .collect();
+
+        Ok(triggers)
+    }
+
+    fn get_check_constraints(&self, table: &str) -> AdapterResult<Vec<CheckConstraint>> {
+        let conn = self.get_conn()?;
+        Ok(introspect::check_constraints(&Self::table_sql(&conn, table)?))
+    }
+
     fn get_row_count(&self, table: &str) -> AdapterResult<u64> {
         let conn = self.get_conn()?;
         let count: i64 = conn
             .query_row(
-                &format!("SELECT COUNT(*) FROM \"{}\"", table),
+                &format!("SELECT COUNT(*) FROM {}", quote_table(table)),
                 [],
                 |row| row.get(0),
             )
@@ -408,59 +632,52 @@ impl DatabaseAdapter for SqliteAdapter {
         limit: usize,
         offset: usize,
         sort: Option<&SortSpec>,
-        filters: &[FilterSpec],
+        filter: Option<&FilterExpr>,
     ) -> AdapterResult<DataPage> {
+        // Looked up before taking a connection, since it needs one of its own
+        let mut row_key = self.primary_key(table)?;
         let conn = self.get_conn()?;
         let start = Instant::now();
 
         // Build WHERE clause
-        let mut where_parts = Vec::new();
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        let mut param_idx = 1;
-
-        for filter in filters {
-            if filter.operator.needs_value() {
-                where_parts.push(format!(
-                    "\"{}\" {} ?{}",
-                    filter.column,
-                    filter.operator.to_sql(),
-                    param_idx
-                ));
-                param_values.push(Box::new(filter.value.clone()));
-                param_idx += 1;
-            } else {
-                where_parts.push(format!(
-                    "\"{}\" {}",
-                    filter.column,
-                    filter.operator.to_sql()
-                ));
-            }
-        }
-
-        let where_clause = if where_parts.is_empty() {
-            String::new()
-        } else {
-            format!(" WHERE {}", where_parts.join(" AND "))
+        let mut params: Vec<SqlValue> = Vec::new();
+        let where_clause = match filter {
+            Some(f) => format!(" WHERE {}", DialectGenerator::new(SqlDialect::Sqlite).filter_sql(f, &mut params)?),
+            None => String::new(),
         };
+        let mut param_values = Self::to_sql_params(&params);
+        let param_idx = params.len() + 1;
 
         // Build ORDER BY
         let order_clause = match sort {
-            Some(s) => format!(" ORDER BY \"{}\" {}", s.column, s.direction),
+            Some(s) => format!(" ORDER BY {} {}", quote(&s.column), s.direction),
             None => String::new(),
         };
 
         // Get total count
-        let count_sql = format!("SELECT COUNT(*) FROM \"{}\"{}", table, where_clause);
+        let count_sql = format!("SELECT COUNT(*) FROM {}{}", quote_table(table), where_clause);
         let count_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|p| p.as_ref()).collect();
         let total_count: i64 = conn
             .query_row(&count_sql, count_refs.as_slice(), |row| row.get(0))
             .map_err(|e| AdapterError::Query(e.to_string()))?;
 
+        // Tables without a primary key are addressed by rowid, so expose it;
+        // views have neither and are read-only
+        let mut select_list = "*";
+        if row_key.is_empty()
+            && conn
+                .prepare(&format!("SELECT rowid FROM {} LIMIT 0", quote_table(table)))
+                .is_ok()
+        {
+            row_key.push(ROWID.to_string());
+            select_list = "rowid AS rowid, *";
+        }
+
         // Build data query
         let data_sql = format!(
-            "SELECT * FROM \"{}\"{}{}  LIMIT ?{} OFFSET ?{}",
-            table, where_clause, order_clause, param_idx, param_idx + 1
+            "SELECT {} FROM {}{}{}  LIMIT ?{} OFFSET ?{}",
+            select_list, quote_table(table), where_clause, order_clause, param_idx, param_idx + 1
         );
 
         param_values.push(Box::new(limit as i64));
@@ -486,20 +703,23 @@ impl DatabaseAdapter for SqliteAdapter {
             .query_map(data_refs.as_slice(), |row| {
                 let mut map = serde_json::Map::new();
                 for (i, col_name) in column_names.iter().enumerate() {
-                    let val = row.get_ref(i).unwrap_or(rusqlite::types::ValueRef::Null);
-                    map.insert(col_name.clone(), Self::value_ref_to_json(val));
+                    map.insert(col_name.clone(), Self::value_ref_to_json(row.get_ref(i)?));
                 }
                 Ok(map)
             })
             .map_err(|e| AdapterError::Query(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
+            .collect::<Result<_, _>>()
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
 
         Ok(DataPage {
             rows,
-            total_count: total_count as u64,
+            total_count: Some(total_count as u64),
+            count_is_estimate: false,
             columns,
             execution_time_ms: start.elapsed().as_millis() as u64,
+            row_key,
+            next_cursor: None,
+            prev_cursor: None,
         })
     }
 
@@ -515,8 +735,8 @@ impl DatabaseAdapter for SqliteAdapter {
         let col_names: Vec<String> = columns.iter().map(|c| format!("\"{}\"", c)).collect();
 
         let sql = format!(
-            "INSERT INTO \"{}\" ({}) VALUES ({})",
-            table,
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_table(table),
             col_names.join(", "),
             placeholders.join(", ")
         );
@@ -542,52 +762,68 @@ impl DatabaseAdapter for SqliteAdapter {
     fn update_row(
         &self,
         table: &str,
-        id: i64,
+        key: &RowKey,
         data: &serde_json::Map<String, Value>,
     ) -> AdapterResult<u64> {
+        key.validate(table, &self.row_key_columns(table)?)?;
         let conn = self.get_conn()?;
+        let gen = DialectGenerator::new(SqlDialect::Sqlite);
 
         let set_clauses: Vec<String> = data
             .keys()
             .enumerate()
-            .map(|(i, k)| format!("\"{}\" = ?{}", k, i + 1))
+            .map(|(i, k)| format!("{} = ?{}", gen.quote_ident(k), i + 1))
             .collect();
 
         let sql = format!(
-            "UPDATE \"{}\" SET {} WHERE id = ?{}",
-            table,
+            "UPDATE {} SET {} WHERE {}",
+            gen.quote_table(table),
             set_clauses.join(", "),
-            data.len() + 1
+            gen.key_condition(&key.columns(), data.len() + 1)
         );
 
-        let mut values: Vec<String> = data
+        let mut values: Vec<SqlValue> = data
             .values()
-            .map(|v| match v {
-                Value::String(s) => s.clone(),
-                _ => v.to_string(),
-            })
+            .map(SqlValue::from)
             .collect();
-        values.push(id.to_string());
+        values.extend(key.values());
 
-        let params: Vec<&dyn rusqlite::types::ToSql> =
-            values.iter().map(|s| s as &dyn rusqlite::types::ToSql).collect();
+        Ok(Self::execute_on(&conn, &sql, &values)?.affected_rows)
+    }
 
-        let affected = conn
-            .execute(&sql, params.as_slice())
-            .map_err(|e| AdapterError::Query(e.to_string()))?;
+    fn delete_row(&self, table: &str, key: &RowKey) -> AdapterResult<u64> {
+        key.validate(table, &self.row_key_columns(table)?)?;
+        let conn = self.get_conn()?;
+        let gen = DialectGenerator::new(SqlDialect::Sqlite);
 
-        Ok(affected as u64)
+        let sql = format!(
+            "DELETE FROM {} WHERE {}",
+            gen.quote_table(table),
+            gen.key_condition(&key.columns(), 1)
+        );
+        Ok(Self::execute_on(&conn, &sql, &key.values())?.affected_rows)
     }
 
-    fn delete_row(&self, table: &str, id: i64) -> AdapterResult<u64> {
+    fn primary_key(&self, table: &str) -> AdapterResult<Vec<String>> {
         let conn = self.get_conn()?;
-        let affected = conn
-            .execute(
-                &format!("DELETE FROM \"{}\" WHERE id = ?1", table),
-                [id],
-            )
-            .map_err(|e| AdapterError::Query(e.to_string()))?;
-        Ok(affected as u64)
+        let mut stmt = conn
+            .prepare("SELECT name, pk FROM pragma_table_info(?1, ?2) WHERE pk > 0 ORDER BY pk")
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+        let (schema, name) = schema_and_name(table);
+        let columns = stmt
+            .query_map([name, schema], |row| row.get(0))
+            .map_err(|e| AdapterError::Schema(e.to_string()))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| AdapterError::Schema(e.to_string()))?;
+        Ok(columns)
+    }
+
+    fn estimate_count(&self, table: &str, filter: Option<&FilterExpr>) -> AdapterResult<Option<u64>> {
+        if filter.is_some() {
+            return Ok(None);
+        }
+        let conn = self.get_conn()?;
+        Ok(Self::estimate_rows(&conn, table))
     }
 
     fn get_database_size(&self) -> AdapterResult<u64> {
@@ -604,16 +840,123 @@ impl DatabaseAdapter for SqliteAdapter {
         conn.execute_batch("SELECT 1")
             .map_err(|e| AdapterError::Connection(e.to_string()))
     }
+
+    fn begin(&self) -> AdapterResult<Box<dyn Transaction>> {
+        let conn = self.get_conn()?;
+        let gen = DialectGenerator::new(SqlDialect::Sqlite);
+        conn.execute_batch(gen.begin_transaction())
+            .map_err(|e| AdapterError::Query(e.to_string()))?;
+        Ok(Box::new(SqliteTransaction {
+            conn,
+            gen,
+            finished: false,
+        }))
+    }
+}
+
+impl rusqlite::types::ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::ToSqlOutput;
+        Ok(match self {
+            SqlValue::Null => ToSqlOutput::from(rusqlite::types::Null),
+            SqlValue::Integer(i) => ToSqlOutput::from(*i),
+            SqlValue::Real(f) => ToSqlOutput::from(*f),
+            SqlValue::Text(s) => ToSqlOutput::from(s.as_str()),
+            SqlValue::Bool(b) => ToSqlOutput::from(*b as i64),
+            SqlValue::Blob(b) => ToSqlOutput::from(b.as_slice()),
+        })
+    }
+}
+
+/// A transaction on one pooled SQLite connection
+pub struct SqliteTransaction {
+    conn: DbConn,
+    gen: DialectGenerator,
+    finished: bool,
+}
+
+impl SqliteTransaction {
+    fn run(&self, sql: &str) -> AdapterResult<()> {
+        self.conn
+            .execute_batch(sql)
+            .map_err(|e| AdapterError::Query(e.to_string()))
+    }
+}
+
+impl Transaction for SqliteTransaction {
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<QueryResult> {
+        SqliteAdapter::query_on(&self.conn, sql, params)
+    }
+
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> AdapterResult<ExecResult> {
+        SqliteAdapter::execute_on(&self.conn, sql, params)
+    }
+
+    fn savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        self.run(&self.gen.savepoint(name))
+    }
+
+    fn release_savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        self.run(&self.gen.release_savepoint(name))
+    }
+
+    fn rollback_to_savepoint(&mut self, name: &str) -> AdapterResult<()> {
+        self.run(&self.gen.rollback_to_savepoint(name))
+    }
+
+    fn commit(mut self: Box<Self>) -> AdapterResult<()> {
+        // Only marked finished once it worked, so a failed COMMIT is
+        // rolled back on drop rather than left open on a pooled connection
+        self.run("COMMIT")?;
+        self.finished = true;
+        Ok(())
+    }
+
+    fn rollback(mut self: Box<Self>) -> AdapterResult<()> {
+        self.run("ROLLBACK")?;
+        self.finished = true;
+        Ok(())
+    }
+
+    fn interrupt_handle(&self) -> Interrupt {
+        SqliteAdapter::interrupt_for(&self.conn)
+    }
+}
+
+impl Drop for SqliteTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
 }
 
 impl SqliteAdapter {
-    fn get_index_columns_inner(&self, conn: &rusqlite::Connection, index_name: &str) -> Vec<String> {
-        let mut stmt = match conn.prepare(&format!("PRAGMA index_info('{}')", index_name)) {
+    /// The `CREATE TABLE` statement SQLite stored for a table; empty for a
+    /// view, which has no constraints or generated columns to read from it
+    fn table_sql(conn: &rusqlite::Connection, table: &str) -> AdapterResult<String> {
+        let (schema, name) = schema_and_name(table);
+        conn.query_row(
+            &format!(
+                "SELECT CASE type WHEN 'table' THEN sql ELSE '' END FROM {} WHERE type IN ('table', 'view') AND name = ?1",
+                sqlite_master(schema)
+            ),
+            [name],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => AdapterError::NotFound(format!("Table {} not found", table)),
+            other => AdapterError::Schema(other.to_string()),
+        })
+    }
+
+    fn get_index_columns_inner(&self, conn: &rusqlite::Connection, schema: &str, index_name: &str) -> Vec<String> {
+        let mut stmt = match conn.prepare("SELECT * FROM pragma_index_info(?1, ?2)") {
             Ok(s) => s,
             Err(_) => return vec![],
         };
 
-        let result: Vec<String> = match stmt.query_map([], |row| {
+        let result: Vec<String> = match stmt.query_map([index_name, schema], |row| {
             let col_name: String = row.get(2)?;
             Ok(col_name)
         }) {
@@ -622,4 +965,643 @@ impl SqliteAdapter {
         };
         result
     }
+
+    fn schemas(conn: &rusqlite::Connection) -> AdapterResult<Vec<String>> {
+        schemas(conn).map_err(|e| AdapterError::Query(e.to_string()))
+    }
+}
+
+/// `main` followed by the aliases of attached databases
+pub(crate) fn schemas(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_database_list WHERE name <> 'temp' ORDER BY seq")?;
+    let schemas = stmt.query_map([], |row| row.get(0))?.collect();
+    schemas
+}
+
+/// The schema table of a database (`main` or an attached alias)
+pub(crate) fn sqlite_master(schema: &str) -> String {
+    format!("{}.sqlite_master", quote(schema))
+}
+
+/// The schema (`main` unless qualified) and bare name of a table
+pub(crate) fn schema_and_name(table: &str) -> (&str, &str) {
+    let (schema, name) = split_table(table);
+    (schema.unwrap_or("main"), name)
+}
+
+/// Name a table the way `get_tables` lists it
+fn qualify(schema: &str, name: String) -> String {
+    if schema == "main" {
+        name
+    } else {
+        format!("{}.{}", schema, name)
+    }
+}
+
+fn quote(ident: &str) -> String {
+    DialectGenerator::new(SqlDialect::Sqlite).quote_ident(ident)
+}
+
+fn quote_table(table: &str) -> String {
+    DialectGenerator::new(SqlDialect::Sqlite).quote_table(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::{CountMode, FtsIndex, SearchQuery, SortDirection, Synchronous};
+    use tempfile::tempdir;
+
+    fn setup(dir: &Path) -> SqliteAdapter {
+        let adapter = SqliteAdapter::new(&dir.join("test.db")).unwrap();
+        adapter
+            .execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+            .unwrap();
+        adapter
+    }
+
+    fn count(adapter: &SqliteAdapter) -> u64 {
+        adapter.get_row_count("items").unwrap()
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+
+        let mut tx = adapter.begin().unwrap();
+        tx.execute("INSERT INTO items (name) VALUES (?1)", &[SqlValue::Text("a".into())])
+            .unwrap();
+        let seen = tx.query("SELECT COUNT(*) FROM items", &[]).unwrap();
+        assert_eq!(seen.rows[0][0], json!(1));
+        tx.commit().unwrap();
+
+        assert_eq!(count(&adapter), 1);
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let dir = tempdir().unwrap();
+        setup(dir.path()).execute("INSERT INTO items (name) VALUES ('a')", &[]).unwrap();
+
+        let adapter = SqliteAdapter::open_read_only(&dir.path().join("test.db"), &ConnectionTuning::default()).unwrap();
+        assert!(adapter.is_read_only());
+        assert_eq!(count(&adapter), 1);
+        assert!(adapter.execute("DELETE FROM items", &[]).is_err());
+        assert!(adapter.execute_batch("PRAGMA query_only = OFF; DELETE FROM items").is_err());
+        assert_eq!(count(&adapter), 1);
+    }
+
+    #[test]
+    fn test_tuning_applies_to_every_connection() {
+        let dir = tempdir().unwrap();
+        let mut tuning = ConnectionTuning {
+            pool_size: 3,
+            busy_timeout_ms: 1234,
+            synchronous: Some(Synchronous::Normal),
+            cache_size: Some(-4000),
+            ..Default::default()
+        };
+        tuning.pragmas.insert("temp_store".to_string(), "memory".to_string());
+        let adapter = SqliteAdapter::with_tuning(&dir.path().join("test.db"), &tuning).unwrap();
+
+        // Hold every connection at once so each one has been opened fresh
+        let conns: Vec<DbConn> = (0..3).map(|_| adapter.get_conn().unwrap()).collect();
+        for conn in &conns {
+            let pragma = |name: &str| -> i64 { conn.query_row(&format!("PRAGMA {}", name), [], |r| r.get(0)).unwrap() };
+            assert_eq!(pragma("busy_timeout"), 1234);
+            assert_eq!(pragma("foreign_keys"), 1);
+            assert_eq!(pragma("synchronous"), 1);
+            assert_eq!(pragma("cache_size"), -4000);
+            assert_eq!(pragma("temp_store"), 2);
+        }
+        assert_eq!(adapter.pool.max_size(), 3);
+    }
+
+    #[test]
+    fn test_attached_databases() {
+        let dir = tempdir().unwrap();
+        SqliteAdapter::new(&dir.path().join("ref.db"))
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE countries (code TEXT PRIMARY KEY, name TEXT NOT NULL);
+                 CREATE TABLE cities (id INTEGER PRIMARY KEY, country TEXT REFERENCES countries(code));
+                 CREATE INDEX idx_cities_country ON cities(country);",
+            )
+            .unwrap();
+
+        let mut tuning = ConnectionTuning::default();
+        tuning.attach.insert("ref".to_string(), dir.path().join("ref.db"));
+        let adapter = SqliteAdapter::with_tuning(&dir.path().join("test.db"), &tuning).unwrap();
+        adapter.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY)").unwrap();
+        assert_eq!(adapter.get_tables().unwrap(), vec!["items", "ref.cities", "ref.countries"]);
+
+        let schema = adapter.get_table_schema("ref.cities").unwrap();
+        assert_eq!(schema.name, "ref.cities");
+        assert_eq!(schema.indexes[0].columns, vec!["country"]);
+        let fk = schema.columns[1].foreign_key.as_ref().unwrap();
+        assert_eq!(fk.table, "ref.countries");
+
+        let mut row = serde_json::Map::new();
+        row.insert("code".into(), json!("NZ"));
+        row.insert("name".into(), json!("New Zealand"));
+        adapter.insert_row("ref.countries", &row).unwrap();
+        assert_eq!(adapter.primary_key("ref.countries").unwrap(), vec!["code"]);
+        let page = adapter.query_rows("ref.countries", 10, 0, None, None).unwrap();
+        assert_eq!(page.rows[0]["name"], json!("New Zealand"));
+
+        // A full-text index goes into the attached database with its table
+        let index = FtsIndex {
+            table: "ref.countries".into(),
+            columns: vec!["name".into()],
+            tokenize: None,
+        };
+        adapter.execute_batch(&index.create_sql()).unwrap();
+        assert_eq!(adapter.search_index("ref.countries").unwrap(), Some(index.clone()));
+        assert!(adapter.search_index("countries").unwrap().is_none());
+        row.insert("code".into(), json!("NL"));
+        row.insert("name".into(), json!("Netherlands"));
+        adapter.insert_row("ref.countries", &row).unwrap();
+        let result = adapter.search("ref.countries", &SearchQuery::new("new")).unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.hits[0].row["code"], json!("NZ"));
+        assert_eq!(result.hits[0].snippet, "<mark>New</mark> Zealand");
+
+        let key = adapter.resolve_row_key("ref.countries", &json!("NZ")).unwrap();
+        assert_eq!(adapter.delete_row("ref.countries", &key).unwrap(), 1);
+        assert_eq!(adapter.get_row_count("ref.countries").unwrap(), 1);
+        assert_eq!(adapter.search("ref.countries", &SearchQuery::new("new")).unwrap().total_count, 0);
+        adapter.execute_batch(&index.drop_sql()).unwrap();
+        assert!(adapter.search_index("ref.countries").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_transaction_rollback_and_drop() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+
+        let mut tx = adapter.begin().unwrap();
+        tx.execute("INSERT INTO items (name) VALUES ('a')", &[]).unwrap();
+        tx.rollback().unwrap();
+        assert_eq!(count(&adapter), 0);
+
+        {
+            let mut tx = adapter.begin().unwrap();
+            tx.execute("INSERT INTO items (name) VALUES ('b')", &[]).unwrap();
+        }
+        assert_eq!(count(&adapter), 0);
+    }
+
+    #[test]
+    fn test_failed_commit_rolls_back() {
+        let dir = tempdir().unwrap();
+        // One connection, so the next transaction gets the same one back
+        let tuning = ConnectionTuning { pool_size: 1, ..ConnectionTuning::default() };
+        let adapter = SqliteAdapter::with_tuning(&dir.path().join("test.db"), &tuning).unwrap();
+        adapter
+            .execute_batch(
+                "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+                 CREATE TABLE notes (item_id INTEGER REFERENCES items (id));",
+            )
+            .unwrap();
+
+        // A deferred foreign key violation makes COMMIT itself fail
+        let mut tx = adapter.begin().unwrap();
+        tx.execute("PRAGMA defer_foreign_keys = ON", &[]).unwrap();
+        tx.execute("INSERT INTO notes (item_id) VALUES (42)", &[]).unwrap();
+        assert!(tx.commit().is_err());
+
+        // The connection went back to the pool outside a transaction
+        adapter.begin().unwrap().rollback().unwrap();
+        assert_eq!(adapter.get_row_count("notes").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_query_cursor() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 25)
+                 INSERT INTO items (name) SELECT 'item' || i FROM n",
+            )
+            .unwrap();
+
+        let cursor = adapter
+            .query_cursor("SELECT id, name FROM items WHERE id > ?1", &[SqlValue::Integer(0)], 10)
+            .unwrap();
+        assert_eq!(cursor.columns().len(), 2);
+        let chunks: Vec<Vec<Vec<Value>>> = cursor.map(|c| c.unwrap()).collect();
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![10, 10, 5]);
+        assert_eq!(chunks[2][4], vec![json!(25), json!("item25")]);
+
+        assert!(adapter.query_cursor("SELECT * FROM missing", &[], 10).is_err());
+    }
+
+    #[test]
+    fn test_row_keys() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "CREATE TABLE lines (order_id INTEGER, line_no INTEGER, qty INTEGER,
+                                     PRIMARY KEY (line_no, order_id));
+                 INSERT INTO lines VALUES (1, 1, 5), (1, 2, 7);
+                 CREATE TABLE docs (slug TEXT PRIMARY KEY, title TEXT);
+                 INSERT INTO docs VALUES ('intro', 'Intro');
+                 CREATE TABLE log (message TEXT);
+                 INSERT INTO log VALUES ('a'), ('b');",
+            )
+            .unwrap();
+
+        // Composite key, in key order rather than column order
+        assert_eq!(adapter.row_key_columns("lines").unwrap(), vec!["line_no", "order_id"]);
+        let key = adapter
+            .resolve_row_key("lines", &json!({"order_id": 1, "line_no": 2}))
+            .unwrap();
+        let mut update = serde_json::Map::new();
+        update.insert("qty".into(), json!(9));
+        assert_eq!(adapter.update_row("lines", &key, &update).unwrap(), 1);
+        assert!(adapter.resolve_row_key("lines", &json!(2)).is_err());
+        assert!(adapter.delete_row("lines", &RowKey::single("qty", json!(9))).is_err());
+
+        // Text key
+        let key = adapter.resolve_row_key("docs", &json!("intro")).unwrap();
+        assert_eq!(adapter.delete_row("docs", &key).unwrap(), 1);
+
+        // No primary key falls back to rowid
+        let page = adapter.query_rows("log", 10, 0, None, None).unwrap();
+        assert_eq!(page.row_key, vec![ROWID]);
+        let rowid = page.rows[1][ROWID].clone();
+        let key = adapter.resolve_row_key("log", &rowid).unwrap();
+        assert_eq!(adapter.delete_row("log", &key).unwrap(), 1);
+        let result = adapter.query("SELECT message FROM log", &[]).unwrap();
+        assert_eq!(result.rows, vec![vec![json!("a")]]);
+    }
+
+    #[test]
+    fn test_update_row_values() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "CREATE TABLE docs (id INTEGER PRIMARY KEY, title TEXT, views INTEGER, draft BOOLEAN);
+                 INSERT INTO docs VALUES (1, 'Intro', 0, 1);",
+            )
+            .unwrap();
+
+        let key = adapter.resolve_row_key("docs", &json!(1)).unwrap();
+        let mut update = serde_json::Map::new();
+        update.insert("title".into(), Value::Null);
+        update.insert("views".into(), json!(12));
+        update.insert("draft".into(), json!(false));
+        assert_eq!(adapter.update_row("docs", &key, &update).unwrap(), 1);
+
+        let result = adapter
+            .query("SELECT title IS NULL, typeof(views), views, draft FROM docs", &[])
+            .unwrap();
+        assert_eq!(result.rows, vec![vec![json!(1), json!("integer"), json!(12), json!(0)]]);
+    }
+
+    #[test]
+    fn test_query_rows_sort_and_row_errors() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "ALTER TABLE items ADD COLUMN \"odd\"\"name\" INTEGER;
+                 INSERT INTO items (name, \"odd\"\"name\") VALUES ('a', 2), ('b', 1), ('{', 3);
+                 CREATE VIEW parsed AS SELECT json_extract(name, '$') AS value FROM items;",
+            )
+            .unwrap();
+
+        let sort = SortSpec { column: "odd\"name".into(), direction: SortDirection::Asc };
+        let page = adapter.query_rows("items", 10, 0, Some(&sort), None).unwrap();
+        let names: Vec<&Value> = page.rows.iter().map(|r| &r["name"]).collect();
+        assert_eq!(names, vec![&json!("b"), &json!("a"), &json!("{")]);
+
+        // Malformed JSON only fails once the row is stepped, and isn't skipped
+        assert!(matches!(adapter.query_rows("parsed", 10, 0, None, None), Err(AdapterError::Query(_))));
+    }
+
+    #[test]
+    fn test_bulk_insert_and_upsert() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+
+        let rows: Vec<serde_json::Map<String, Value>> = (1..=7)
+            .map(|i| {
+                let mut row = serde_json::Map::new();
+                row.insert("id".into(), json!(i));
+                row.insert("name".into(), if i == 4 { Value::Null } else { json!(format!("n{}", i)) });
+                row
+            })
+            .collect();
+
+        // Row 3 (id 4) violates NOT NULL; the rest of its batch still lands
+        let result = adapter.insert_rows("items", &rows, 3).unwrap();
+        assert_eq!(result.batches.iter().map(|b| b.rows).collect::<Vec<_>>(), vec![3, 3, 1]);
+        assert_eq!(result.rows_affected, 6);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].row, 3);
+        assert_eq!(count(&adapter), 6);
+
+        let mut changed = serde_json::Map::new();
+        changed.insert("id".into(), json!(1));
+        changed.insert("name".into(), json!("renamed"));
+        let mut added = serde_json::Map::new();
+        added.insert("id".into(), json!(4));
+        added.insert("name".into(), json!("n4"));
+        let result = adapter.upsert_rows("items", &[changed, added], &[], 100).unwrap();
+        assert!(result.errors.is_empty());
+        assert_eq!(result.batches.len(), 1);
+        assert_eq!(count(&adapter), 7);
+
+        let renamed = adapter.query("SELECT name FROM items WHERE id = 1", &[]).unwrap();
+        assert_eq!(renamed.rows[0][0], json!("renamed"));
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "INSERT INTO items (name) VALUES ('Apple'), ('banana'), ('Cherry'), ('date'), ('elderberry')",
+            )
+            .unwrap();
+
+        let names = |filter: serde_json::Value| -> Vec<Value> {
+            let filter: FilterExpr = serde_json::from_value(filter).unwrap();
+            adapter
+                .query_rows("items", 100, 0, None, Some(&filter))
+                .unwrap()
+                .rows
+                .into_iter()
+                .map(|r| r["name"].clone())
+                .collect()
+        };
+
+        assert_eq!(
+            names(json!({"or": [
+                {"column": "id", "operator": "in", "values": [1, 3]},
+                {"and": [
+                    {"column": "id", "operator": "between", "values": [4, 5]},
+                    {"not": {"column": "name", "operator": "not_like", "value": "%e%"}}
+                ]}
+            ]})),
+            vec![json!("Apple"), json!("Cherry"), json!("date"), json!("elderberry")]
+        );
+        assert_eq!(
+            names(json!({"column": "name", "operator": "ilike", "value": "%AN%"})),
+            vec![json!("banana")]
+        );
+        assert_eq!(names(json!({"column": "id", "operator": "in", "values": []})).len(), 0);
+        assert_eq!(names(json!({"and": []})).len(), 5);
+
+        let bad: FilterExpr =
+            serde_json::from_value(json!({"column": "id", "operator": "between", "values": [1]}))
+                .unwrap();
+        assert!(adapter.query_rows("items", 10, 0, None, Some(&bad)).is_err());
+    }
+
+    #[test]
+    fn test_savepoints() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+
+        let mut tx = adapter.begin().unwrap();
+        tx.execute("INSERT INTO items (name) VALUES ('keep')", &[]).unwrap();
+        tx.savepoint("sp1").unwrap();
+        tx.execute("INSERT INTO items (name) VALUES ('discard')", &[]).unwrap();
+        tx.rollback_to_savepoint("sp1").unwrap();
+        tx.savepoint("sp2").unwrap();
+        tx.execute("INSERT INTO items (name) VALUES ('nested')", &[]).unwrap();
+        tx.release_savepoint("sp2").unwrap();
+        tx.commit().unwrap();
+
+        let result = adapter.query("SELECT name FROM items ORDER BY id", &[]).unwrap();
+        assert_eq!(result.rows, vec![vec![json!("keep")], vec![json!("nested")]]);
+    }
+
+    #[test]
+    fn test_keyset_pagination() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "ALTER TABLE items ADD COLUMN rank INTEGER;
+                 INSERT INTO items (name, rank) VALUES
+                    ('a', 3), ('b', NULL), ('c', 1), ('d', 3), ('e', NULL), ('f', 2), ('g', 1);",
+            )
+            .unwrap();
+
+        let sort = SortSpec {
+            column: "rank".into(),
+            direction: SortDirection::Asc,
+        };
+        let names = |page: &DataPage| -> String {
+            page.rows.iter().map(|r| r["name"].as_str().unwrap()).collect()
+        };
+        let page = |cursor: Option<&str>, count| {
+            adapter
+                .query_rows_keyset("items", 3, Some(&sort), None, cursor, count)
+                .unwrap()
+        };
+
+        // Ties on rank fall back to the key; NULLs sort last
+        let first = page(None, CountMode::Exact);
+        assert_eq!(names(&first), "cgf");
+        assert_eq!(first.total_count, Some(7));
+        assert!(first.prev_cursor.is_none());
+
+        let second = page(first.next_cursor.as_deref(), CountMode::Skip);
+        assert_eq!(names(&second), "adb");
+        assert_eq!(second.total_count, None);
+
+        let third = page(second.next_cursor.as_deref(), CountMode::Estimate);
+        assert_eq!(names(&third), "e");
+        assert!(third.next_cursor.is_none());
+        assert_eq!(third.total_count, Some(7));
+        assert!(third.count_is_estimate);
+
+        let back = page(third.prev_cursor.as_deref(), CountMode::Skip);
+        assert_eq!(names(&back), "adb");
+        let back = page(back.prev_cursor.as_deref(), CountMode::Skip);
+        assert_eq!(names(&back), "cgf");
+        assert!(back.prev_cursor.is_none());
+
+        // Descending puts NULLs first
+        let desc = SortSpec {
+            column: "rank".into(),
+            direction: SortDirection::Desc,
+        };
+        let page = adapter
+            .query_rows_keyset("items", 4, Some(&desc), None, None, CountMode::Skip)
+            .unwrap();
+        assert_eq!(names(&page), "ebda");
+        let rest = adapter
+            .query_rows_keyset("items", 4, Some(&desc), None, page.next_cursor.as_deref(), CountMode::Skip)
+            .unwrap();
+        assert_eq!(names(&rest), "fgc");
+
+        // A cursor only applies to the ordering it came from
+        assert!(adapter
+            .query_rows_keyset("items", 4, None, None, page.next_cursor.as_deref(), CountMode::Skip)
+            .is_err());
+
+        // Keyless tables page by rowid, with filters
+        adapter
+            .execute_batch("CREATE TABLE logs (msg TEXT); INSERT INTO logs VALUES ('x'), ('y'), ('z');")
+            .unwrap();
+        let filter: FilterExpr =
+            serde_json::from_value(json!({"column": "msg", "operator": "neq", "value": "y"})).unwrap();
+        let logs = adapter
+            .query_rows_keyset("logs", 1, None, Some(&filter), None, CountMode::Exact)
+            .unwrap();
+        assert_eq!(logs.row_key, vec![ROWID.to_string()]);
+        assert_eq!(logs.total_count, Some(2));
+        let logs = adapter
+            .query_rows_keyset("logs", 1, None, Some(&filter), logs.next_cursor.as_deref(), CountMode::Skip)
+            .unwrap();
+        assert_eq!(logs.rows[0]["msg"], json!("z"));
+        assert!(logs.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_full_text_search() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "ALTER TABLE items ADD COLUMN body TEXT;
+                 INSERT INTO items (name, body) VALUES
+                    ('Groceries', 'milk, eggs and bread'),
+                    ('Garden', 'plant tomatoes near the fence'),
+                    ('Bread', 'sourdough starter needs feeding');",
+            )
+            .unwrap();
+
+        let index = FtsIndex {
+            table: "items".into(),
+            columns: vec!["name".into(), "body".into()],
+            tokenize: Some("porter unicode61".into()),
+        };
+        adapter.execute_batch(&index.create_sql()).unwrap();
+        assert_eq!(adapter.search_index("items").unwrap(), Some(index.clone()));
+        assert!(adapter.search_index("other").unwrap().is_none());
+        assert_eq!(adapter.get_tables().unwrap(), vec!["items"]);
+
+        // Existing rows were indexed
+        let result = adapter.search("items", &SearchQuery::new("bread")).unwrap();
+        assert_eq!(result.total_count, 2);
+        assert!(result.hits[0].rank <= result.hits[1].rank);
+        let groceries = result.hits.iter().find(|h| h.row["name"] == json!("Groceries")).unwrap();
+        assert_eq!(groceries.snippet, "milk, eggs and <mark>bread</mark>");
+        assert_eq!(result.row_key, vec!["id"]);
+        assert!(result.columns.iter().all(|c| !c.name.starts_with("_airdb")));
+
+        // Triggers keep the index in sync; the last term matches as a prefix
+        adapter
+            .execute("INSERT INTO items (name, body) VALUES ('Bakery', 'buy bread rolls')", &[])
+            .unwrap();
+        adapter
+            .execute("UPDATE items SET body = 'milk and eggs' WHERE name = 'Groceries'", &[])
+            .unwrap();
+        adapter.execute("DELETE FROM items WHERE name = 'Garden'", &[]).unwrap();
+        let names = |query: SearchQuery| -> Vec<Value> {
+            let mut names: Vec<Value> = adapter
+                .search("items", &query)
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|h| h.row["name"].clone())
+                .collect();
+            names.sort_by_key(|v| v.to_string());
+            names
+        };
+        assert_eq!(names(SearchQuery::new("bread")), vec![json!("Bakery"), json!("Bread")]);
+        assert_eq!(names(SearchQuery::new("tomato")).len(), 0);
+        assert_eq!(names(SearchQuery::new("sour")), vec![json!("Bread")]);
+        let mut raw = SearchQuery::new("milk OR rolls");
+        raw.raw = true;
+        assert_eq!(names(raw), vec![json!("Bakery"), json!("Groceries")]);
+
+        let mut bad = SearchQuery::new("milk AND (");
+        bad.raw = true;
+        assert!(matches!(adapter.search("items", &bad), Err(AdapterError::Validation(_))));
+
+        adapter.execute_batch(&index.drop_sql()).unwrap();
+        assert!(adapter.search_index("items").unwrap().is_none());
+        assert!(matches!(
+            adapter.search("items", &SearchQuery::new("bread")),
+            Err(AdapterError::NotFound(_))
+        ));
+        // The source table is untouched and writable without the triggers
+        adapter.execute("DELETE FROM items", &[]).unwrap();
+    }
+
+    #[test]
+    fn test_views_triggers_checks_and_generated_columns() {
+        let dir = tempdir().unwrap();
+        let adapter = setup(dir.path());
+        adapter
+            .execute_batch(
+                "CREATE TABLE orders (
+                    id INTEGER PRIMARY KEY,
+                    qty INTEGER NOT NULL CHECK (qty > 0),
+                    price REAL NOT NULL,
+                    total REAL GENERATED ALWAYS AS (qty * price) STORED,
+                    CONSTRAINT price_positive CHECK (price >= 0)
+                 );
+                 CREATE TABLE order_log (order_id INTEGER);
+                 CREATE VIEW big_orders AS SELECT id, total FROM orders WHERE total > 100;
+                 CREATE TRIGGER log_order AFTER INSERT ON orders BEGIN
+                     INSERT INTO order_log VALUES (new.id);
+                 END;",
+            )
+            .unwrap();
+
+        assert_eq!(adapter.get_tables().unwrap(), vec!["items", "order_log", "orders"]);
+
+        let views = adapter.get_views().unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].name, "big_orders");
+        assert_eq!(views[0].definition, "SELECT id, total FROM orders WHERE total > 100");
+        assert_eq!(views[0].columns, vec!["id", "total"]);
+        let view = adapter.get_table_schema("big_orders").unwrap();
+        let columns: Vec<&str> = view.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, vec!["id", "total"]);
+        assert!(view.check_constraints.is_empty() && view.columns.iter().all(|c| c.generated.is_none()));
+        assert!(matches!(adapter.get_table_schema("no_such_view"), Err(AdapterError::NotFound(_))));
+
+        let schema = adapter.get_table_schema("orders").unwrap();
+        let total = schema.columns.iter().find(|c| c.name == "total").unwrap();
+        let generated = total.generated.as_ref().unwrap();
+        assert_eq!(generated.expression, "qty * price");
+        assert!(generated.stored);
+        assert!(schema.columns.iter().filter(|c| c.name != "total").all(|c| c.generated.is_none()));
+
+        let checks: Vec<_> = schema
+            .check_constraints
+            .iter()
+            .map(|c| (c.name.as_deref(), c.expression.as_str()))
+            .collect();
+        assert_eq!(checks, vec![(None, "qty > 0"), (Some("price_positive"), "price >= 0")]);
+
+        assert_eq!(schema.triggers.len(), 1);
+        assert_eq!(schema.triggers[0].name, "log_order");
+        assert_eq!(schema.triggers[0].timing, "AFTER");
+        assert_eq!(schema.triggers[0].events, vec!["INSERT"]);
+        assert!(adapter.get_triggers("order_log").unwrap().is_empty());
+
+        // FTS sync triggers belong to the hidden index, not the table
+        let index = FtsIndex {
+            table: "items".into(),
+            columns: vec!["name".into()],
+            tokenize: None,
+        };
+        adapter.execute_batch(&index.create_sql()).unwrap();
+        assert!(adapter.get_triggers("items").unwrap().is_empty());
+    }
 }
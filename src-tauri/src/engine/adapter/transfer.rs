@@ -0,0 +1,667 @@
+//! Data Transfer
+//!
+//! Copies tables from one database to another, across dialects. Tables the
+//! target lacks are created from the source schema through `transpile`,
+//! then rows are read in row key order and written in multi-row batches,
+//! converting values the target would store differently (binary columns,
+//! MySQL datetimes).
+//!
+//! Progress is checkpointed to a file after every batch, so an interrupted
+//! transfer picks up after the last row it wrote. Target tables with a
+//! primary key are upserted into, which makes replaying a batch harmless.
+//! Tables without a row key are paged by offset, which assumes the source
+//! isn't written to meanwhile. When the target has no primary key either,
+//! the last batch may have been written without being checkpointed, so such
+//! a table isn't resumed halfway: the transfer has to be restarted.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::bulk::DEFAULT_BATCH_SIZE;
+use super::diff::dependency_order;
+use super::transpile::{self, ExportWarning};
+use super::{AdapterError, AdapterResult, DatabaseAdapter, DialectGenerator, SqlDialect, SqlValue, TableSchema};
+
+/// What to copy and how
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferOptions {
+    /// Tables to copy; every source table when empty
+    #[serde(default)]
+    pub tables: Vec<String>,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Create the tables the target doesn't have yet
+    #[serde(default = "default_create_schema")]
+    pub create_schema: bool,
+    /// Throw away an unfinished transfer's checkpoint and start over
+    #[serde(default)]
+    pub restart: bool,
+}
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH_SIZE
+}
+
+fn default_create_schema() -> bool {
+    true
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            tables: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            create_schema: true,
+            restart: false,
+        }
+    }
+}
+
+/// A transfer's progress, as checkpointed between batches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferState {
+    pub source: SqlDialect,
+    pub target: SqlDialect,
+    /// Tables in copy order: referenced tables before the tables referencing them
+    pub tables: Vec<TableProgress>,
+    /// Whether the tables missing from the target have been created
+    #[serde(default)]
+    pub schema_created: bool,
+    /// Tables this transfer created, whose triggers are added once the rows are in
+    #[serde(default)]
+    pub created_tables: Vec<String>,
+    #[serde(default)]
+    pub warnings: Vec<ExportWarning>,
+}
+
+/// Progress of one table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableProgress {
+    pub table: String,
+    /// Source rows when the transfer started
+    pub total_rows: u64,
+    pub rows_copied: u64,
+    /// Row key of the last row copied; the next batch starts after it
+    #[serde(default)]
+    pub last_key: Option<Vec<Value>>,
+    pub done: bool,
+}
+
+impl TransferState {
+    /// Load a checkpoint, or `None` when there is no unfinished transfer
+    pub fn load(path: &Path) -> AdapterResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| AdapterError::Internal(format!("Failed to read transfer checkpoint: {}", e)))?;
+        serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| AdapterError::Internal(format!("Failed to parse transfer checkpoint: {}", e)))
+    }
+
+    fn save(&self, path: &Path) -> AdapterResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AdapterError::Internal(format!("Failed to create checkpoint dir: {}", e)))?;
+        }
+        let data = serde_json::to_string_pretty(self).map_err(|e| AdapterError::Internal(e.to_string()))?;
+        std::fs::write(path, data)
+            .map_err(|e| AdapterError::Internal(format!("Failed to write transfer checkpoint: {}", e)))
+    }
+
+    /// Rows copied so far across all tables
+    pub fn rows_copied(&self) -> u64 {
+        self.tables.iter().map(|t| t.rows_copied).sum()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.tables.iter().all(|t| t.done)
+    }
+}
+
+/// Where the checkpoint of a transfer between two databases is kept:
+/// `~/.airdb/transfers/<source>--<target>.json`
+pub fn checkpoint_path(source: &str, target: &str) -> PathBuf {
+    let slug = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    };
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".airdb")
+        .join("transfers")
+        .join(format!("{}--{}.json", slug(source), slug(target)))
+}
+
+/// Copy tables from `source` to `target`, resuming from the checkpoint at
+/// `checkpoint` if one is there. `on_progress` is called after every batch.
+/// The checkpoint is removed once every table has been copied.
+pub fn run<F>(
+    source: &dyn DatabaseAdapter,
+    target: &dyn DatabaseAdapter,
+    options: &TransferOptions,
+    checkpoint: &Path,
+    mut on_progress: F,
+) -> AdapterResult<TransferState>
+where
+    F: FnMut(&TableProgress),
+{
+    let resumed = if options.restart { None } else { TransferState::load(checkpoint)? };
+    let mut state = match resumed {
+        Some(state) => {
+            let same_tables = options.tables.is_empty()
+                || options.tables.iter().collect::<HashSet<_>>()
+                    == state.tables.iter().map(|t| &t.table).collect::<HashSet<_>>();
+            if !same_tables || state.source != source.dialect() || state.target != target.dialect() {
+                return Err(AdapterError::Validation(format!(
+                    "An unfinished transfer of other tables is checkpointed at {}; restart it to copy these",
+                    checkpoint.display()
+                )));
+            }
+            state
+        }
+        None => plan(source, target, &options.tables)?,
+    };
+    state.save(checkpoint)?;
+
+    if options.create_schema && !state.schema_created {
+        create_tables(source, target, &mut state)?;
+        state.schema_created = true;
+        state.save(checkpoint)?;
+    }
+
+    let gen = DialectGenerator::new(target.dialect());
+    for i in 0..state.tables.len() {
+        if state.tables[i].done {
+            continue;
+        }
+        let copy = TableCopy::prepare(source, target, &state.tables[i].table, &mut state.warnings)?;
+        copy.check_resumable(&state.tables[i])?;
+        let batch_size = options
+            .batch_size
+            .clamp(1, (gen.max_bind_params() / copy.columns.len()).max(1));
+
+        loop {
+            let copied = copy.copy_batch(source, target, &mut state.tables[i], batch_size)?;
+            state.save(checkpoint)?;
+            on_progress(&state.tables[i]);
+            if copied < batch_size {
+                break;
+            }
+        }
+
+        copy.reset_sequences(target)?;
+        state.tables[i].done = true;
+        state.save(checkpoint)?;
+    }
+
+    if source.dialect() == target.dialect() {
+        for table in &state.created_tables {
+            for trigger in source.get_triggers(table)? {
+                if let Err(e) = target.execute_batch(&trigger.definition) {
+                    state.warnings.push(ExportWarning {
+                        table: table.clone(),
+                        column: None,
+                        message: format!("Trigger {} not created: {}", trigger.name, e),
+                    });
+                }
+            }
+        }
+    }
+
+    std::fs::remove_file(checkpoint)
+        .map_err(|e| AdapterError::Internal(format!("Failed to remove transfer checkpoint: {}", e)))?;
+    Ok(state)
+}
+
+/// Start a fresh transfer of `tables` (every table when empty)
+fn plan(source: &dyn DatabaseAdapter, target: &dyn DatabaseAdapter, tables: &[String]) -> AdapterResult<TransferState> {
+    let existing = source.get_tables()?;
+    let names = if tables.is_empty() {
+        existing
+    } else {
+        if let Some(missing) = tables.iter().find(|t| !existing.contains(t)) {
+            return Err(AdapterError::NotFound(format!("Table {} does not exist in the source", missing)));
+        }
+        tables.to_vec()
+    };
+
+    let schemas: BTreeMap<String, TableSchema> = names
+        .iter()
+        .map(|t| Ok((t.clone(), source.get_table_schema(t)?)))
+        .collect::<AdapterResult<_>>()?;
+    let tables = dependency_order(&schemas)
+        .into_iter()
+        .map(|table| {
+            Ok(TableProgress {
+                total_rows: source.get_row_count(&table)?,
+                table,
+                rows_copied: 0,
+                last_key: None,
+                done: false,
+            })
+        })
+        .collect::<AdapterResult<_>>()?;
+
+    Ok(TransferState {
+        source: source.dialect(),
+        target: target.dialect(),
+        tables,
+        schema_created: false,
+        created_tables: Vec::new(),
+        warnings: Vec::new(),
+    })
+}
+
+/// Create the tables missing from the target, without their triggers so
+/// they don't fire while rows are copied in
+fn create_tables(source: &dyn DatabaseAdapter, target: &dyn DatabaseAdapter, state: &mut TransferState) -> AdapterResult<()> {
+    let existing: HashSet<String> = target.get_tables()?.into_iter().collect();
+    let mut missing = Vec::new();
+    for progress in state.tables.iter().filter(|t| !existing.contains(&t.table)) {
+        let mut schema = source.get_table_schema(&progress.table)?;
+        schema.triggers.clear();
+        missing.push(schema);
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let export = transpile::transpile(&missing, &[], source.dialect(), target.dialect());
+    target.execute_batch(&export.sql)?;
+    state.created_tables = missing.into_iter().map(|t| t.name).collect();
+    state.warnings.extend(export.warnings);
+    Ok(())
+}
+
+/// How one table's rows are read from the source and written to the target
+struct TableCopy {
+    table: String,
+    /// Columns written to the target: non-generated columns both sides have
+    columns: Vec<String>,
+    /// Whether each column holds binary data, read hex-encoded
+    binary: Vec<bool>,
+    target_types: Vec<String>,
+    /// Source row key, empty when rows are paged by offset
+    key: Vec<String>,
+    /// Position of each key column in the select list
+    key_positions: Vec<usize>,
+    select_list: String,
+    /// Target primary key to upsert on, when every key column is copied
+    conflict: Vec<String>,
+    /// Target auto-increment columns whose sequences must catch up
+    auto_increment: Vec<String>,
+}
+
+impl TableCopy {
+    fn prepare(
+        source: &dyn DatabaseAdapter,
+        target: &dyn DatabaseAdapter,
+        table: &str,
+        warnings: &mut Vec<ExportWarning>,
+    ) -> AdapterResult<Self> {
+        let from = source.get_table_schema(table)?;
+        let to = target.get_table_schema(table)?;
+        let gen = DialectGenerator::new(source.dialect());
+
+        let mut copy = TableCopy {
+            table: table.to_string(),
+            columns: Vec::new(),
+            binary: Vec::new(),
+            target_types: Vec::new(),
+            key: Vec::new(),
+            key_positions: Vec::new(),
+            select_list: String::new(),
+            conflict: Vec::new(),
+            auto_increment: Vec::new(),
+        };
+        let mut select = Vec::new();
+        for col in from.columns.iter().filter(|c| c.generated.is_none()) {
+            let Some(target_col) = to.columns.iter().find(|c| c.name == col.name && c.generated.is_none()) else {
+                let warning = ExportWarning {
+                    table: table.to_string(),
+                    column: Some(col.name.clone()),
+                    message: "Not copied: the target table has no such column".to_string(),
+                };
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                continue;
+            };
+
+            let binary = is_binary(&col.col_type);
+            let quoted = gen.quote_ident(&col.name);
+            select.push(if binary {
+                format!("{} AS {}", hex_expr(source.dialect(), &quoted), quoted)
+            } else {
+                quoted
+            });
+            copy.columns.push(col.name.clone());
+            copy.binary.push(binary);
+            copy.target_types.push(target_col.col_type.clone());
+            if target_col.is_auto_increment {
+                copy.auto_increment.push(col.name.clone());
+            }
+        }
+        if copy.columns.is_empty() {
+            return Err(AdapterError::Schema(format!(
+                "None of the columns of {} can be copied to the target",
+                table
+            )));
+        }
+
+        copy.key = match source.row_key_columns(table) {
+            Ok(key) => key,
+            Err(AdapterError::Validation(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        for column in &copy.key {
+            let position = match copy.columns.iter().position(|c| c == column) {
+                Some(position) => position,
+                None => {
+                    select.push(gen.quote_ident(column));
+                    select.len() - 1
+                }
+            };
+            copy.key_positions.push(position);
+        }
+        copy.select_list = select.join(", ");
+
+        let target_pk: Vec<String> = to.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect();
+        if target_pk.iter().all(|c| copy.columns.contains(c)) {
+            copy.conflict = target_pk;
+        }
+        Ok(copy)
+    }
+
+    /// Offset-paged rows upserted into a table without a primary key are
+    /// duplicated when a batch is replayed, so such a table can only be
+    /// copied from the start
+    fn check_resumable(&self, progress: &TableProgress) -> AdapterResult<()> {
+        if progress.rows_copied > 0 && self.key.is_empty() && self.conflict.is_empty() {
+            return Err(AdapterError::Validation(format!(
+                "The transfer of {} can't be resumed: it has no row key or target primary key, so the last batch \
+                 may already be in the target. Empty the table in the target and restart the transfer",
+                progress.table
+            )));
+        }
+        Ok(())
+    }
+
+    /// Copy the next batch and advance `progress`; returns the rows copied
+    fn copy_batch(
+        &self,
+        source: &dyn DatabaseAdapter,
+        target: &dyn DatabaseAdapter,
+        progress: &mut TableProgress,
+        batch_size: usize,
+    ) -> AdapterResult<usize> {
+        let read = DialectGenerator::new(source.dialect());
+        let (sql, params) = self.select_batch(&read, progress, batch_size);
+        let result = source.query(&sql, &params)?;
+        let Some(last) = result.rows.last() else {
+            return Ok(0);
+        };
+
+        let write = DialectGenerator::new(target.dialect());
+        let params: Vec<SqlValue> = result
+            .rows
+            .iter()
+            .flat_map(|row| {
+                (0..self.columns.len()).map(|i| {
+                    convert_value(
+                        row.get(i).unwrap_or(&Value::Null),
+                        self.binary[i],
+                        &self.target_types[i],
+                        target.dialect(),
+                    )
+                })
+            })
+            .collect();
+        let columns: Vec<&str> = self.columns.iter().map(String::as_str).collect();
+        let sql = if self.conflict.is_empty() {
+            write.insert_rows(&self.table, &columns, result.rows.len())
+        } else {
+            let conflict: Vec<&str> = self.conflict.iter().map(String::as_str).collect();
+            write.upsert_rows(&self.table, &columns, result.rows.len(), &conflict)
+        };
+        target.execute(&sql, &params)?;
+
+        progress.rows_copied += result.rows.len() as u64;
+        if !self.key.is_empty() {
+            progress.last_key = Some(
+                self.key_positions
+                    .iter()
+                    .map(|&p| last.get(p).cloned().unwrap_or(Value::Null))
+                    .collect(),
+            );
+        }
+        Ok(result.rows.len())
+    }
+
+    /// The next batch in key order after `progress.last_key`, or by offset
+    /// for tables without a row key
+    fn select_batch(&self, gen: &DialectGenerator, progress: &TableProgress, limit: usize) -> (String, Vec<SqlValue>) {
+        let table = gen.quote_ident(&self.table);
+        if self.key.is_empty() {
+            let sql = format!(
+                "SELECT {} FROM {} LIMIT {} OFFSET {}",
+                self.select_list, table, limit, progress.rows_copied
+            );
+            return (sql, Vec::new());
+        }
+
+        let key: Vec<String> = self.key.iter().map(|c| gen.quote_ident(c)).collect();
+        let mut params = Vec::new();
+        let seek = match &progress.last_key {
+            Some(values) => {
+                // (k0 > v0) OR (k0 = v0 AND k1 > v1) OR ...
+                let mut alternatives = Vec::new();
+                for i in 0..key.len() {
+                    let mut parts = Vec::new();
+                    for (j, column) in key.iter().enumerate().take(i + 1) {
+                        params.push(SqlValue::from(values.get(j).unwrap_or(&Value::Null)));
+                        let op = if j == i { ">" } else { "=" };
+                        parts.push(format!("{} {} {}", column, op, gen.placeholder(params.len())));
+                    }
+                    alternatives.push(format!("({})", parts.join(" AND ")));
+                }
+                format!(" WHERE {}", alternatives.join(" OR "))
+            }
+            None => String::new(),
+        };
+        let sql = format!(
+            "SELECT {} FROM {}{} ORDER BY {} LIMIT {}",
+            self.select_list,
+            table,
+            seek,
+            key.join(", "),
+            limit
+        );
+        (sql, params)
+    }
+
+    /// Rows were inserted with explicit ids, which Postgres sequences don't
+    /// notice; move each past the largest id so new rows don't collide
+    fn reset_sequences(&self, target: &dyn DatabaseAdapter) -> AdapterResult<()> {
+        if target.dialect() != SqlDialect::Postgres {
+            return Ok(());
+        }
+        let gen = DialectGenerator::new(SqlDialect::Postgres);
+        for column in &self.auto_increment {
+            let sql = format!(
+                "SELECT setval(pg_get_serial_sequence($1, $2), COALESCE(MAX({}), 0) + 1, false) FROM {}",
+                gen.quote_ident(column),
+                gen.quote_ident(&self.table)
+            );
+            let params = [SqlValue::Text(gen.quote_ident(&self.table)), SqlValue::Text(column.clone())];
+            target.query(&sql, &params)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_binary(col_type: &str) -> bool {
+    let upper = col_type.to_ascii_uppercase();
+    upper.contains("BLOB") || upper.contains("BYTEA") || upper.contains("BINARY")
+}
+
+/// Binary values come back from the adapters as placeholders, so they are
+/// read hex-encoded and decoded again before writing
+fn hex_expr(dialect: SqlDialect, column: &str) -> String {
+    match dialect {
+        SqlDialect::Sqlite => format!("hex({})", column),
+        SqlDialect::Postgres => format!("encode({}, 'hex')", column),
+        SqlDialect::Mysql => format!("HEX({})", column),
+    }
+}
+
+/// A source value as a parameter for a target column of `target_type`
+fn convert_value(value: &Value, binary: bool, target_type: &str, target: SqlDialect) -> SqlValue {
+    match value {
+        Value::String(s) if binary => match hex::decode(s) {
+            Ok(bytes) => SqlValue::Blob(bytes),
+            Err(_) => SqlValue::Text(s.clone()),
+        },
+        // MySQL DATETIME and TIMESTAMP reject RFC 3339 offsets
+        Value::String(s) if target == SqlDialect::Mysql && is_datetime(target_type) => {
+            match chrono::DateTime::parse_from_rfc3339(s.trim()) {
+                Ok(dt) => SqlValue::Text(dt.naive_utc().format("%Y-%m-%d %H:%M:%S%.f").to_string()),
+                Err(_) => SqlValue::Text(s.clone()),
+            }
+        }
+        other => SqlValue::from(other),
+    }
+}
+
+fn is_datetime(col_type: &str) -> bool {
+    let upper = col_type.to_ascii_uppercase();
+    upper.starts_with("DATETIME") || upper.starts_with("TIMESTAMP")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::sqlite::SqliteAdapter;
+
+    fn source() -> SqliteAdapter {
+        let db = SqliteAdapter::in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             CREATE TABLE posts (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 author_id INTEGER REFERENCES authors(id),
+                 title TEXT,
+                 cover BLOB,
+                 slug TEXT GENERATED ALWAYS AS (lower(title)) VIRTUAL
+             );
+             CREATE TABLE tags (label TEXT);
+             INSERT INTO authors VALUES (1, 'Ada'), (2, 'Grace');
+             INSERT INTO posts (author_id, title, cover) VALUES
+                 (1, 'One', x'00ff10'), (2, 'Two', NULL), (1, 'Three', x'de'),
+                 (2, 'Four', NULL), (1, 'Five', NULL);
+             INSERT INTO tags VALUES ('a'), ('b'), ('c');",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_copies_schema_and_rows_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("transfer.json");
+        let (from, to) = (source(), SqliteAdapter::in_memory().unwrap());
+
+        let options = TransferOptions { batch_size: 2, ..Default::default() };
+        let mut batches = 0;
+        let state = run(&from, &to, &options, &checkpoint, |_| batches += 1).unwrap();
+
+        assert!(state.is_done());
+        assert_eq!(state.rows_copied(), 10);
+        let order: Vec<&str> = state.tables.iter().map(|t| t.table.as_str()).collect();
+        assert!(order.iter().position(|t| *t == "authors") < order.iter().position(|t| *t == "posts"));
+        // posts: 2 + 2 + 1; authors: 2 + 0; tags: 2 + 1
+        assert_eq!(batches, 7);
+        assert!(!checkpoint.exists());
+
+        let copied = to
+            .query("SELECT hex(cover), slug FROM posts WHERE id = 1", &[])
+            .unwrap();
+        assert_eq!(copied.rows[0], vec![Value::from("00FF10"), Value::from("one")]);
+        assert_eq!(to.get_row_count("tags").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_resumes_after_last_checkpointed_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("transfer.json");
+        let (from, to) = (source(), SqliteAdapter::in_memory().unwrap());
+        let options = TransferOptions { tables: vec!["authors".into(), "posts".into()], ..Default::default() };
+
+        // Interrupted after the authors and the first two posts were copied
+        let mut state = plan(&from, &to, &options.tables).unwrap();
+        create_tables(&from, &to, &mut state).unwrap();
+        state.schema_created = true;
+        to.execute_batch(
+            "INSERT INTO authors VALUES (1, 'Ada'), (2, 'Grace');
+             INSERT INTO posts (id, author_id, title) VALUES (1, 1, 'One'), (2, 2, 'Two');",
+        )
+        .unwrap();
+        state.tables[0].rows_copied = 2;
+        state.tables[0].done = true;
+        state.tables[1].rows_copied = 2;
+        state.tables[1].last_key = Some(vec![Value::from(2)]);
+        state.save(&checkpoint).unwrap();
+
+        let state = run(&from, &to, &options, &checkpoint, |_| {}).unwrap();
+        assert_eq!(state.tables[1].rows_copied, 5);
+        assert_eq!(to.get_row_count("posts").unwrap(), 5);
+
+        // A checkpoint for other tables isn't silently reused
+        state.save(&checkpoint).unwrap();
+        let other = TransferOptions { tables: vec!["tags".into()], ..Default::default() };
+        assert!(run(&from, &to, &other, &checkpoint, |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_refuses_to_resume_table_without_key() {
+        let (from, to) = (source(), SqliteAdapter::in_memory().unwrap());
+        to.execute_batch("CREATE TABLE tags (label TEXT)").unwrap();
+        let mut state = plan(&from, &to, &["tags".to_string()]).unwrap();
+        let progress = &mut state.tables[0];
+
+        // SQLite sources always page by rowid; a Postgres or MySQL table
+        // without a primary key has no row key at all
+        let mut copy = TableCopy::prepare(&from, &to, "tags", &mut Vec::new()).unwrap();
+        copy.key.clear();
+        assert!(copy.check_resumable(progress).is_ok());
+        progress.rows_copied = 2;
+        assert!(matches!(copy.check_resumable(progress), Err(AdapterError::Validation(_))));
+
+        // Replaying a batch into a table with a primary key is harmless
+        copy.conflict = vec!["label".to_string()];
+        assert!(copy.check_resumable(progress).is_ok());
+    }
+
+    #[test]
+    fn test_convert_value() {
+        assert!(matches!(
+            convert_value(&Value::from("00ff"), true, "BYTEA", SqlDialect::Postgres),
+            SqlValue::Blob(ref b) if b == &[0, 255]
+        ));
+        assert!(matches!(
+            convert_value(&Value::from("2024-03-01T10:00:00+02:00"), false, "DATETIME", SqlDialect::Mysql),
+            SqlValue::Text(ref s) if s == "2024-03-01 08:00:00"
+        ));
+        assert!(matches!(
+            convert_value(&Value::from("2024-03-01T10:00:00+02:00"), false, "TIMESTAMPTZ", SqlDialect::Postgres),
+            SqlValue::Text(ref s) if s == "2024-03-01T10:00:00+02:00"
+        ));
+    }
+}
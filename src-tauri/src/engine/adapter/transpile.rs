@@ -0,0 +1,483 @@
+//! Schema Transpilation
+//!
+//! Turns a schema read through one adapter into equivalent DDL for another
+//! dialect, e.g. a SQLite prototype into a Postgres schema. Column types are
+//! reduced to the universal names `DialectGenerator::map_type` understands
+//! and mapped from there, and defaults are rewritten where the dialects
+//! spell them differently. Anything that can't be carried over faithfully
+//! is reported as a warning instead of being changed silently.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::diff::normalize_expr;
+use super::{AdapterResult, ColumnSchema, DatabaseAdapter, DialectGenerator, SchemaDiff, SqlDialect, TableSchema, ViewInfo};
+
+/// A schema rendered as DDL for another dialect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaExport {
+    pub source: SqlDialect,
+    pub target: SqlDialect,
+    pub sql: String,
+    pub warnings: Vec<ExportWarning>,
+}
+
+/// Something the export changed or left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportWarning {
+    pub table: String,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Read the full schema through an adapter and render it for `target`
+pub fn export_schema(adapter: &dyn DatabaseAdapter, target: SqlDialect) -> AdapterResult<SchemaExport> {
+    let tables = adapter.get_schema()?;
+    let views = adapter.get_views()?;
+    Ok(transpile(&tables, &views, adapter.dialect(), target))
+}
+
+/// Render tables and views read from a `source` database as DDL for
+/// `target`: tables in dependency order, then indexes, triggers and views.
+/// Trigger bodies and view queries are written in the source's SQL, so
+/// they are only kept when the dialect doesn't change.
+pub fn transpile(tables: &[TableSchema], views: &[ViewInfo], source: SqlDialect, target: SqlDialect) -> SchemaExport {
+    let gen = DialectGenerator::new(target);
+    let mut warnings = Vec::new();
+
+    let tables: Vec<TableSchema> = tables
+        .iter()
+        .map(|table| {
+            let mut table = table.clone();
+            table.row_count = None;
+            if source != target {
+                convert_table(&mut table, source, target, &mut warnings);
+            }
+            table
+        })
+        .collect();
+
+    let mut statements = SchemaDiff::between(&[], &tables).render(&gen);
+    for view in views {
+        if source == target {
+            statements.push(format!(
+                "CREATE VIEW {} AS\n{};",
+                gen.quote_ident(&view.name),
+                view.definition.trim().trim_end_matches(';')
+            ));
+        } else {
+            warnings.push(ExportWarning {
+                table: view.name.clone(),
+                column: None,
+                message: format!("View skipped: its query is written in {} SQL", source),
+            });
+        }
+    }
+
+    SchemaExport {
+        source,
+        target,
+        sql: statements.join("\n\n"),
+        warnings,
+    }
+}
+
+fn convert_table(table: &mut TableSchema, source: SqlDialect, target: SqlDialect, warnings: &mut Vec<ExportWarning>) {
+    let mut warn = |column: Option<&str>, message: String| {
+        warnings.push(ExportWarning {
+            table: table.name.clone(),
+            column: column.map(str::to_string),
+            message,
+        })
+    };
+
+    let indexed: HashSet<&str> = table.indexes.iter().flat_map(|i| i.columns.iter().map(String::as_str)).collect();
+    for col in table.columns.iter_mut() {
+        let mut col_type = convert_type(&col.col_type, target);
+        // MySQL can't key or index TEXT without a prefix length
+        if target == SqlDialect::Mysql
+            && col_type == "TEXT"
+            && (col.is_primary_key || col.is_unique || col.foreign_key.is_some() || indexed.contains(col.name.as_str()))
+        {
+            col_type = "VARCHAR(255)".to_string();
+        }
+        if is_lossy(&col.col_type, &col_type, target) {
+            let from = if col.col_type.trim().is_empty() { "untyped" } else { col.col_type.as_str() };
+            warn(Some(&col.name), format!("{} becomes {}", from, col_type));
+        }
+
+        let (default, note) = convert_default(col, &col_type, source, target);
+        if let Some(note) = note {
+            warn(Some(&col.name), note);
+        }
+        col.default_value = default;
+        col.col_type = col_type;
+
+        if col.generated.is_some() {
+            warn(Some(&col.name), format!("Generation expression copied as written in {} SQL", source));
+        }
+    }
+
+    for check in &table.check_constraints {
+        if check.expression.contains("::") {
+            warn(None, format!("CHECK ({}) uses Postgres casts", check.expression));
+        }
+    }
+    for trigger in table.triggers.drain(..) {
+        warn(None, format!("Trigger {} skipped: its body is written in {} SQL", trigger.name, source));
+    }
+}
+
+/// A column type in the universal names `map_type` understands, from any
+/// dialect's spelling of it. Unknown types are kept as written.
+fn universal_type(ty: &str) -> String {
+    let mut ty = ty.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut strip = |suffix: &str| match ty.to_ascii_uppercase().find(suffix) {
+        Some(at) => {
+            ty.replace_range(at..at + suffix.len(), "");
+            true
+        }
+        None => false,
+    };
+    let array = strip("[]") || strip(" ARRAY");
+    let unsigned = strip(" UNSIGNED");
+    strip(" ZEROFILL");
+    let upper = ty.to_ascii_uppercase();
+
+    // Arguments keep their case, which matters for ENUM values
+    let (base, args) = match (upper.find('('), upper.rfind(')')) {
+        (Some(open), Some(close)) if open < close => (
+            format!("{}{}", upper[..open].trim_end(), &upper[close + 1..]),
+            ty[open..=close].to_string(),
+        ),
+        _ => (upper.clone(), String::new()),
+    };
+
+    let mut universal = match base.as_str() {
+        "INT" | "INTEGER" | "INT4" | "MEDIUMINT" | "SERIAL" | "SERIAL4" => "INTEGER".to_string(),
+        "TINYINT" if args == "(1)" => "BOOLEAN".to_string(),
+        "SMALLINT" | "INT2" | "TINYINT" | "SMALLSERIAL" | "SERIAL2" => "SMALLINT".to_string(),
+        "BIGINT" | "INT8" | "BIGSERIAL" | "SERIAL8" => "BIGINT".to_string(),
+        "BOOL" | "BOOLEAN" => "BOOLEAN".to_string(),
+        "REAL" | "FLOAT4" => "REAL".to_string(),
+        "FLOAT" | "FLOAT8" | "DOUBLE" | "DOUBLE PRECISION" => "DOUBLE".to_string(),
+        "NUMERIC" | "DECIMAL" => format!("DECIMAL{}", args),
+        "VARCHAR" | "CHARACTER VARYING" | "NVARCHAR" | "VARYING CHARACTER" if !args.is_empty() => {
+            format!("VARCHAR{}", args)
+        }
+        "CHAR" | "CHARACTER" | "NCHAR" | "BPCHAR" => format!("CHAR{}", args),
+        "VARCHAR" | "CHARACTER VARYING" | "NVARCHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "CLOB"
+        | "CITEXT" => "TEXT".to_string(),
+        "DATE" => "DATE".to_string(),
+        "TIME" | "TIME WITHOUT TIME ZONE" | "TIMETZ" | "TIME WITH TIME ZONE" => "TIME".to_string(),
+        "DATETIME" | "TIMESTAMP" | "TIMESTAMP WITHOUT TIME ZONE" => "DATETIME".to_string(),
+        "TIMESTAMPTZ" | "TIMESTAMP WITH TIME ZONE" => "TIMESTAMPTZ".to_string(),
+        "BLOB" | "BYTEA" | "BINARY" | "VARBINARY" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" => "BYTEA".to_string(),
+        _ => format!("{}{}", base, args),
+    };
+
+    if unsigned {
+        universal.push_str(" UNSIGNED");
+    }
+    if array {
+        universal.push_str("[]");
+    }
+    universal
+}
+
+/// A column type for the `target` dialect
+fn convert_type(ty: &str, target: SqlDialect) -> String {
+    let gen = DialectGenerator::new(target);
+    let universal = universal_type(ty);
+    let base = universal.split('(').next().unwrap_or_default();
+
+    let mapped = match (target, base) {
+        (_, "") => "TEXT".to_string(),
+        (SqlDialect::Postgres, _) if universal.ends_with("[]") => universal.clone(),
+        (_, _) if universal.ends_with("[]") => gen.map_type("JSON"),
+        // Only MySQL has unsigned integers; widen to keep the range
+        (SqlDialect::Mysql, _) if universal.ends_with(" UNSIGNED") => universal.clone(),
+        (_, "SMALLINT UNSIGNED") => gen.map_type("INTEGER"),
+        (_, "INTEGER UNSIGNED") => gen.map_type("BIGINT"),
+        (_, "BIGINT UNSIGNED") => gen.map_type("DECIMAL(20,0)"),
+        (_, "BOOLEAN UNSIGNED") => gen.map_type("BOOLEAN"),
+        (SqlDialect::Mysql, "ENUM" | "SET") => universal.clone(),
+        (_, "ENUM" | "SET") => "TEXT".to_string(),
+        (SqlDialect::Sqlite, "SMALLINT" | "INTEGER" | "BIGINT") => "INTEGER".to_string(),
+        (SqlDialect::Postgres, "TIMESTAMPTZ") => "TIMESTAMPTZ".to_string(),
+        (_, "TIMESTAMPTZ") => gen.map_type("DATETIME"),
+        _ => gen.map_type(&universal),
+    };
+
+    // Types that imply a default (UUID on Postgres) get it from the column
+    mapped.split(" DEFAULT ").next().unwrap_or_default().to_string()
+}
+
+/// Whether mapping `from` to `to` loses range, precision or meaning
+fn is_lossy(from: &str, to: &str, target: SqlDialect) -> bool {
+    let (from, to) = (universal_type(from), universal_type(to));
+    // SQLite's INTEGER holds any integer up to 64 bits and its REAL is a double
+    fn sqlite_storage(t: &str) -> &str {
+        match t {
+            "SMALLINT" | "INTEGER" | "BIGINT" => "INTEGER",
+            "REAL" | "DOUBLE" => "REAL",
+            other => other,
+        }
+    }
+    from != to && !(target == SqlDialect::Sqlite && sqlite_storage(&from) == sqlite_storage(&to))
+}
+
+/// A column default for the `target` dialect, where the column becomes
+/// `target_type`, with a note when it had to be dropped or couldn't be
+/// translated
+fn convert_default(
+    col: &ColumnSchema,
+    target_type: &str,
+    source: SqlDialect,
+    target: SqlDialect,
+) -> (Option<String>, Option<String>) {
+    let Some(default) = col.default_value.as_deref().filter(|d| !d.trim().is_empty()) else {
+        return (None, None);
+    };
+    // Auto-increment columns get their values from the target's own mechanism
+    if col.is_auto_increment {
+        return (None, None);
+    }
+
+    if universal_type(&col.col_type).ends_with("[]") && target != SqlDialect::Postgres {
+        return (None, Some(format!("Default {} dropped: arrays are Postgres-only", default)));
+    }
+
+    let expr = strip_casts(default);
+    let upper = expr.to_uppercase();
+    let boolean = universal_type(target_type) == "BOOLEAN";
+    let blob_like = matches!(universal_type(target_type).as_str(), "TEXT" | "JSON" | "JSONB" | "BYTEA");
+    let paren = |e: &str| {
+        if target == SqlDialect::Mysql {
+            format!("({})", e)
+        } else {
+            e.to_string()
+        }
+    };
+
+    let converted = match upper.as_str() {
+        "CURRENT_TIMESTAMP" | "CURRENT_TIMESTAMP()" | "NOW()" | "LOCALTIMESTAMP" | "TRANSACTION_TIMESTAMP()"
+        | "DATETIME('NOW')" => "CURRENT_TIMESTAMP".to_string(),
+        "CURRENT_DATE" | "CURDATE()" | "DATE('NOW')" => paren("CURRENT_DATE"),
+        "CURRENT_TIME" | "CURTIME()" | "TIME('NOW')" => paren("CURRENT_TIME"),
+        "TRUE" | "FALSE" => match target {
+            SqlDialect::Sqlite => if upper == "TRUE" { "1" } else { "0" }.to_string(),
+            _ => upper.clone(),
+        },
+        "0" | "1" if boolean && target == SqlDialect::Postgres => {
+            if upper == "1" { "TRUE" } else { "FALSE" }.to_string()
+        }
+        "GEN_RANDOM_UUID()" | "UUID()" | "UUID_GENERATE_V4()" => match target {
+            SqlDialect::Postgres => "gen_random_uuid()".to_string(),
+            SqlDialect::Mysql => "(UUID())".to_string(),
+            SqlDialect::Sqlite => {
+                return (None, Some(format!("Default {} dropped: SQLite can't generate UUIDs", default)));
+            }
+        },
+        "NULL" => return (None, None),
+        _ if upper.starts_with("NEXTVAL(") => {
+            return (None, Some(format!("Default {} dropped: sequences are Postgres-only", default)));
+        }
+        _ if expr.starts_with('\'') || expr.parse::<f64>().is_ok() => {
+            // MySQL only allows literal defaults on TEXT, JSON and BLOB
+            // columns as expressions
+            if blob_like && target == SqlDialect::Mysql {
+                format!("({})", expr)
+            } else {
+                expr.clone()
+            }
+        }
+        _ => {
+            return (
+                Some(expr.clone()),
+                Some(format!("Default {} copied as written in {} SQL", expr, source)),
+            )
+        }
+    };
+
+    (Some(converted), None)
+}
+
+/// A default expression without Postgres `::type` casts or redundant
+/// outer parentheses
+fn strip_casts(expr: &str) -> String {
+    let mut expr = normalize_expr(expr);
+    loop {
+        // The last `::` outside string literals
+        let mut in_string = false;
+        let mut cast = None;
+        let bytes = expr.as_bytes();
+        for i in 0..bytes.len() {
+            match bytes[i] {
+                b'\'' => in_string = !in_string,
+                b':' if !in_string && bytes.get(i + 1) == Some(&b':') => cast = Some(i),
+                _ => {}
+            }
+        }
+        let Some(at) = cast else {
+            return expr;
+        };
+        let type_name = &expr[at + 2..];
+        if !type_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ' ' | '(' | ')' | ',' | '[' | ']' | '"'))
+        {
+            return expr;
+        }
+        expr = normalize_expr(&expr[..at]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::diff::schema_from_sql;
+
+    #[test]
+    fn test_type_mapping() {
+        assert_eq!(convert_type("character varying(255)", SqlDialect::Sqlite), "TEXT");
+        assert_eq!(convert_type("TEXT", SqlDialect::Postgres), "TEXT");
+        assert_eq!(convert_type("DATETIME", SqlDialect::Postgres), "TIMESTAMPTZ");
+        assert_eq!(convert_type("TIMESTAMP WITH TIME ZONE", SqlDialect::Mysql), "DATETIME");
+        assert_eq!(convert_type("TINYINT(1)", SqlDialect::Postgres), "BOOLEAN");
+        assert_eq!(convert_type("INT UNSIGNED", SqlDialect::Postgres), "BIGINT");
+        assert_eq!(convert_type("numeric(10,2)", SqlDialect::Mysql), "DECIMAL(10,2)");
+        assert_eq!(convert_type("UUID", SqlDialect::Postgres), "UUID");
+        assert_eq!(convert_type("INTEGER[]", SqlDialect::Mysql), "JSON");
+        assert_eq!(convert_type("ENUM('a','B')", SqlDialect::Mysql), "ENUM('a','B')");
+        assert_eq!(convert_type("BYTEA", SqlDialect::Mysql), "LONGBLOB");
+        assert_eq!(convert_type("", SqlDialect::Postgres), "TEXT");
+
+        assert!(!is_lossy("BIGINT", "INTEGER", SqlDialect::Sqlite));
+        assert!(!is_lossy("TINYINT(1)", "BOOLEAN", SqlDialect::Postgres));
+        assert!(is_lossy("VARCHAR(64)", "TEXT", SqlDialect::Sqlite));
+        assert!(is_lossy("numeric(10,2)", "REAL", SqlDialect::Sqlite));
+    }
+
+    #[test]
+    fn test_default_translation() {
+        let col = |ty: &str, default: &str| ColumnSchema {
+            name: "c".into(),
+            col_type: ty.into(),
+            nullable: true,
+            default_value: Some(default.into()),
+            is_primary_key: false,
+            is_unique: false,
+            is_auto_increment: false,
+            foreign_key: None,
+            generated: None,
+        };
+        let convert = |ty: &str, default: &str, source, target| {
+            convert_default(&col(ty, default), &convert_type(ty, target), source, target)
+        };
+
+        assert_eq!(
+            convert("TEXT", "'new'::character varying", SqlDialect::Postgres, SqlDialect::Sqlite),
+            (Some("'new'".into()), None)
+        );
+        assert_eq!(
+            convert("TEXT", "datetime('now')", SqlDialect::Sqlite, SqlDialect::Postgres),
+            (Some("CURRENT_TIMESTAMP".into()), None)
+        );
+        assert_eq!(
+            convert("BOOLEAN", "1", SqlDialect::Sqlite, SqlDialect::Postgres),
+            (Some("TRUE".into()), None)
+        );
+        assert_eq!(
+            convert("BOOLEAN", "false", SqlDialect::Postgres, SqlDialect::Sqlite),
+            (Some("0".into()), None)
+        );
+        assert_eq!(
+            convert("TEXT", "'x'", SqlDialect::Sqlite, SqlDialect::Mysql),
+            (Some("('x')".into()), None)
+        );
+        assert!(convert("UUID", "gen_random_uuid()", SqlDialect::Postgres, SqlDialect::Sqlite).1.is_some());
+        assert!(convert("TEXT", "lower('A')", SqlDialect::Sqlite, SqlDialect::Postgres).1.is_some());
+    }
+
+    #[test]
+    fn test_sqlite_to_postgres() {
+        let tables = schema_from_sql(
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY, name VARCHAR(64) NOT NULL, active BOOLEAN DEFAULT 1);
+             CREATE TABLE posts (
+                 id INTEGER PRIMARY KEY,
+                 author_id INTEGER NOT NULL REFERENCES authors (id),
+                 price NUMERIC(10,2),
+                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+             );
+             CREATE INDEX idx_posts_author ON posts (author_id);
+             CREATE TRIGGER posts_touch AFTER UPDATE ON posts BEGIN SELECT 1; END;",
+        )
+        .unwrap();
+        let export = transpile(&tables, &[], SqlDialect::Sqlite, SqlDialect::Postgres);
+
+        assert_eq!(
+            export.sql,
+            "CREATE TABLE \"authors\" (\n  \"id\" SERIAL PRIMARY KEY,\n  \"name\" VARCHAR(64) NOT NULL,\n  \"active\" BOOLEAN DEFAULT TRUE\n);\n\n\
+             CREATE TABLE \"posts\" (\n  \"id\" SERIAL PRIMARY KEY,\n  \"author_id\" INTEGER NOT NULL,\n  \"price\" DECIMAL(10,2),\n  \
+             \"created_at\" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,\n  FOREIGN KEY (\"author_id\") REFERENCES \"authors\" (\"id\")\n);\n\n\
+             CREATE INDEX \"idx_posts_author\" ON \"posts\" (\"author_id\");"
+        );
+        let messages: Vec<&str> = export.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["DATETIME becomes TIMESTAMPTZ", "Trigger posts_touch skipped: its body is written in sqlite SQL"]
+        );
+    }
+
+    #[test]
+    fn test_postgres_shapes_to_mysql() {
+        let table = TableSchema {
+            name: "tags".into(),
+            columns: vec![
+                ColumnSchema {
+                    name: "name".into(),
+                    col_type: "TEXT".into(),
+                    nullable: false,
+                    default_value: None,
+                    is_primary_key: true,
+                    is_unique: false,
+                    is_auto_increment: false,
+                    foreign_key: None,
+                    generated: None,
+                },
+                ColumnSchema {
+                    name: "labels".into(),
+                    col_type: "TEXT[]".into(),
+                    nullable: true,
+                    default_value: Some("'{}'::text[]".into()),
+                    is_primary_key: false,
+                    is_unique: false,
+                    is_auto_increment: false,
+                    foreign_key: None,
+                    generated: None,
+                },
+            ],
+            indexes: vec![],
+            row_count: Some(3),
+            check_constraints: vec![],
+            triggers: vec![],
+        };
+        let export = transpile(&[table], &[], SqlDialect::Postgres, SqlDialect::Mysql);
+
+        assert_eq!(
+            export.sql,
+            "CREATE TABLE `tags` (\n  `name` VARCHAR(255) PRIMARY KEY,\n  `labels` JSON\n);"
+        );
+        let messages: Vec<&str> = export.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "TEXT becomes VARCHAR(255)",
+                "TEXT[] becomes JSON",
+                "Default '{}'::text[] dropped: arrays are Postgres-only",
+            ]
+        );
+    }
+}
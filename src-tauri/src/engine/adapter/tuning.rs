@@ -0,0 +1,186 @@
+//! Connection Tuning
+//!
+//! Pool size and SQLite pragmas for a database, set per project (in
+//! airdb.config.json) or per saved connection. The pragmas and any attached
+//! databases are set up on every new pooled connection, since they only last
+//! for the connection.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use super::{AdapterError, AdapterResult};
+
+/// Pool and pragma settings; unset pragmas keep SQLite's defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTuning {
+    /// Most connections the pool keeps open
+    #[serde(default = "default_pool_size")]
+    pub pool_size: u32,
+    /// How long SQLite waits on a locked database before failing with
+    /// `database is locked`, in milliseconds
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u32,
+    #[serde(default)]
+    pub synchronous: Option<Synchronous>,
+    /// Page cache size: pages when positive, KiB when negative
+    #[serde(default)]
+    pub cache_size: Option<i64>,
+    /// Bytes of the database file to memory-map
+    #[serde(default)]
+    pub mmap_size: Option<u64>,
+    /// Any other pragmas, run after the ones above in name order
+    #[serde(default)]
+    pub pragmas: BTreeMap<String, String>,
+    /// SQLite files attached to every connection, by schema alias. Filled
+    /// in from the project's `database.attach` rather than saved here.
+    #[serde(skip)]
+    pub attach: BTreeMap<String, PathBuf>,
+}
+
+fn default_pool_size() -> u32 {
+    10
+}
+
+fn default_busy_timeout_ms() -> u32 {
+    5000
+}
+
+impl Default for ConnectionTuning {
+    fn default() -> Self {
+        Self {
+            pool_size: default_pool_size(),
+            busy_timeout_ms: default_busy_timeout_ms(),
+            synchronous: None,
+            cache_size: None,
+            mmap_size: None,
+            pragmas: BTreeMap::new(),
+            attach: BTreeMap::new(),
+        }
+    }
+}
+
+/// SQLite's `synchronous` levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+impl fmt::Display for Synchronous {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Synchronous::Off => write!(f, "OFF"),
+            Synchronous::Normal => write!(f, "NORMAL"),
+            Synchronous::Full => write!(f, "FULL"),
+            Synchronous::Extra => write!(f, "EXTRA"),
+        }
+    }
+}
+
+impl ConnectionTuning {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Pool size checks shared by every dialect
+    pub fn validate(&self) -> AdapterResult<()> {
+        if self.pool_size == 0 {
+            return Err(AdapterError::Validation("poolSize must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The statements to run on each new SQLite connection: attachments
+    /// come first, so extra pragmas can be schema-qualified (`ref.cache_size`).
+    /// Foreign keys are always enforced. Extra pragma names must be
+    /// identifiers and values plain words or numbers, so the batch can't
+    /// carry other statements.
+    pub fn sqlite_init_sql(&self) -> AdapterResult<String> {
+        self.validate()?;
+
+        let mut sql = format!(
+            "PRAGMA foreign_keys = ON;\nPRAGMA busy_timeout = {};\n",
+            self.busy_timeout_ms
+        );
+        for (alias, path) in &self.attach {
+            let reserved = alias.eq_ignore_ascii_case("main") || alias.eq_ignore_ascii_case("temp");
+            if !is_identifier(alias) || alias.contains('.') || reserved {
+                return Err(AdapterError::Validation(format!("Invalid alias '{}' for an attached database", alias)));
+            }
+            sql.push_str(&format!(
+                "ATTACH DATABASE '{}' AS \"{}\";\n",
+                path.to_string_lossy().replace('\'', "''"),
+                alias
+            ));
+        }
+        if let Some(synchronous) = self.synchronous {
+            sql.push_str(&format!("PRAGMA synchronous = {};\n", synchronous));
+        }
+        if let Some(cache_size) = self.cache_size {
+            sql.push_str(&format!("PRAGMA cache_size = {};\n", cache_size));
+        }
+        if let Some(mmap_size) = self.mmap_size {
+            sql.push_str(&format!("PRAGMA mmap_size = {};\n", mmap_size));
+        }
+        for (name, value) in &self.pragmas {
+            if !is_identifier(name) {
+                return Err(AdapterError::Validation(format!("Invalid pragma name '{}'", name)));
+            }
+            let is_value = !value.is_empty()
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+            if !is_value {
+                return Err(AdapterError::Validation(format!("Invalid value '{}' for pragma {}", value, name)));
+            }
+            sql.push_str(&format!("PRAGMA {} = {};\n", name, value));
+        }
+        Ok(sql)
+    }
+}
+
+/// Letters, digits, `_` and `.`, not starting with a digit
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_init_sql() {
+        let tuning: ConnectionTuning = serde_json::from_str(
+            r#"{"busyTimeoutMs": 250, "synchronous": "normal", "cacheSize": -20000, "pragmas": {"temp_store": "memory"}}"#,
+        )
+        .unwrap();
+        assert_eq!(tuning.pool_size, 10);
+        assert_eq!(
+            tuning.sqlite_init_sql().unwrap(),
+            "PRAGMA foreign_keys = ON;\nPRAGMA busy_timeout = 250;\nPRAGMA synchronous = NORMAL;\n\
+             PRAGMA cache_size = -20000;\nPRAGMA temp_store = memory;\n"
+        );
+
+        let mut bad = ConnectionTuning::default();
+        bad.pragmas.insert("journal_mode".to_string(), "WAL; DROP TABLE users".to_string());
+        assert!(bad.sqlite_init_sql().is_err());
+        let mut bad = ConnectionTuning::default();
+        bad.pragmas.insert("x = 1; DELETE FROM t; PRAGMA y".to_string(), "1".to_string());
+        assert!(bad.sqlite_init_sql().is_err());
+
+        let mut attached = ConnectionTuning::default();
+        attached.attach.insert("ref".to_string(), PathBuf::from("/data/o'brien.db"));
+        assert!(attached
+            .sqlite_init_sql()
+            .unwrap()
+            .contains("ATTACH DATABASE '/data/o''brien.db' AS \"ref\";"));
+        attached.attach.insert("main".to_string(), PathBuf::from("other.db"));
+        assert!(attached.sqlite_init_sql().is_err());
+    }
+}
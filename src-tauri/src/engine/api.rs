@@ -15,6 +15,13 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 
+use crate::engine::adapter::fts;
+use crate::engine::adapter::keyset::{self, KeysetQuery};
+use crate::engine::adapter::sqlite::SqliteAdapter;
+use crate::engine::adapter::{
+    AdapterError, AdapterResult, ColumnMeta, CountMode, DialectGenerator, FilterExpr, QueryResult,
+    SearchQuery, SortDirection, SortSpec, SqlDialect, SqlValue, ROWID,
+};
 use crate::engine::database::Database;
 
 #[derive(Clone)]
@@ -27,6 +34,7 @@ pub struct ApiState {
     paths(
         list_tables,
         get_table_rows,
+        search_table,
         insert_row,
         update_row,
         delete_row,
@@ -48,6 +56,7 @@ pub fn create_router(state: ApiState) -> Router {
         .route("/api/tables", get(list_tables))
         .route("/api/tables/:table", get(get_table_rows))
         .route("/api/tables/:table", post(insert_row))
+        .route("/api/tables/:table/search", get(search_table))
         .route("/api/tables/:table/:id", put(update_row))
         .route("/api/tables/:table/:id", delete(delete_row))
         .route("/api/health", get(health_check))
@@ -70,6 +79,49 @@ fn validate_identifier(name: &str) -> Result<&str, StatusCode> {
     }
 }
 
+/// A table name, optionally qualified by the alias of an attached database
+/// (`ref.countries`)
+fn validate_table(name: &str) -> Result<&str, StatusCode> {
+    match name.split_once('.') {
+        Some((schema, table)) => validate_identifier(schema).and(validate_identifier(table)).map(|_| name),
+        None => validate_identifier(name),
+    }
+}
+
+/// Build the WHERE condition addressing one row from the `{id}` path segment.
+/// Rows are addressed by primary key; composite keys take comma-separated
+/// values in key order, and tables without a primary key use SQLite's `rowid`.
+fn row_key_condition(
+    db: &Database,
+    table: &str,
+    id: &str,
+    first_param: usize,
+) -> Result<(String, Vec<String>), StatusCode> {
+    let mut key_columns = db
+        .primary_key(table)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if key_columns.is_empty() {
+        key_columns.push("rowid".to_string());
+    }
+
+    let values: Vec<String> = if key_columns.len() == 1 {
+        vec![id.to_string()]
+    } else {
+        id.split(',').map(|v| v.to_string()).collect()
+    };
+    if values.len() != key_columns.len() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let condition = key_columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("\"{}\" = ?{}", c.replace('"', "\"\""), first_param + i))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    Ok((condition, values))
+}
+
 #[derive(Serialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -105,17 +157,83 @@ pub struct QueryParams {
     limit: usize,
     #[serde(default)]
     offset: usize,
+    /// JSON-encoded filter expression, e.g. `{"or":[{"column":"age","operator":"gt","value":30}]}`
+    filter: Option<String>,
+    /// Sort column, prefixed with `-` for descending
+    sort: Option<String>,
+    /// `keyset` to page by cursor instead of offset
+    pagination: Option<String>,
+    /// Cursor from a previous keyset page; implies keyset pagination
+    cursor: Option<String>,
+    /// How keyset pages compute `total`: `exact`, `estimate` or `skip`
+    #[serde(default)]
+    count: CountMode,
 }
 
 fn default_limit() -> usize {
     100
 }
 
+/// Run a query for the REST API, returning blobs base64-encoded
+fn query_sqlite(conn: &rusqlite::Connection, sql: &str, bind: &[SqlValue]) -> AdapterResult<QueryResult> {
+    let start = std::time::Instant::now();
+    let mut stmt = conn.prepare(sql).map_err(|e| AdapterError::Query(e.to_string()))?;
+    let columns: Vec<ColumnMeta> = stmt
+        .column_names()
+        .iter()
+        .map(|name| ColumnMeta {
+            name: name.to_string(),
+            col_type: String::from("TEXT"),
+        })
+        .collect();
+
+    let rows: Vec<Vec<Value>> = stmt
+        .query_map(rusqlite::params_from_iter(bind.iter()), |row| {
+            Ok((0..columns.len())
+                .map(|i| match row.get_ref(i) {
+                    Ok(rusqlite::types::ValueRef::Null) => Value::Null,
+                    Ok(rusqlite::types::ValueRef::Integer(i)) => json!(i),
+                    Ok(rusqlite::types::ValueRef::Real(f)) => json!(f),
+                    Ok(rusqlite::types::ValueRef::Text(t)) => {
+                        json!(String::from_utf8_lossy(t).to_string())
+                    }
+                    Ok(rusqlite::types::ValueRef::Blob(b)) => {
+                        json!(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, b))
+                    }
+                    Err(_) => Value::Null,
+                })
+                .collect())
+        })
+        .map_err(|e| AdapterError::Query(e.to_string()))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| AdapterError::Query(e.to_string()))?;
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+fn adapter_status(error: AdapterError) -> StatusCode {
+    match error {
+        AdapterError::Validation(_) | AdapterError::Query(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/tables/{table}",
     params(
         ("table" = String, Path, description = "Table name"),
+        ("limit" = Option<usize>, Query, description = "Maximum rows to return"),
+        ("offset" = Option<usize>, Query, description = "Rows to skip"),
+        ("filter" = Option<String>, Query, description = "JSON filter expression: conditions {column, operator, value | values} nested in {and: []}, {or: []} and {not: {}}"),
+        ("sort" = Option<String>, Query, description = "Sort column, prefixed with - for descending"),
+        ("pagination" = Option<String>, Query, description = "Set to keyset to page by cursor instead of offset"),
+        ("cursor" = Option<String>, Query, description = "nextCursor or prevCursor from a previous keyset page"),
+        ("count" = Option<String>, Query, description = "Total for keyset pages: exact (default), estimate or skip"),
     ),
     responses(
         (status = 200, description = "Table rows", body = Value)
@@ -127,39 +245,113 @@ async fn get_table_rows(
     Path(table): Path<String>,
     Query(params): Query<QueryParams>,
 ) -> Result<Json<Value>, StatusCode> {
-    let table = validate_identifier(&table)?;
+    let table = validate_table(&table)?;
+    let primary_key = state
+        .db
+        .primary_key(table)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let conn = state.db.get_connection().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    let gen = DialectGenerator::new(SqlDialect::Sqlite);
+
+    let filter: Option<FilterExpr> = match params.filter.as_deref() {
+        Some(raw) => {
+            let filter: FilterExpr = serde_json::from_str(raw).map_err(|_| StatusCode::BAD_REQUEST)?;
+            for column in filter.columns() {
+                validate_identifier(column)?;
+            }
+            Some(filter)
+        }
+        None => None,
+    };
+    let sort = match params.sort.as_deref() {
+        Some(raw) => {
+            let (column, direction) = match raw.strip_prefix('-') {
+                Some(column) => (column, SortDirection::Desc),
+                None => (raw, SortDirection::Asc),
+            };
+            Some(SortSpec {
+                column: validate_identifier(column)?.to_string(),
+                direction,
+            })
+        }
+        None => None,
+    };
+
+    if params.cursor.is_some() || params.pagination.as_deref() == Some("keyset") {
+        // Tables without a primary key are paged (and addressed) by rowid
+        let key_columns = if primary_key.is_empty() {
+            vec![ROWID.to_string()]
+        } else {
+            primary_key
+        };
+        let query = KeysetQuery {
+            table,
+            limit: params.limit,
+            sort: sort.as_ref(),
+            filter: filter.as_ref(),
+            cursor: params.cursor.as_deref(),
+            key_columns: &key_columns,
+        };
+        let page = keyset::fetch_page(&gen, &query, |sql, bind| query_sqlite(&conn, sql, bind))
+            .map_err(adapter_status)?;
+
+        let total = match params.count {
+            CountMode::Exact => Some(
+                keyset::count_rows(&gen, table, filter.as_ref(), |sql, bind| query_sqlite(&conn, sql, bind))
+                    .map_err(adapter_status)?,
+            ),
+            CountMode::Estimate if filter.is_none() => SqliteAdapter::estimate_rows(&conn, table),
+            _ => None,
+        };
+
+        return Ok(Json(json!({
+            "data": page.rows,
+            "count": page.rows.len(),
+            "limit": params.limit,
+            "total": total,
+            "totalIsEstimate": total.is_some() && params.count == CountMode::Estimate,
+            "nextCursor": page.next_cursor,
+            "prevCursor": page.prev_cursor,
+        })));
+    }
+
+    // Tables without a primary key are addressed by rowid, so return it
+    let select_list = if primary_key.is_empty()
+        && conn.prepare(&format!("SELECT rowid FROM {} LIMIT 0", gen.quote_table(table))).is_ok()
+    {
+        "rowid AS rowid, *"
+    } else {
+        "*"
+    };
+    let mut bind: Vec<SqlValue> = Vec::new();
+    let where_clause = match &filter {
+        Some(filter) => {
+            let condition = gen.filter_sql(filter, &mut bind).map_err(|_| StatusCode::BAD_REQUEST)?;
+            format!(" WHERE {}", condition)
+        }
+        None => String::new(),
+    };
+    let order_clause = match &sort {
+        Some(s) => format!(" ORDER BY \"{}\" {}", s.column, s.direction),
+        None => String::new(),
+    };
     let query = format!(
-        "SELECT * FROM \"{}\" LIMIT ?1 OFFSET ?2",
-        table
+        "SELECT {} FROM {}{}{} LIMIT ?{} OFFSET ?{}",
+        select_list,
+        gen.quote_table(table),
+        where_clause,
+        order_clause,
+        bind.len() + 1,
+        bind.len() + 2
     );
+    bind.push(SqlValue::Integer(params.limit as i64));
+    bind.push(SqlValue::Integer(params.offset as i64));
 
-    let mut stmt = conn.prepare(&query).map_err(|_| StatusCode::BAD_REQUEST)?;
-    let column_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
-    
-    let rows: Vec<Value> = stmt
-        .query_map(rusqlite::params![params.limit as i64, params.offset as i64], |row| {
-            let mut obj = serde_json::Map::new();
-            for (i, col_name) in column_names.iter().enumerate() {
-                let value: Value = match row.get_ref(i) {
-                    Ok(rusqlite::types::ValueRef::Null) => Value::Null,
-                    Ok(rusqlite::types::ValueRef::Integer(i)) => json!(i),
-                    Ok(rusqlite::types::ValueRef::Real(f)) => json!(f),
-                    Ok(rusqlite::types::ValueRef::Text(t)) => {
-                        json!(String::from_utf8_lossy(t).to_string())
-                    }
-                    Ok(rusqlite::types::ValueRef::Blob(b)) => {
-                        json!(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, b))
-                    }
-                    Err(_) => Value::Null,
-                };
-                obj.insert(col_name.clone(), value);
-            }
-            Ok(Value::Object(obj))
-        })
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .filter_map(|r| r.ok())
+    let result = query_sqlite(&conn, &query, &bind).map_err(adapter_status)?;
+    let rows: Vec<Value> = result
+        .rows
+        .into_iter()
+        .map(|values| Value::Object(result.columns.iter().map(|c| c.name.clone()).zip(values).collect()))
         .collect();
 
     Ok(Json(json!({
@@ -170,6 +362,72 @@ async fn get_table_rows(
     })))
 }
 
+#[derive(Deserialize)]
+pub struct SearchParams {
+    /// Search terms
+    q: String,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    /// Treat `q` as an FTS5 query expression
+    #[serde(default)]
+    raw: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tables/{table}/search",
+    params(
+        ("table" = String, Path, description = "Table name"),
+        ("q" = String, Query, description = "Search terms; the last one also matches as a prefix"),
+        ("limit" = Option<usize>, Query, description = "Maximum hits to return"),
+        ("offset" = Option<usize>, Query, description = "Hits to skip"),
+        ("raw" = Option<bool>, Query, description = "Treat q as an FTS5 query expression (AND, OR, NEAR, column:term)"),
+    ),
+    responses(
+        (status = 200, description = "Ranked hits with highlighted snippets", body = Value),
+        (status = 404, description = "Table has no full-text index")
+    ),
+    tag = "rows"
+)]
+async fn search_table(
+    State(state): State<ApiState>,
+    Path(table): Path<String>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Value>, StatusCode> {
+    let table = validate_table(&table)?;
+    let primary_key = state
+        .db
+        .primary_key(table)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let key_columns = if primary_key.is_empty() {
+        vec![ROWID.to_string()]
+    } else {
+        primary_key
+    };
+    let conn = state.db.get_connection().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut query = SearchQuery::new(params.q);
+    query.limit = params.limit;
+    query.offset = params.offset;
+    query.raw = params.raw;
+
+    let result = fts::search(table, &key_columns, &query, |sql, bind| query_sqlite(&conn, sql, bind))
+        .map_err(|e| match e {
+            AdapterError::NotFound(_) => StatusCode::NOT_FOUND,
+            other => adapter_status(other),
+        })?;
+
+    Ok(Json(json!({
+        "data": result.hits,
+        "count": result.hits.len(),
+        "total": result.total_count,
+        "limit": params.limit,
+        "offset": params.offset
+    })))
+}
+
 #[utoipa::path(
     post,
     path = "/api/tables/{table}",
@@ -187,7 +445,7 @@ async fn insert_row(
     Path(table): Path<String>,
     Json(body): Json<Value>,
 ) -> Result<(StatusCode, Json<Value>), StatusCode> {
-    let table = validate_identifier(&table)?;
+    let table = validate_table(&table)?;
     let obj = body.as_object().ok_or(StatusCode::BAD_REQUEST)?;
     
     // Validate all column names
@@ -198,24 +456,17 @@ async fn insert_row(
     
     let col_names: Vec<String> = columns.iter().map(|c| format!("\"{}\"", c)).collect();
     let query = format!(
-        "INSERT INTO \"{}\" ({}) VALUES ({})",
-        table,
+        "INSERT INTO {} ({}) VALUES ({})",
+        DialectGenerator::new(SqlDialect::Sqlite).quote_table(table),
         col_names.join(", "),
         placeholders.join(", ")
     );
 
     let conn = state.db.get_connection().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let values: Vec<String> = obj.values().map(|v| {
-        match v {
-            Value::String(s) => s.clone(),
-            _ => v.to_string(),
-        }
-    }).collect();
+    let values: Vec<SqlValue> = obj.values().map(SqlValue::from).collect();
 
-    let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
-    
-    conn.execute(&query, params.as_slice())
+    conn.execute(&query, rusqlite::params_from_iter(values))
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
     let last_id = conn.last_insert_rowid();
@@ -231,7 +482,7 @@ async fn insert_row(
     path = "/api/tables/{table}/{id}",
     params(
         ("table" = String, Path, description = "Table name"),
-        ("id" = i64, Path, description = "Row ID"),
+        ("id" = String, Path, description = "Primary key value; comma-separated in key order for composite keys, rowid for tables without one"),
     ),
     request_body = Value,
     responses(
@@ -241,10 +492,10 @@ async fn insert_row(
 )]
 async fn update_row(
     State(state): State<ApiState>,
-    Path((table, id)): Path<(String, i64)>,
+    Path((table, id)): Path<(String, String)>,
     Json(body): Json<Value>,
 ) -> Result<Json<Value>, StatusCode> {
-    let table = validate_identifier(&table)?;
+    let table = validate_table(&table)?;
     let obj = body.as_object().ok_or(StatusCode::BAD_REQUEST)?;
     
     let set_clauses: Vec<String> = obj
@@ -254,27 +505,21 @@ async fn update_row(
             validate_identifier(k.as_str()).map(|validated| format!("\"{}\" = ?{}", validated, i + 1))
         })
         .collect::<Result<Vec<_>, _>>()?;
+    let (key_condition, key_values) = row_key_condition(&state.db, table, &id, obj.len() + 1)?;
     
     let query = format!(
-        "UPDATE \"{}\" SET {} WHERE id = ?{}",
-        table,
+        "UPDATE {} SET {} WHERE {}",
+        DialectGenerator::new(SqlDialect::Sqlite).quote_table(table),
         set_clauses.join(", "),
-        obj.len() + 1
+        key_condition
     );
 
     let conn = state.db.get_connection().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let mut values: Vec<String> = obj.values().map(|v| {
-        match v {
-            Value::String(s) => s.clone(),
-            _ => v.to_string(),
-        }
-    }).collect();
-    values.push(id.to_string());
-
-    let params: Vec<&dyn rusqlite::ToSql> = values.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
+    let mut values: Vec<SqlValue> = obj.values().map(SqlValue::from).collect();
+    values.extend(key_values.into_iter().map(SqlValue::Text));
 
-    let affected = conn.execute(&query, params.as_slice())
+    let affected = conn.execute(&query, rusqlite::params_from_iter(values))
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
     Ok(Json(json!({
@@ -288,7 +533,7 @@ async fn update_row(
     path = "/api/tables/{table}/{id}",
     params(
         ("table" = String, Path, description = "Table name"),
-        ("id" = i64, Path, description = "Row ID"),
+        ("id" = String, Path, description = "Primary key value; comma-separated in key order for composite keys, rowid for tables without one"),
     ),
     responses(
         (status = 200, description = "Row deleted", body = Value)
@@ -297,13 +542,18 @@ async fn update_row(
 )]
 async fn delete_row(
     State(state): State<ApiState>,
-    Path((table, id)): Path<(String, i64)>,
+    Path((table, id)): Path<(String, String)>,
 ) -> Result<Json<Value>, StatusCode> {
+    let table = validate_table(&table)?;
+    let (key_condition, key_values) = row_key_condition(&state.db, table, &id, 1)?;
+
     let conn = state.db.get_connection().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let table = validate_identifier(&table)?;
-    let query = format!("DELETE FROM \"{}\" WHERE id = ?1", table);
-    let affected = conn.execute(&query, [id])
+    let query = format!(
+        "DELETE FROM {} WHERE {}",
+        DialectGenerator::new(SqlDialect::Sqlite).quote_table(table),
+        key_condition
+    );
+    let affected = conn.execute(&query, rusqlite::params_from_iter(key_values))
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
     Ok(Json(json!({
@@ -311,3 +561,49 @@ async fn delete_row(
         "message": "Row deleted successfully"
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_sqlite_reports_row_errors() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE VIRTUAL TABLE docs USING fts5(body); INSERT INTO docs VALUES ('hello');")
+            .unwrap();
+
+        let found = query_sqlite(&conn, "SELECT body FROM docs WHERE docs MATCH ?1", &[SqlValue::Text("hello".into())]);
+        assert_eq!(found.unwrap().rows.len(), 1);
+        // A malformed FTS5 expression only fails once rows are stepped
+        let malformed = query_sqlite(&conn, "SELECT body FROM docs WHERE docs MATCH ?1", &[SqlValue::Text("\"unclosed".into())]);
+        assert!(matches!(malformed, Err(AdapterError::Query(_))));
+    }
+
+    #[tokio::test]
+    async fn test_row_values_bound_by_json_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute_batch("CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, views INTEGER, draft BOOLEAN)")
+            .unwrap();
+        let state = ApiState { db: Arc::new(db) };
+
+        let row = json!({"title": "Hello", "views": 3, "draft": true});
+        let (_, Json(created)) = insert_row(State(state.clone()), Path("posts".to_string()), Json(row)).await.unwrap();
+        assert_eq!(created["id"], json!(1));
+        let update = json!({"title": null, "views": 4, "draft": false});
+        let Json(updated) = update_row(State(state.clone()), Path(("posts".to_string(), "1".to_string())), Json(update))
+            .await
+            .unwrap();
+        assert_eq!(updated["affected"], json!(1));
+
+        let conn = state.db.get_connection().unwrap();
+        let stored: (Option<String>, String, i64, String) = conn
+            .query_row("SELECT title, typeof(views), views, typeof(draft) FROM posts", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))
+            })
+            .unwrap();
+        assert_eq!(stored, (None, "integer".to_string(), 4, "integer".to_string()));
+    }
+}
@@ -117,6 +117,60 @@ pub enum Commands {
 
     /// Display detailed project information
     Info,
+
+    /// Export the schema as DDL for another database engine
+    Export {
+        /// Target dialect: sqlite, postgres or mysql
+        #[arg(short, long)]
+        dialect: String,
+
+        /// Schema to export: a .sql file, a SQLite database file, a connection
+        /// URL or a connection name (defaults to the project database)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Write the DDL to a file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show how the database would run a query, flagging full table scans
+    Explain {
+        /// The SQL statement to explain
+        sql: String,
+
+        /// Database to plan against: a SQLite database file, a connection URL
+        /// or a connection name (defaults to the project database)
+        #[arg(long)]
+        from: Option<String>,
+    },
+
+    /// Copy tables from one database to another, across engines
+    Transfer {
+        /// Source: a SQLite database file, a connection URL or a connection name
+        #[arg(long)]
+        from: String,
+
+        /// Target: a SQLite database file, a connection URL or a connection name
+        #[arg(long)]
+        to: String,
+
+        /// Tables to copy (comma-separated; defaults to every table)
+        #[arg(short, long, value_delimiter = ',')]
+        tables: Vec<String>,
+
+        /// Rows per INSERT statement
+        #[arg(long, default_value = "500")]
+        batch_size: usize,
+
+        /// Don't create tables missing from the target
+        #[arg(long)]
+        no_create: bool,
+
+        /// Discard an interrupted transfer's progress and start over
+        #[arg(long)]
+        restart: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -128,10 +182,20 @@ pub enum MigrateAction {
     },
 
     /// Run pending migrations and push to GitHub
-    Push,
+    Push {
+        /// Apply the pending migrations to a scratch copy of the database
+        /// and report what they would do, without touching the database
+        #[arg(long)]
+        dry_run: bool,
+    },
 
-    /// Check migration status
-    Check,
+    /// Check migration status, including applied files edited or deleted
+    /// since they were applied
+    Check {
+        /// Record the current checksums of edited migration files
+        #[arg(long)]
+        repair: bool,
+    },
 
     /// Rollback last N migrations
     Rollback {
@@ -142,6 +206,34 @@ pub enum MigrateAction {
 
     /// List migration history
     List,
+
+    /// Write a migration from schema changes: those made to the database
+    /// since sql/schema.sql was written, or those a schema file declares
+    Generate {
+        /// Migration name
+        name: String,
+
+        /// Declarative schema (.sql) to take the database to, instead of
+        /// capturing changes already made to it
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+
+    /// Show the DDL that turns one schema into another
+    Diff {
+        /// Schema to change: a .sql file, a SQLite database file, a connection
+        /// URL or a connection name (defaults to the project database)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Schema to reach, in the same forms (defaults to sql/schema.sql)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Dialect of the generated DDL (defaults to the --from database's)
+        #[arg(long)]
+        dialect: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
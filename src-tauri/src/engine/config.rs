@@ -2,9 +2,12 @@
 //! Handles loading and validating airdb.config.json
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use super::adapter::ConnectionTuning;
+
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("Config file not found: {0}")]
@@ -23,6 +26,10 @@ pub struct Config {
     pub api: ApiConfig,
     #[serde(default)]
     pub github: Option<GitHubConfig>,
+    /// Connection URLs shared with the project, by name. Secrets belong in
+    /// `${VAR}` placeholders, which are filled in when connecting.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub connections: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +43,27 @@ pub struct DatabaseConfig {
     #[serde(rename = "type")]
     pub db_type: String,
     pub path: PathBuf,
+    /// Pool size and pragmas for the project database
+    #[serde(default, skip_serializing_if = "ConnectionTuning::is_default")]
+    pub tuning: ConnectionTuning,
+    /// Extra SQLite files attached to the project database, by schema alias;
+    /// their tables are named `alias.table`. Relative paths are resolved
+    /// against the project directory.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attach: BTreeMap<String, PathBuf>,
+}
+
+impl DatabaseConfig {
+    /// Settings to open the project database with, attached files included
+    pub fn connection_tuning(&self, project_dir: &Path) -> ConnectionTuning {
+        let mut tuning = self.tuning.clone();
+        tuning.attach = self
+            .attach
+            .iter()
+            .map(|(alias, path)| (alias.clone(), project_dir.join(path)))
+            .collect();
+        tuning
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,12 +116,15 @@ impl Config {
             database: DatabaseConfig {
                 db_type: "sqlite".to_string(),
                 path: PathBuf::from("./data/airdb.db"),
+                tuning: ConnectionTuning::default(),
+                attach: BTreeMap::new(),
             },
             api: ApiConfig {
                 port: default_port(),
                 host: default_host(),
             },
             github: None,
+            connections: BTreeMap::new(),
         }
     }
 }
@@ -4,9 +4,13 @@
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::params;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use crate::engine::adapter::fts::{hide_fts_tables, FtsIndex};
+use crate::engine::adapter::sqlite::{schema_and_name, schemas, sqlite_master};
+use crate::engine::adapter::tuning::ConnectionTuning;
+
 pub type DbPool = Pool<SqliteConnectionManager>;
 pub type DbConnection = PooledConnection<SqliteConnectionManager>;
 
@@ -18,6 +22,8 @@ pub enum DatabaseError {
     SqliteError(#[from] rusqlite::Error),
     #[error("Database file not found: {0}")]
     NotFound(String),
+    #[error("Invalid database settings: {0}")]
+    InvalidSettings(String),
 }
 
 #[derive(Clone)]
@@ -27,14 +33,23 @@ pub struct Database {
 
 impl Database {
     pub fn new(db_path: &Path) -> Result<Self, DatabaseError> {
+        Self::with_tuning(db_path, &ConnectionTuning::default())
+    }
+
+    /// Open with the project's pool size and pragmas, applied to every
+    /// pooled connection
+    pub fn with_tuning(db_path: &Path, tuning: &ConnectionTuning) -> Result<Self, DatabaseError> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
 
-        let manager = SqliteConnectionManager::file(db_path);
+        let init_sql = tuning
+            .sqlite_init_sql()
+            .map_err(|e| DatabaseError::InvalidSettings(e.to_string()))?;
+        let manager = SqliteConnectionManager::file(db_path).with_init(move |conn| conn.execute_batch(&init_sql));
         let pool = Pool::builder()
-            .max_size(10)
+            .max_size(tuning.pool_size)
             .build(manager)?;
 
         let db = Self { pool };
@@ -43,7 +58,7 @@ impl Database {
     }
 
     pub fn in_memory() -> Result<Self, DatabaseError> {
-        let manager = SqliteConnectionManager::memory();
+        let manager = SqliteConnectionManager::memory().with_init(|conn| conn.execute_batch("PRAGMA foreign_keys=ON"));
         let pool = Pool::builder()
             .max_size(1)
             .build(manager)?;
@@ -68,8 +83,6 @@ impl Database {
 
         // Enable WAL mode for better concurrency (PRAGMA returns result, use query)
         let _: String = conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))?;
-        // Foreign keys PRAGMA doesn't return value in same way
-        conn.execute_batch("PRAGMA foreign_keys=ON")?;
 
         Ok(())
     }
@@ -96,40 +109,151 @@ impl Database {
         Ok(())
     }
 
-    pub fn get_tables(&self) -> Result<Vec<String>, DatabaseError> {
+    /// Applied migrations with the checksum recorded for each, in the order
+    /// they were applied
+    pub fn get_applied_checksums(&self) -> Result<Vec<(String, String)>, DatabaseError> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE '_airdb_%' AND name NOT LIKE 'sqlite_%'"
+        let mut stmt = conn.prepare("SELECT name, checksum FROM _airdb_migrations ORDER BY id")?;
+        let migrations = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        Ok(migrations)
+    }
+
+    pub fn update_migration_checksum(&self, name: &str, checksum: &str) -> Result<(), DatabaseError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE _airdb_migrations SET checksum = ?2 WHERE name = ?1",
+            params![name, checksum],
         )?;
-        let tables = stmt
-            .query_map([], |row| row.get(0))?
-            .collect::<Result<Vec<String>, _>>()?;
+        Ok(())
+    }
+
+    /// Tables of the main database, then those of attached databases as
+    /// `alias.table`
+    pub fn get_tables(&self) -> Result<Vec<String>, DatabaseError> {
+        let conn = self.get_connection()?;
+        let mut tables = Vec::new();
+        for schema in schemas(&conn)? {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT name FROM {} m WHERE type='table' AND name NOT LIKE '_airdb_%' AND name NOT LIKE 'sqlite_%' AND {}",
+                sqlite_master(&schema),
+                hide_fts_tables(&schema)
+            ))?;
+            let names = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            tables.extend(names.into_iter().map(|name| match schema.as_str() {
+                "main" => name,
+                alias => format!("{}.{}", alias, name),
+            }));
+        }
         Ok(tables)
     }
 
     pub fn get_table_schema(&self, table_name: &str) -> Result<Vec<ColumnInfo>, DatabaseError> {
         let conn = self.get_connection()?;
-        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
+        // table_xinfo also lists generated columns; hidden = 1 marks the
+        // hidden columns of virtual tables
+        let mut stmt = conn.prepare("SELECT * FROM pragma_table_xinfo(?1, ?2)")?;
+        let (schema, name) = schema_and_name(table_name);
         let columns = stmt
-            .query_map([], |row| {
-                Ok(ColumnInfo {
-                    cid: row.get(0)?,
-                    name: row.get(1)?,
-                    col_type: row.get(2)?,
-                    notnull: row.get(3)?,
-                    dflt_value: row.get(4)?,
-                    pk: row.get(5)?,
-                })
+            .query_map([name, schema], |row| {
+                Ok((
+                    row.get::<_, i32>(6)?,
+                    ColumnInfo {
+                        cid: row.get(0)?,
+                        name: row.get(1)?,
+                        col_type: row.get(2)?,
+                        notnull: row.get(3)?,
+                        dflt_value: row.get(4)?,
+                        pk: row.get(5)?,
+                    },
+                ))
             })?
+            .filter(|r| !matches!(r, Ok((1, _))))
+            .map(|r| r.map(|(_, column)| column))
             .collect::<Result<Vec<ColumnInfo>, _>>()?;
         Ok(columns)
     }
 
+    /// The `CREATE TABLE` statement SQLite stored for a table
+    pub fn table_sql(&self, table_name: &str) -> Result<String, DatabaseError> {
+        let conn = self.get_connection()?;
+        let (schema, name) = schema_and_name(table_name);
+        let sql = conn.query_row(
+            &format!("SELECT sql FROM {} WHERE type='table' AND name = ?1", sqlite_master(schema)),
+            [name],
+            |row| row.get(0),
+        )?;
+        Ok(sql)
+    }
+
+    /// Views or triggers (`kind`) in creation order, with the SQL that
+    /// created them. Triggers maintaining FTS indexes are left out, like
+    /// the index tables themselves.
+    pub fn get_schema_objects(&self, kind: &str) -> Result<Vec<SchemaObject>, DatabaseError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, tbl_name, sql FROM sqlite_master
+             WHERE type = ?1 AND name NOT LIKE '_airdb_%' AND sql IS NOT NULL
+             ORDER BY rowid",
+        )?;
+        let objects = stmt
+            .query_map([kind], |row| {
+                Ok(SchemaObject {
+                    name: row.get(0)?,
+                    table: row.get(1)?,
+                    sql: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<SchemaObject>, _>>()?;
+        Ok(objects
+            .into_iter()
+            .filter(|o| !FtsIndex::is_sync_trigger(&o.table, &o.name))
+            .collect())
+    }
+
+    /// Primary key column names of a table, in key order
+    pub fn primary_key(&self, table_name: &str) -> Result<Vec<String>, DatabaseError> {
+        let conn = self.get_connection()?;
+        let mut stmt =
+            conn.prepare("SELECT name FROM pragma_table_info(?1, ?2) WHERE pk > 0 ORDER BY pk")?;
+        let (schema, name) = schema_and_name(table_name);
+        let columns = stmt
+            .query_map([name, schema], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(columns)
+    }
+
     pub fn backup(&self, backup_path: &Path) -> Result<(), DatabaseError> {
         let conn = self.get_connection()?;
         conn.execute(&format!("VACUUM INTO '{}'", backup_path.display()), [])?;
         Ok(())
     }
+
+    /// Copy the database and each attached one into `dir` as `<schema>.db`.
+    /// Returns the copy of the main database and the tuning that attaches
+    /// the other copies under their usual aliases.
+    pub fn copy_into(&self, dir: &Path) -> Result<(PathBuf, ConnectionTuning), DatabaseError> {
+        let conn = self.get_connection()?;
+        let mut tuning = ConnectionTuning { pool_size: 1, ..ConnectionTuning::default() };
+        for schema in schemas(&conn)? {
+            let path = dir.join(format!("{}.db", schema));
+            conn.execute(
+                &format!(
+                    "VACUUM \"{}\" INTO '{}'",
+                    schema,
+                    path.to_string_lossy().replace('\'', "''")
+                ),
+                [],
+            )?;
+            if schema != "main" {
+                tuning.attach.insert(schema, path);
+            }
+        }
+        Ok((dir.join("main.db"), tuning))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -141,3 +265,12 @@ pub struct ColumnInfo {
     pub dflt_value: Option<String>,
     pub pk: bool,
 }
+
+/// A view or trigger as stored in `sqlite_master`
+#[derive(Debug, Clone)]
+pub struct SchemaObject {
+    pub name: String,
+    /// The table a trigger is attached to; the view's own name for views
+    pub table: String,
+    pub sql: String,
+}
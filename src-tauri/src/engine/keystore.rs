@@ -1,9 +1,12 @@
 //! AirDB Keystore Module
-//! Encrypted storage for API keys and GitHub tokens
+//! Encrypted storage for API keys, GitHub tokens and connection passwords
 
 use keyring::Entry;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use uuid::Uuid;
@@ -20,6 +23,8 @@ pub enum KeystoreError {
     SerdeError(#[from] serde_json::Error),
     #[error("Key not found: {0}")]
     NotFound(String),
+    #[error("Encryption error: {0}")]
+    CryptoError(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,12 +51,24 @@ impl Default for ApiKeyStore {
 
 pub struct Keystore {
     project_dir: PathBuf,
+    /// Whether connection secrets may go to the OS keyring
+    use_keyring: bool,
 }
 
 impl Keystore {
     pub fn new(project_dir: &Path) -> Self {
         Self {
             project_dir: project_dir.to_path_buf(),
+            use_keyring: true,
+        }
+    }
+
+    /// A keystore that keeps connection secrets in the encrypted secrets
+    /// file only, leaving the OS keyring alone
+    pub fn file_only(project_dir: &Path) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            use_keyring: false,
         }
     }
 
@@ -84,6 +101,163 @@ impl Keystore {
         Ok(())
     }
 
+    /// Store a saved connection's password. It goes to the OS keyring when
+    /// one is available, and to the encrypted secrets file otherwise.
+    pub fn store_connection_secret(&self, connection_id: &str, secret: &str) -> Result<(), KeystoreError> {
+        let account = Self::connection_account(connection_id);
+        if self.use_keyring && Self::keyring_store(&account, secret) {
+            // Drop any copy an earlier keyring-less run left in the file
+            return self.remove_file_secret(&account);
+        }
+
+        let mut secrets = self.load_file_secrets()?;
+        secrets.insert(account.clone(), self.seal(&account, secret)?);
+        self.save_file_secrets(&secrets)
+    }
+
+    /// Look up a saved connection's password, from the keyring or the
+    /// encrypted secrets file
+    pub fn get_connection_secret(&self, connection_id: &str) -> Result<Option<String>, KeystoreError> {
+        let account = Self::connection_account(connection_id);
+        if self.use_keyring {
+            if let Ok(secret) = Entry::new(SERVICE_NAME, &account).and_then(|entry| entry.get_password()) {
+                return Ok(Some(secret));
+            }
+        }
+
+        match self.load_file_secrets()?.get(&account) {
+            Some(sealed) => self.open(&account, sealed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Forget a saved connection's password wherever it is stored
+    pub fn delete_connection_secret(&self, connection_id: &str) -> Result<(), KeystoreError> {
+        let account = Self::connection_account(connection_id);
+        if self.use_keyring {
+            if let Ok(entry) = Entry::new(SERVICE_NAME, &account) {
+                let _ = entry.delete_credential();
+            }
+        }
+        self.remove_file_secret(&account)
+    }
+
+    fn connection_account(connection_id: &str) -> String {
+        format!("connection:{}", connection_id)
+    }
+
+    /// Write a secret to the OS keyring, reporting whether it stuck. Without
+    /// a platform store the keyring crate falls back to an in-memory mock
+    /// that forgets on every new entry, so the secret is read back to check.
+    fn keyring_store(account: &str, secret: &str) -> bool {
+        let stored = Entry::new(SERVICE_NAME, account).and_then(|entry| entry.set_password(secret));
+        stored.is_ok()
+            && Entry::new(SERVICE_NAME, account)
+                .and_then(|entry| entry.get_password())
+                .is_ok_and(|read| read == secret)
+    }
+
+    fn secrets_path(&self) -> PathBuf {
+        self.project_dir.join(".airdb").join("secrets.json")
+    }
+
+    fn load_file_secrets(&self) -> Result<BTreeMap<String, String>, KeystoreError> {
+        let path = self.secrets_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    fn save_file_secrets(&self, secrets: &BTreeMap<String, String>) -> Result<(), KeystoreError> {
+        let path = self.secrets_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(secrets)?)?;
+        Ok(())
+    }
+
+    fn remove_file_secret(&self, account: &str) -> Result<(), KeystoreError> {
+        let mut secrets = self.load_file_secrets()?;
+        if secrets.remove(account).is_some() {
+            self.save_file_secrets(&secrets)?;
+        }
+        Ok(())
+    }
+
+    /// The key for the secrets file, created on first use and readable only
+    /// by the current user
+    fn file_key(&self) -> Result<LessSafeKey, KeystoreError> {
+        let path = self.project_dir.join(".airdb").join("secrets.key");
+        let bytes = if path.exists() {
+            std::fs::read(&path)?
+        } else {
+            let mut bytes = vec![0u8; AES_256_GCM.key_len()];
+            SystemRandom::new()
+                .fill(&mut bytes)
+                .map_err(|_| KeystoreError::CryptoError("no random source".to_string()))?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            // Created with its final permissions, so the key is never readable
+            // by others, and never over a key another process just wrote
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            match options.open(&path) {
+                Ok(mut file) => {
+                    use std::io::Write;
+                    file.write_all(&bytes)?;
+                    bytes
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => std::fs::read(&path)?,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        let key = UnboundKey::new(&AES_256_GCM, &bytes)
+            .map_err(|_| KeystoreError::CryptoError(format!("invalid key in {}", path.display())))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    /// Encrypt a secret, bound to its account name; the result is the
+    /// base64 of nonce followed by ciphertext
+    fn seal(&self, account: &str, secret: &str) -> Result<String, KeystoreError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| KeystoreError::CryptoError("no random source".to_string()))?;
+
+        let mut data = secret.as_bytes().to_vec();
+        self.file_key()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(account.as_bytes()), &mut data)
+            .map_err(|_| KeystoreError::CryptoError("could not encrypt secret".to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(data);
+        Ok(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, sealed))
+    }
+
+    fn open(&self, account: &str, sealed: &str) -> Result<String, KeystoreError> {
+        let unreadable = || KeystoreError::CryptoError(format!("secret for {} can't be decrypted", account));
+        let mut data = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, sealed)
+            .map_err(|_| unreadable())?;
+        if data.len() < NONCE_LEN {
+            return Err(unreadable());
+        }
+        let (nonce, ciphertext) = data.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| unreadable())?;
+        let plain = self
+            .file_key()?
+            .open_in_place(nonce, Aad::from(account.as_bytes()), ciphertext)
+            .map_err(|_| unreadable())?;
+        String::from_utf8(plain.to_vec()).map_err(|_| unreadable())
+    }
+
     /// Generate a new API key and store it
     pub fn create_api_key(&self, name: &str, role: &str) -> Result<(String, ApiKey), KeystoreError> {
         let raw_key = format!("airdb_{}_{}", role, Uuid::new_v4());
@@ -1,11 +1,20 @@
 //! AirDB Migrations Module
 //! Handles migration file generation, execution, and rollback
 
+use crate::engine::adapter::sqlite::SqliteAdapter;
+use crate::engine::adapter::diff::schema_from_sql;
+use crate::engine::adapter::{
+    guard, AdapterError, DatabaseAdapter, DialectGenerator, SchemaDiff, SqlDialect, TableSchema,
+};
 use crate::engine::database::{Database, DatabaseError};
 use chrono::Utc;
+use rusqlite::params;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -20,16 +29,86 @@ pub enum MigrationError {
     NotFound(String),
     #[error("Schema drift detected: {0}")]
     SchemaDrift(String),
+    #[error("Foreign key violation: {0}")]
+    ForeignKeyViolation(String),
+    #[error("No down migration for {0}: add a `-- down` section or a .down.sql file")]
+    MissingDown(String),
     #[error("SQL execution error: {0}")]
     SqlError(#[from] rusqlite::Error),
+    #[error("{0}")]
+    Adapter(#[from] AdapterError),
+    #[error("Apply pending migrations first: {0}")]
+    Pending(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct Migration {
     pub name: String,
     pub path: PathBuf,
+    /// The whole file, which the checksum covers
     pub sql: String,
     pub checksum: String,
+    /// What `push` runs: the file without its `-- down` section
+    pub up: String,
+    /// What `rollback` runs, if the migration can be undone
+    pub down: Option<String>,
+}
+
+impl Migration {
+    /// Read a migration file. Its down script is the `-- down` section, or
+    /// else a `<name>.down.sql` file next to it.
+    fn load(path: &Path) -> Result<Self, MigrationError> {
+        let sql = fs::read_to_string(path)?;
+        let (up, down) = split_sections(&sql);
+        let down = match down {
+            Some(down) => Some(down),
+            None => fs::read_to_string(path.with_extension("down.sql"))
+                .ok()
+                .filter(|sql| has_statements(sql)),
+        };
+        Ok(Self {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            checksum: MigrationRunner::compute_checksum(&sql),
+            sql,
+            up,
+            down,
+        })
+    }
+}
+
+/// Split a migration into its `-- up` and `-- down` sections (markers are
+/// case-insensitive). Without markers the whole file is the up section; a
+/// down section with nothing but comments counts as missing.
+fn split_sections(sql: &str) -> (String, Option<String>) {
+    let mut up = String::new();
+    let mut down: Option<String> = None;
+    for line in sql.lines() {
+        let marker = line
+            .trim()
+            .strip_prefix("--")
+            .map(|rest| rest.trim().trim_end_matches(':').to_lowercase());
+        match marker.as_deref() {
+            Some("up") => continue,
+            Some("down") => {
+                down.get_or_insert_with(String::new);
+                continue;
+            }
+            _ => {}
+        }
+        let section = down.as_mut().unwrap_or(&mut up);
+        section.push_str(line);
+        section.push('\n');
+    }
+    (up, down.filter(|sql| has_statements(sql)))
+}
+
+/// Whether SQL has anything besides blank lines and `--` comments
+fn has_statements(sql: &str) -> bool {
+    sql.lines().any(|line| {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with("--")
+    })
 }
 
 pub struct MigrationRunner {
@@ -44,6 +123,80 @@ impl MigrationRunner {
     }
 
     pub fn create(&self, name: &str) -> Result<PathBuf, MigrationError> {
+        self.write_migration(
+            name,
+            "-- up\n-- Write your SQL here\n\n-- down\n-- Write the SQL that undoes it here, for `airdb migrate rollback`\n",
+        )
+    }
+
+    /// Write a migration with generated SQL and apply it right away, for
+    /// schema changes made from the app. The file is removed again if the
+    /// SQL fails, so only working migrations end up in version control.
+    pub fn create_and_apply(&self, db: &Database, name: &str, sql: &str) -> Result<Migration, MigrationError> {
+        let path = self.write_migration(name, sql)?;
+        let migration = Migration::load(&path)?;
+        if let Err(e) = self.apply(db, &migration) {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+        Ok(migration)
+    }
+
+    /// Generate a migration for changes made to the database directly since
+    /// `sql/schema.sql` was written, taking the snapshot's schema to the
+    /// database's. The database already has the changes, so the migration
+    /// is recorded as applied. None when they match.
+    pub fn generate_from_database(&self, db: &Database, name: &str) -> Result<Option<Migration>, MigrationError> {
+        self.ensure_no_pending(db)?;
+        let snapshot = match fs::read_to_string(self.migrations_dir.with_file_name("schema.sql")) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(MigrationError::NotFound("sql/schema.sql; run `airdb migrate push` to write it".to_string()))
+            }
+            result => result?,
+        };
+        let current = snapshot_sql(&*db.get_connection()?)?;
+        let Some(migration) = self.write_generated(name, &snapshot, &current)? else {
+            return Ok(None);
+        };
+        db.record_migration(&migration.name, &migration.checksum)?;
+        Ok(Some(migration))
+    }
+
+    /// Generate a migration taking the database to the schema declared in a
+    /// SQL file. It is left pending, for `push`. None when they match.
+    pub fn generate_from_schema(&self, db: &Database, name: &str, schema_file: &Path) -> Result<Option<Migration>, MigrationError> {
+        self.ensure_no_pending(db)?;
+        let current = snapshot_sql(&*db.get_connection()?)?;
+        self.write_generated(name, &current, &fs::read_to_string(schema_file)?)
+    }
+
+    /// Generation diffs against the database as it is, which is only right
+    /// once the migrations before the new one have run
+    fn ensure_no_pending(&self, db: &Database) -> Result<(), MigrationError> {
+        let pending = self.list_pending(db)?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let names: Vec<&str> = pending.iter().map(|m| m.name.as_str()).collect();
+        Err(MigrationError::Pending(names.join(", ")))
+    }
+
+    /// Write a migration whose up section takes the schema `from_sql`
+    /// creates to the one `to_sql` creates, and whose down section goes back
+    fn write_generated(&self, name: &str, from_sql: &str, to_sql: &str) -> Result<Option<Migration>, MigrationError> {
+        let from = load_schema(from_sql)?;
+        let to = load_schema(to_sql)?;
+        let up = generated_sql(&from, &to);
+        if up.is_empty() {
+            return Ok(None);
+        }
+        let down = generated_sql(&to, &from);
+        let path = self.write_migration(name, &format!("-- up\n{}\n\n-- down\n{}\n", up, down))?;
+        Ok(Some(Migration::load(&path)?))
+    }
+
+    /// Write a new timestamped migration file, headed with its name
+    fn write_migration(&self, name: &str, body: &str) -> Result<PathBuf, MigrationError> {
         fs::create_dir_all(&self.migrations_dir)?;
 
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
@@ -51,13 +204,13 @@ impl MigrationRunner {
         let filename = format!("{}_{}.sql", timestamp, safe_name);
         let path = self.migrations_dir.join(&filename);
 
-        let template = format!(
-            "-- Migration: {}\n-- Created: {}\n\n-- Write your SQL here\n",
+        let content = format!(
+            "-- Migration: {}\n-- Created: {}\n\n{}",
             name,
-            Utc::now().to_rfc3339()
+            Utc::now().to_rfc3339(),
+            body
         );
-
-        fs::write(&path, template)?;
+        fs::write(&path, content)?;
         Ok(path)
     }
 
@@ -72,6 +225,8 @@ impl MigrationRunner {
         let mut entries: Vec<_> = fs::read_dir(&self.migrations_dir)?
             .filter_map(|e| e.ok())
             .filter(|e| e.path().extension().map_or(false, |ext| ext == "sql"))
+            // Down scripts belong to the migration of the same name
+            .filter(|e| !e.file_name().to_string_lossy().ends_with(".down.sql"))
             .collect();
 
         entries.sort_by_key(|e| e.file_name());
@@ -79,14 +234,7 @@ impl MigrationRunner {
         for entry in entries {
             let name = entry.file_name().to_string_lossy().to_string();
             if !applied.contains(&name) {
-                let sql = fs::read_to_string(entry.path())?;
-                let checksum = Self::compute_checksum(&sql);
-                pending.push(Migration {
-                    name,
-                    path: entry.path(),
-                    sql,
-                    checksum,
-                });
+                pending.push(Migration::load(&entry.path())?);
             }
         }
 
@@ -94,25 +242,168 @@ impl MigrationRunner {
     }
 
     pub fn apply(&self, db: &Database, migration: &Migration) -> Result<(), MigrationError> {
+        Self::run_in_transaction(db, &migration.name, &[&migration.up], |conn| {
+            conn.execute(
+                "INSERT INTO _airdb_migrations (name, applied_at, checksum) VALUES (?1, datetime('now'), ?2)",
+                params![migration.name, migration.checksum],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// Undo the last `count` applied migrations, newest first, in a single
+    /// transaction that also removes them from `_airdb_migrations`. Nothing
+    /// runs unless every one of them has a down script and is unchanged
+    /// since it was applied, as the down script may not undo what ran.
+    pub fn rollback(&self, db: &Database, count: usize) -> Result<Vec<String>, MigrationError> {
+        let applied = db.get_applied_migrations()?;
+        let targets: Vec<&String> = applied.iter().rev().take(count).collect();
+        let drift: Vec<MigrationDrift> =
+            self.verify(db)?.into_iter().filter(|d| targets.contains(&&d.name)).collect();
+        if !drift.is_empty() {
+            return Err(Self::drift_error(&drift));
+        }
+
+        let mut migrations = Vec::new();
+        for name in targets {
+            let path = self.migrations_dir.join(name);
+            if !path.is_file() {
+                return Err(MigrationError::NotFound(name.clone()));
+            }
+            let migration = Migration::load(&path)?;
+            if migration.down.is_none() {
+                return Err(MigrationError::MissingDown(name.clone()));
+            }
+            migrations.push(migration);
+        }
+        if migrations.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let scripts: Vec<&str> = migrations.iter().filter_map(|m| m.down.as_deref()).collect();
+        let names: Vec<&str> = migrations.iter().map(|m| m.name.as_str()).collect();
+        Self::run_in_transaction(db, &format!("Rolling back {}", names.join(", ")), &scripts, |conn| {
+            for name in &names {
+                conn.execute("DELETE FROM _airdb_migrations WHERE name = ?1", [name])?;
+            }
+            Ok(())
+        })?;
+        Ok(names.into_iter().map(String::from).collect())
+    }
+
+    /// Run migration scripts in one transaction, updating the journal with
+    /// `record` on the same connection before committing
+    fn run_in_transaction(
+        db: &Database,
+        label: &str,
+        scripts: &[&str],
+        record: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<()>,
+    ) -> Result<(), MigrationError> {
         let conn = db.get_connection()?;
 
+        // Table rebuilds (see adapter::diff) turn foreign keys off, which
+        // SQLite ignores inside a transaction, so do it before opening one
+        // and check the references ourselves before committing
+        let disables_fks = scripts.iter().any(|sql| Self::disables_foreign_keys(sql));
+        if disables_fks {
+            conn.execute_batch("PRAGMA foreign_keys=OFF")?;
+        }
+
         // Execute in a transaction
         conn.execute("BEGIN TRANSACTION", [])?;
 
-        match conn.execute_batch(&migration.sql) {
-            Ok(_) => {
-                db.record_migration(&migration.name, &migration.checksum)?;
-                conn.execute("COMMIT", [])?;
-                Ok(())
-            }
-            Err(e) => {
-                conn.execute("ROLLBACK", [])?;
-                Err(MigrationError::SqlError(e))
+        let result = scripts
+            .iter()
+            .try_for_each(|sql| conn.execute_batch(sql))
+            .and_then(|_| record(&conn))
+            .and_then(|_| {
+                if !disables_fks {
+                    return Ok(None);
+                }
+                let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+                let violation = stmt
+                    .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(2)?)))?
+                    .next()
+                    .transpose()?;
+                Ok(violation)
+            });
+
+        let outcome = match result {
+            Ok(None) => conn.execute_batch("COMMIT").map_err(MigrationError::from),
+            Ok(Some((table, parent))) => Err(MigrationError::ForeignKeyViolation(format!(
+                "{} leaves rows in {} referencing missing rows in {}",
+                label, table, parent
+            ))),
+            Err(e) => Err(MigrationError::SqlError(e)),
+        };
+        // Whatever happened, the connection goes back to the pool outside a
+        // transaction (unless the script already ended it) and with foreign
+        // keys enforced
+        if outcome.is_err() && !conn.is_autocommit() {
+            conn.execute_batch("ROLLBACK").ok();
+        }
+        if disables_fks {
+            conn.execute_batch("PRAGMA foreign_keys=ON")?;
+        }
+        outcome
+    }
+
+    /// Whether migration SQL switches foreign key enforcement off
+    fn disables_foreign_keys(sql: &str) -> bool {
+        sql.lines().any(|line| {
+            let line: String = line.split_whitespace().collect::<String>().to_lowercase();
+            line.starts_with("pragmaforeign_keys=off") || line.starts_with("pragmaforeign_keys=0")
+        })
+    }
+
+    /// Applied migrations whose files were edited or deleted since they
+    /// were applied, found by re-hashing each file
+    pub fn verify(&self, db: &Database) -> Result<Vec<MigrationDrift>, MigrationError> {
+        let mut drift = Vec::new();
+        for (name, checksum) in db.get_applied_checksums()? {
+            let path = self.migrations_dir.join(&name);
+            let kind = match fs::read_to_string(&path) {
+                Ok(sql) if Self::compute_checksum(&sql) == checksum => continue,
+                Ok(_) => DriftKind::Edited,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => DriftKind::Deleted,
+                Err(e) => return Err(e.into()),
+            };
+            drift.push(MigrationDrift { name, kind });
+        }
+        Ok(drift)
+    }
+
+    /// Accept the current contents of edited migration files by recording
+    /// their new checksums. Deleted files can't be repaired this way; they
+    /// have to be restored.
+    pub fn repair(&self, db: &Database) -> Result<Vec<String>, MigrationError> {
+        let mut repaired = Vec::new();
+        for drift in self.verify(db)? {
+            if drift.kind == DriftKind::Edited {
+                let sql = fs::read_to_string(self.migrations_dir.join(&drift.name))?;
+                db.update_migration_checksum(&drift.name, &Self::compute_checksum(&sql))?;
+                repaired.push(drift.name);
             }
         }
+        Ok(repaired)
+    }
+
+    /// Apply the pending migrations to a scratch copy of the database and
+    /// report what they would do, leaving the real database untouched
+    pub fn dry_run(&self, db: &Database) -> Result<DryRunReport, MigrationError> {
+        let pending = self.list_pending(db)?;
+        let scripts: Vec<(&str, &str)> = pending.iter().map(|m| (m.name.as_str(), m.up.as_str())).collect();
+        dry_run(db, &scripts)
     }
 
+    /// Apply pending migrations in order. Refuses while any applied
+    /// migration has drifted, since the database may no longer match the
+    /// files.
     pub fn push(&self, db: &Database) -> Result<Vec<String>, MigrationError> {
+        let drift = self.verify(db)?;
+        if !drift.is_empty() {
+            return Err(Self::drift_error(&drift));
+        }
         let pending = self.list_pending(db)?;
         let mut applied = Vec::new();
 
@@ -124,44 +415,36 @@ impl MigrationRunner {
         Ok(applied)
     }
 
+    fn drift_error(drift: &[MigrationDrift]) -> MigrationError {
+        let files: Vec<String> = drift.iter().map(|d| format!("{} was {}", d.name, d.kind)).collect();
+        MigrationError::SchemaDrift(format!(
+            "{}; restore the applied files or run `airdb migrate check --repair`",
+            files.join(", ")
+        ))
+    }
+
     pub fn check(&self, db: &Database) -> Result<MigrationStatus, MigrationError> {
         let applied = db.get_applied_migrations()?;
         let pending = self.list_pending(db)?;
+        // A snapshot committed with pending migrations is ahead of the
+        // database, so it's only compared once they are applied
+        let schema_drift = if pending.is_empty() { self.schema_drift(db)? } else { Vec::new() };
 
         Ok(MigrationStatus {
             applied_count: applied.len(),
             pending_count: pending.len(),
             pending_migrations: pending.iter().map(|m| m.name.clone()).collect(),
+            drift: self.verify(db)?,
+            schema_drift,
         })
     }
 
+    /// Write `sql/schema.sql`: the statements SQLite stored for every table,
+    /// index, view and trigger, which recreate the schema exactly. Objects
+    /// are sorted by kind and name so the same schema always gives the same
+    /// file.
     pub fn generate_schema_snapshot(&self, db: &Database, project_dir: &Path) -> Result<PathBuf, MigrationError> {
-        let tables = db.get_tables()?;
-        let mut schema = String::new();
-
-        schema.push_str("-- AirDB Schema Snapshot\n");
-        schema.push_str(&format!("-- Generated: {}\n\n", Utc::now().to_rfc3339()));
-
-        for table in tables {
-            let columns = db.get_table_schema(&table)?;
-            schema.push_str(&format!("CREATE TABLE {} (\n", table));
-
-            for (i, col) in columns.iter().enumerate() {
-                let null_str = if col.notnull { " NOT NULL" } else { "" };
-                let pk_str = if col.pk { " PRIMARY KEY" } else { "" };
-                let default_str = col.dflt_value.as_ref()
-                    .map(|d| format!(" DEFAULT {}", d))
-                    .unwrap_or_default();
-                let comma = if i < columns.len() - 1 { "," } else { "" };
-                
-                schema.push_str(&format!(
-                    "  {} {}{}{}{}{}\n",
-                    col.name, col.col_type, null_str, pk_str, default_str, comma
-                ));
-            }
-
-            schema.push_str(");\n\n");
-        }
+        let schema = snapshot_sql(&*db.get_connection()?)?;
 
         let schema_path = project_dir.join("sql").join("schema.sql");
         fs::create_dir_all(schema_path.parent().unwrap())?;
@@ -170,6 +453,42 @@ impl MigrationRunner {
         Ok(schema_path)
     }
 
+    /// Compare the database with `sql/schema.sql` and list the objects that
+    /// differ, such as tables altered outside migrations. Empty when there
+    /// is no snapshot yet.
+    pub fn schema_drift(&self, db: &Database) -> Result<Vec<SnapshotDrift>, MigrationError> {
+        let schema_path = self.migrations_dir.with_file_name("schema.sql");
+        let snapshot = match fs::read_to_string(&schema_path) {
+            Ok(sql) => sql,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let expected = load_schema(&snapshot)?.objects;
+        let conn = db.get_connection()?;
+        let actual = snapshot_objects(&conn)?;
+
+        let normalize = |sql: &str| sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut drift = Vec::new();
+        for object in &actual {
+            let change = match expected.iter().find(|o| o.kind == object.kind && o.name == object.name) {
+                None => SnapshotChange::Added,
+                Some(o) if normalize(&o.sql) != normalize(&object.sql) => SnapshotChange::Changed,
+                Some(_) => continue,
+            };
+            drift.push(SnapshotDrift { kind: object.kind.clone(), name: object.name.clone(), change });
+        }
+        for object in &expected {
+            if !actual.iter().any(|o| o.kind == object.kind && o.name == object.name) {
+                drift.push(SnapshotDrift {
+                    kind: object.kind.clone(),
+                    name: object.name.clone(),
+                    change: SnapshotChange::Removed,
+                });
+            }
+        }
+        Ok(drift)
+    }
+
     fn compute_checksum(sql: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(sql.as_bytes());
@@ -183,4 +502,471 @@ pub struct MigrationStatus {
     pub applied_count: usize,
     pub pending_count: usize,
     pub pending_migrations: Vec<String>,
+    /// Applied migrations whose files no longer match
+    pub drift: Vec<MigrationDrift>,
+    /// Objects where the database differs from `sql/schema.sql`
+    pub schema_drift: Vec<SnapshotDrift>,
+}
+
+/// An applied migration whose file differs from what was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationDrift {
+    pub name: String,
+    pub kind: DriftKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftKind {
+    /// The file's checksum no longer matches the recorded one
+    Edited,
+    /// The file is gone
+    Deleted,
+}
+
+impl std::fmt::Display for DriftKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DriftKind::Edited => write!(f, "edited"),
+            DriftKind::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+/// A table, index, view or trigger as SQLite stored it
+struct SnapshotObject {
+    kind: String,
+    name: String,
+    sql: String,
+}
+
+/// The schema objects a snapshot holds, by kind then name. Internal tables
+/// and the shadow tables of full-text indexes are left out, since SQLite
+/// and the indexes create them.
+fn snapshot_objects(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<SnapshotObject>> {
+    let mut stmt = conn.prepare(
+        "SELECT type, name, sql FROM sqlite_master m
+         WHERE type IN ('table', 'index', 'view', 'trigger') AND sql IS NOT NULL
+           AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_airdb_%' AND tbl_name NOT LIKE '_airdb_%'
+           AND NOT EXISTS (
+             SELECT 1 FROM sqlite_master v
+             WHERE v.type = 'table' AND v.sql LIKE 'CREATE VIRTUAL TABLE%USING fts5%'
+               AND m.name IN (v.name || '_data', v.name || '_idx', v.name || '_docsize',
+                              v.name || '_config', v.name || '_content'))
+         ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 WHEN 'view' THEN 2 ELSE 3 END, name",
+    )?;
+    let objects = stmt
+        .query_map([], |row| {
+            Ok(SnapshotObject {
+                kind: row.get(0)?,
+                name: row.get(1)?,
+                sql: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(objects)
+}
+
+/// A schema object that differs between the database and `sql/schema.sql`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDrift {
+    /// `table`, `index`, `view` or `trigger`
+    pub kind: String,
+    pub name: String,
+    pub change: SnapshotChange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotChange {
+    /// Only in the database
+    Added,
+    /// Only in the snapshot
+    Removed,
+    /// Defined differently in the database
+    Changed,
+}
+
+impl std::fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotChange::Added => write!(f, "added"),
+            SnapshotChange::Removed => write!(f, "removed"),
+            SnapshotChange::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// The snapshot of a schema: every object's statement, by kind then name
+fn snapshot_sql(conn: &rusqlite::Connection) -> rusqlite::Result<String> {
+    let mut schema = String::from("-- AirDB Schema Snapshot\n-- Written after migrations run; do not edit by hand\n\n");
+    for object in snapshot_objects(conn)? {
+        schema.push_str(object.sql.trim_end().trim_end_matches(';'));
+        schema.push_str(";\n\n");
+    }
+    Ok(schema)
+}
+
+/// A schema read back from SQL by running it against an in-memory database
+struct LoadedSchema {
+    tables: Vec<TableSchema>,
+    objects: Vec<SnapshotObject>,
+}
+
+fn load_schema(sql: &str) -> Result<LoadedSchema, MigrationError> {
+    // Loading lets SQLite store the statements the same way it stored the
+    // database's
+    let conn = rusqlite::Connection::open_in_memory()?;
+    conn.execute_batch(sql)?;
+    Ok(LoadedSchema {
+        tables: schema_from_sql(sql)?,
+        objects: snapshot_objects(&conn)?,
+    })
+}
+
+/// DDL from one schema to another: the table plan of `SchemaDiff`, which
+/// rebuilds SQLite tables where ALTER TABLE can't make a change, with
+/// changed views dropped before it and created after it
+fn generated_sql(from: &LoadedSchema, to: &LoadedSchema) -> String {
+    let gen = DialectGenerator::new(SqlDialect::Sqlite);
+    let views = |schema: &LoadedSchema| -> BTreeMap<String, String> {
+        schema
+            .objects
+            .iter()
+            .filter(|o| o.kind == "view")
+            .map(|o| (o.name.clone(), o.sql.split_whitespace().collect::<Vec<_>>().join(" ")))
+            .collect()
+    };
+    let (from_views, to_views) = (views(from), views(to));
+
+    let mut stmts: Vec<String> = from_views
+        .iter()
+        .filter(|(name, sql)| to_views.get(*name) != Some(sql))
+        .map(|(name, _)| format!("DROP VIEW {};", gen.quote_ident(name)))
+        .collect();
+    stmts.extend(SchemaDiff::between(&from.tables, &to.tables).render(&gen));
+    stmts.extend(
+        to.objects
+            .iter()
+            .filter(|o| o.kind == "view" && from_views.get(&o.name) != to_views.get(&o.name))
+            .map(|o| format!("{};", o.sql.trim_end().trim_end_matches(';'))),
+    );
+    stmts.join("\n\n")
+}
+
+/// Run named migration scripts, in order, against a copy of the database
+/// (attached databases included) made with `VACUUM INTO`. Each script runs
+/// in its own transaction as `push` would run it, and the run stops at the
+/// first failure. The copy is deleted afterwards.
+pub fn dry_run(db: &Database, scripts: &[(&str, &str)]) -> Result<DryRunReport, MigrationError> {
+    let dir = std::env::temp_dir().join(format!("airdb-dry-run-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir)?;
+    let report = dry_run_in(&dir, db, scripts);
+    fs::remove_dir_all(&dir).ok();
+    report
+}
+
+fn dry_run_in(dir: &Path, db: &Database, scripts: &[(&str, &str)]) -> Result<DryRunReport, MigrationError> {
+    let (path, tuning) = db.copy_into(dir)?;
+    let scratch = Database::with_tuning(&path, &tuning)?;
+    let adapter = SqliteAdapter::with_tuning(&path, &tuning)?;
+    let before = adapter.get_schema()?;
+
+    let mut report = DryRunReport::default();
+    for (name, sql) in scripts {
+        let start = Instant::now();
+        let result = MigrationRunner::run_in_transaction(&scratch, name, &[sql], |_| Ok(()));
+        let duration_ms = start.elapsed().as_millis() as u64;
+        match result {
+            Ok(()) => report.migrations.push(DryRunStep { name: name.to_string(), duration_ms }),
+            Err(e) => {
+                report.failure = Some(DryRunFailure {
+                    migration: name.to_string(),
+                    statement: failing_statement(&scratch, sql)?,
+                    error: e.to_string(),
+                });
+                break;
+            }
+        }
+    }
+
+    let after = adapter.get_schema()?;
+    let diff = SchemaDiff::between(&before, &after);
+    report.schema_changes = diff.changes.iter().map(ToString::to_string).collect();
+    report.destructive = diff.is_destructive();
+    let counts = |tables: &[TableSchema]| -> BTreeMap<String, Option<u64>> {
+        tables.iter().map(|t| (t.name.clone(), t.row_count)).collect()
+    };
+    let (before, after) = (counts(&before), counts(&after));
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for table in names {
+        let (rows_before, rows_after) = (before.get(table).copied().flatten(), after.get(table).copied().flatten());
+        if rows_before != rows_after {
+            report.row_counts.push(RowCountChange {
+                table: table.clone(),
+                before: rows_before,
+                after: rows_after,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Replay a failed script statement by statement, in a transaction that
+/// is rolled back, to find the statement that fails. None when every
+/// statement runs on its own, as with a foreign key check failing at the end.
+fn failing_statement(db: &Database, sql: &str) -> Result<Option<String>, MigrationError> {
+    let conn = db.get_connection()?;
+    conn.execute_batch("BEGIN")?;
+    let failed = guard::classify(sql)
+        .into_iter()
+        .find(|statement| conn.execute_batch(&statement.sql).is_err())
+        .map(|statement| statement.sql);
+    // The script may have ended the transaction itself
+    conn.execute_batch("ROLLBACK").ok();
+    Ok(failed)
+}
+
+/// What applying migrations would do, found on a scratch copy
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport {
+    /// Migrations that applied, in order, with their timings
+    pub migrations: Vec<DryRunStep>,
+    /// The migration that failed, which ends the run
+    pub failure: Option<DryRunFailure>,
+    /// How the schema changed, one line per change
+    pub schema_changes: Vec<String>,
+    /// Whether any schema change can lose data
+    pub destructive: bool,
+    /// Tables whose row count changed, including created and dropped ones
+    pub row_counts: Vec<RowCountChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunStep {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunFailure {
+    pub migration: String,
+    /// The statement that failed, when one did on its own
+    pub statement: Option<String>,
+    pub error: String,
+}
+
+/// Rows in a table before and after; None where the table doesn't exist
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowCountChange {
+    pub table: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::adapter::ConnectionTuning;
+    use tempfile::tempdir;
+
+    fn write(runner: &MigrationRunner, name: &str, sql: &str) {
+        fs::create_dir_all(&runner.migrations_dir).unwrap();
+        fs::write(runner.migrations_dir.join(name), sql).unwrap();
+    }
+
+    #[test]
+    fn test_push_and_rollback() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        write(&runner, "001_users.sql", "-- Up\nCREATE TABLE users (id INTEGER PRIMARY KEY);\n\n-- Down\nDROP TABLE users;\n");
+        write(&runner, "002_posts.sql", "CREATE TABLE posts (id INTEGER PRIMARY KEY);\n");
+        write(&runner, "002_posts.down.sql", "DROP TABLE posts;\n");
+        write(&runner, "003_tags.sql", "-- up\nCREATE TABLE tags (id INTEGER PRIMARY KEY);\n-- down\n-- nothing yet\n");
+
+        assert_eq!(runner.push(&db).unwrap(), vec!["001_users.sql", "002_posts.sql", "003_tags.sql"]);
+        assert_eq!(db.get_tables().unwrap().len(), 3);
+
+        // The last migration can't be undone, so nothing is
+        assert!(matches!(runner.rollback(&db, 2), Err(MigrationError::MissingDown(name)) if name == "003_tags.sql"));
+        assert_eq!(db.get_applied_migrations().unwrap().len(), 3);
+
+        // Adding the down script edits an applied migration, which has to be
+        // accepted before it is trusted to undo it
+        write(&runner, "003_tags.sql", "-- up\nCREATE TABLE tags (id INTEGER PRIMARY KEY);\n-- down\nDROP TABLE tags;\n");
+        assert!(matches!(runner.rollback(&db, 2), Err(MigrationError::SchemaDrift(m)) if m.contains("003_tags.sql")));
+        assert_eq!(db.get_tables().unwrap().len(), 3);
+        runner.repair(&db).unwrap();
+        assert_eq!(runner.rollback(&db, 2).unwrap(), vec!["003_tags.sql", "002_posts.sql"]);
+        assert_eq!(db.get_tables().unwrap(), vec!["users"]);
+        assert_eq!(db.get_applied_migrations().unwrap(), vec!["001_users.sql"]);
+
+        // Rolled-back migrations are pending again
+        assert_eq!(runner.check(&db).unwrap().pending_count, 2);
+    }
+
+    #[test]
+    fn test_checksum_drift() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        write(&runner, "001_users.sql", "CREATE TABLE users (id INTEGER PRIMARY KEY);\n");
+        write(&runner, "002_posts.sql", "CREATE TABLE posts (id INTEGER PRIMARY KEY);\n");
+        runner.push(&db).unwrap();
+        assert!(runner.verify(&db).unwrap().is_empty());
+
+        write(&runner, "001_users.sql", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);\n");
+        fs::remove_file(runner.migrations_dir.join("002_posts.sql")).unwrap();
+        write(&runner, "003_tags.sql", "CREATE TABLE tags (id INTEGER PRIMARY KEY);\n");
+        let drift = runner.check(&db).unwrap().drift;
+        assert_eq!(drift.iter().map(|d| d.kind).collect::<Vec<_>>(), vec![DriftKind::Edited, DriftKind::Deleted]);
+        assert!(matches!(runner.push(&db), Err(MigrationError::SchemaDrift(_))));
+        assert_eq!(db.get_applied_migrations().unwrap().len(), 2);
+
+        // Repair accepts the edit; the deleted file still blocks pushes
+        assert_eq!(runner.repair(&db).unwrap(), vec!["001_users.sql"]);
+        assert_eq!(runner.verify(&db).unwrap(), vec![MigrationDrift { name: "002_posts.sql".into(), kind: DriftKind::Deleted }]);
+        write(&runner, "002_posts.sql", "CREATE TABLE posts (id INTEGER PRIMARY KEY);\n");
+        assert_eq!(runner.push(&db).unwrap(), vec!["003_tags.sql"]);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        write(&runner, "001_users.sql", "CREATE TABLE users (id INTEGER PRIMARY KEY);\nINSERT INTO users DEFAULT VALUES;\n");
+        runner.push(&db).unwrap();
+        write(&runner, "002_posts.sql", "CREATE TABLE posts (id INTEGER PRIMARY KEY);\nINSERT INTO posts DEFAULT VALUES;\n");
+        write(&runner, "003_name.sql", "ALTER TABLE users ADD COLUMN name TEXT;\nINSERT INTO nowhere DEFAULT VALUES;\n");
+
+        let report = runner.dry_run(&db).unwrap();
+        assert_eq!(report.migrations.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["002_posts.sql"]);
+        let failure = report.failure.unwrap();
+        assert_eq!(failure.migration, "003_name.sql");
+        assert_eq!(failure.statement.as_deref(), Some("INSERT INTO nowhere DEFAULT VALUES"));
+        assert!(failure.error.contains("no such table: nowhere"));
+        assert_eq!(report.schema_changes, vec!["create table posts"]);
+        assert_eq!(
+            report.row_counts,
+            vec![RowCountChange { table: "posts".into(), before: None, after: Some(1) }]
+        );
+
+        // The real database is untouched
+        assert_eq!(db.get_tables().unwrap(), vec!["users"]);
+        assert_eq!(runner.check(&db).unwrap().pending_count, 2);
+    }
+
+    #[test]
+    fn test_schema_snapshot_and_drift() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        write(
+            &runner,
+            "001_schema.sql",
+            r#"CREATE TABLE "users" (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL UNIQUE);
+            CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users (id) ON DELETE CASCADE);
+            CREATE INDEX idx_posts_user ON posts (user_id);
+            CREATE VIEW user_posts AS SELECT u.email, p.id FROM users u JOIN posts p ON p.user_id = u.id;
+            INSERT INTO users (email) VALUES ('a@example.com');"#,
+        );
+        runner.push(&db).unwrap();
+        let path = runner.generate_schema_snapshot(&db, dir.path()).unwrap();
+        let snapshot = fs::read_to_string(&path).unwrap();
+        assert!(snapshot.contains(r#"CREATE TABLE "users" (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL UNIQUE);"#));
+        assert!(snapshot.contains("REFERENCES users (id) ON DELETE CASCADE"));
+        assert!(snapshot.contains("CREATE INDEX idx_posts_user ON posts (user_id);"));
+        assert!(!snapshot.contains("sqlite_sequence"));
+        assert!(runner.check(&db).unwrap().schema_drift.is_empty());
+
+        // The snapshot recreates the same schema, and snapshots it the same way
+        let copy_dir = tempdir().unwrap();
+        let copy = Database::new(&copy_dir.path().join("copy.db")).unwrap();
+        copy.get_connection().unwrap().execute_batch(&snapshot).unwrap();
+        let copy_path = MigrationRunner::new(copy_dir.path()).generate_schema_snapshot(&copy, copy_dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(copy_path).unwrap(), snapshot);
+
+        // Changes made outside migrations are drift
+        db.get_connection()
+            .unwrap()
+            .execute_batch("ALTER TABLE posts ADD COLUMN title TEXT; DROP INDEX idx_posts_user; CREATE TABLE notes (id INTEGER);")
+            .unwrap();
+        let drift: Vec<String> = runner
+            .schema_drift(&db)
+            .unwrap()
+            .iter()
+            .map(|d| format!("{} {} {}", d.kind, d.name, d.change))
+            .collect();
+        assert_eq!(drift, vec!["table notes added", "table posts changed", "index idx_posts_user removed"]);
+    }
+
+    #[test]
+    fn test_generate_migrations() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        write(&runner, "001_users.sql", "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT);\n");
+        runner.push(&db).unwrap();
+        runner.generate_schema_snapshot(&db, dir.path()).unwrap();
+
+        // Changes made to the database directly are captured and recorded as applied
+        db.get_connection()
+            .unwrap()
+            .execute_batch("ALTER TABLE users ADD COLUMN name TEXT; CREATE VIEW emails AS SELECT email FROM users;")
+            .unwrap();
+        let migration = runner.generate_from_database(&db, "add name").unwrap().unwrap();
+        assert!(migration.name.ends_with("_add_name.sql"));
+        assert!(migration.up.contains("ADD COLUMN"));
+        assert!(migration.up.contains("CREATE VIEW emails AS SELECT email FROM users;"));
+        assert!(migration.down.as_deref().unwrap().contains("DROP VIEW \"emails\";"));
+        assert_eq!(db.get_applied_migrations().unwrap().len(), 2);
+        runner.generate_schema_snapshot(&db, dir.path()).unwrap();
+        assert!(runner.check(&db).unwrap().schema_drift.is_empty());
+        assert!(runner.generate_from_database(&db, "again").unwrap().is_none());
+
+        // A declarative schema that ALTER TABLE can't reach rebuilds the table
+        let schema_file = dir.path().join("schema.sql");
+        fs::write(
+            &schema_file,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL, name TEXT);\n\
+             CREATE VIEW emails AS SELECT email FROM users;\n",
+        )
+        .unwrap();
+        let migration = runner.generate_from_schema(&db, "require email", &schema_file).unwrap().unwrap();
+        assert!(migration.up.contains("_airdb_rebuild_users"));
+        assert!(matches!(runner.generate_from_database(&db, "blocked"), Err(MigrationError::Pending(_))));
+
+        db.get_connection().unwrap().execute("INSERT INTO users (email) VALUES ('a@example.com')", []).unwrap();
+        assert_eq!(runner.push(&db).unwrap(), vec![migration.name.clone()]);
+        assert!(runner.generate_from_schema(&db, "again", &schema_file).unwrap().is_none());
+        assert_eq!(runner.rollback(&db, 1).unwrap(), vec![migration.name]);
+        assert!(db.get_table_schema("users").unwrap().iter().all(|c| !c.notnull));
+    }
+
+    #[test]
+    fn test_failed_migration_restores_foreign_keys() {
+        let dir = tempdir().unwrap();
+        // One connection, so the check below sees the one the migration used
+        let tuning = ConnectionTuning { pool_size: 1, ..ConnectionTuning::default() };
+        let db = Database::with_tuning(&dir.path().join("test.db"), &tuning).unwrap();
+        let runner = MigrationRunner::new(dir.path());
+
+        // Ending the transaction itself makes the runner's COMMIT fail
+        write(&runner, "001_rebuild.sql", "PRAGMA foreign_keys=OFF;\nCREATE TABLE t (id INTEGER);\nCOMMIT;\n");
+        assert!(runner.push(&db).is_err());
+
+        let conn = db.get_connection().unwrap();
+        let enforced: i64 = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert_eq!(enforced, 1);
+        assert!(conn.is_autocommit());
+    }
 }
@@ -26,6 +26,8 @@ pub use database::Database;
 pub use adapter::{DatabaseAdapter, SqlValue, QueryResult, ExecResult, DataPage, AdapterError};
 pub use adapter::dialect::{SqlDialect, DialectGenerator};
 pub use adapter::sqlite::SqliteAdapter;
+pub use adapter::postgres::PostgresAdapter;
+pub use adapter::mysql::MysqlAdapter;
 pub use connections::{ConnectionManager, ConnectionConfig, AdapterConfig};
 pub use nosql::NoSqlEngine;
 pub use hybrid::{Relation, RelationType, AirQuery, AirResult};
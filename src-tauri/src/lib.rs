@@ -6,11 +6,12 @@ pub mod commands;
 
 use engine::config::Config;
 use engine::database::Database;
-use engine::adapter::DatabaseAdapter;
+use engine::adapter::{CancelHandle, DatabaseAdapter, RowCursor, SqlGuard};
 use engine::adapter::sqlite::SqliteAdapter;
 use engine::migrations::MigrationRunner;
 use engine::keystore::Keystore;
 use engine::api::{ApiState, create_router};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tauri::State;
@@ -19,6 +20,14 @@ pub struct AppState {
     pub project_dir: Mutex<Option<PathBuf>>,
     pub db: Mutex<Option<Database>>,
     pub adapter: Mutex<Option<Box<dyn DatabaseAdapter>>>,
+    /// Saved connection the adapter is open on; None while it is the
+    /// project database
+    pub active_connection: Mutex<Option<String>>,
+    /// Raw SQL limits for the active connection
+    pub sql_guard: Mutex<SqlGuard>,
+    /// Cancel handles of raw SQL runs in flight, by the UI's query id
+    pub running_queries: Mutex<HashMap<String, CancelHandle>>,
+    pub cursors: Mutex<HashMap<String, RowCursor>>,
     pub api_server_handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
     pub api_server_port: Mutex<Option<u16>>,
 }
@@ -29,6 +38,10 @@ impl Default for AppState {
             project_dir: Mutex::new(None),
             db: Mutex::new(None),
             adapter: Mutex::new(None),
+            active_connection: Mutex::new(None),
+            sql_guard: Mutex::new(SqlGuard::default()),
+            running_queries: Mutex::new(HashMap::new()),
+            cursors: Mutex::new(HashMap::new()),
             api_server_handle: Mutex::new(None),
             api_server_port: Mutex::new(None),
         }
@@ -81,14 +94,17 @@ fn open_project(path: String, state: State<AppState>) -> Result<serde_json::Valu
     let config = Config::load(&project_dir).map_err(|e| e.to_string())?;
     
     let db_path = project_dir.join(&config.database.path);
-    let db = Database::new(&db_path).map_err(|e| e.to_string())?;
+    let tuning = config.database.connection_tuning(&project_dir);
+    let db = Database::with_tuning(&db_path, &tuning).map_err(|e| e.to_string())?;
     
     // Create the adapter alongside the legacy Database handle
-    let adapter = SqliteAdapter::new(&db_path).map_err(|e| e.to_string())?;
+    let adapter = SqliteAdapter::with_tuning(&db_path, &tuning).map_err(|e| e.to_string())?;
     
     *state.project_dir.lock().unwrap() = Some(project_dir);
     *state.db.lock().unwrap() = Some(db);
     *state.adapter.lock().unwrap() = Some(Box::new(adapter));
+    *state.active_connection.lock().unwrap() = None;
+    *state.sql_guard.lock().unwrap() = SqlGuard::default();
     
     Ok(serde_json::json!({
         "success": true,
@@ -120,6 +136,18 @@ fn run_migrations(state: State<AppState>) -> Result<Vec<String>, String> {
     runner.push(db).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn rollback_migrations(count: usize, state: State<AppState>) -> Result<Vec<String>, String> {
+    let project_dir = state.project_dir.lock().unwrap();
+    let project_dir = project_dir.as_ref().ok_or("No project open")?;
+    
+    let db = state.db.lock().unwrap();
+    let db = db.as_ref().ok_or("Database not initialized")?;
+    
+    let runner = MigrationRunner::new(project_dir);
+    runner.rollback(db, count).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn get_migration_status(state: State<AppState>) -> Result<serde_json::Value, String> {
     let project_dir = state.project_dir.lock().unwrap();
@@ -135,6 +163,15 @@ fn get_migration_status(state: State<AppState>) -> Result<serde_json::Value, Str
         "applied_count": status.applied_count,
         "pending_count": status.pending_count,
         "pending": status.pending_migrations,
+        "drift": status.drift.iter().map(|d| serde_json::json!({
+            "name": d.name,
+            "kind": d.kind.to_string(),
+        })).collect::<Vec<_>>(),
+        "schema_drift": status.schema_drift.iter().map(|d| serde_json::json!({
+            "kind": d.kind,
+            "name": d.name,
+            "change": d.change.to_string(),
+        })).collect::<Vec<_>>(),
     }))
 }
 
@@ -551,6 +588,7 @@ pub fn run() {
             open_project,
             create_migration,
             run_migrations,
+            rollback_migrations,
             get_migration_status,
             list_all_migrations,
             generate_snapshot,
@@ -591,7 +629,9 @@ pub fn run() {
             commands::get_table_indexes,
             commands::generate_table_migration,
             commands::apply_generated_migration,
+            commands::dry_run_generated_migration,
             commands::execute_raw_sql,
+            commands::cancel_query,
             commands::get_project_type,
             commands::set_project_type,
             // Lock commands
@@ -604,10 +644,23 @@ pub fn run() {
             commands::disable_autostart,
             // Data browser commands
             commands::query_table_data,
+            commands::explain_query,
+            commands::explain_table_query,
+            commands::search_table,
+            commands::get_search_index,
+            commands::create_search_index,
+            commands::drop_search_index,
             commands::adapter_insert_row,
+            commands::adapter_insert_rows,
             commands::adapter_update_row,
             commands::adapter_delete_row,
+            commands::adapter_execute_transaction,
+            commands::open_query_cursor,
+            commands::fetch_query_cursor,
+            commands::close_query_cursor,
             commands::adapter_get_table_schema,
+            commands::adapter_get_views,
+            commands::export_schema,
             commands::get_dialect,
             commands::get_dialect_types,
             commands::get_database_size,
@@ -616,10 +669,13 @@ pub fn run() {
             // Connection commands
             commands::list_connections,
             commands::add_connection,
+            commands::add_connection_from_url,
             commands::update_connection,
             commands::remove_connection,
             commands::test_connection,
             commands::connect_to_database,
+            commands::transfer_data,
+            commands::get_transfer_status,
             // Audit & Health commands
             commands::get_audit_log,
             commands::get_audit_count,
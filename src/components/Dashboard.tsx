@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { MigrationStatus } from '../types';
 import {
   Database,
   Table2,
@@ -39,7 +40,7 @@ interface DashboardData {
   tableStats: TableStat[];
   dbSize: string;
   totalRows: number;
-  migrationStatus: { applied_count: number; pending_count: number; pending: string[] };
+  migrationStatus: MigrationStatus;
   recentActivity: AuditEntry[];
 }
 
@@ -59,7 +60,7 @@ export default function Dashboard({ projectName, dbType, apiPort, onNavigate }:
       setLoading(true);
       const [tablesResult, migStatus, sizeResult, auditResult] = await Promise.allSettled([
         invoke<string[]>('get_tables'),
-        invoke<{ applied_count: number; pending_count: number; pending: string[] }>('get_migration_status'),
+        invoke<MigrationStatus>('get_migration_status'),
         invoke<string>('get_database_size'),
         invoke<{ total: number; entries: AuditEntry[] }>('get_audit_log', { limit: 10 }),
       ]);
@@ -100,6 +101,7 @@ export default function Dashboard({ projectName, dbType, apiPort, onNavigate }:
   }
 
   const hasPending = (data?.migrationStatus.pending_count ?? 0) > 0;
+  const schemaDrift = data?.migrationStatus.schema_drift ?? [];
 
   function formatTimeAgo(ts: string): string {
     const diff = Date.now() - new Date(ts).getTime();
@@ -268,6 +270,14 @@ export default function Dashboard({ projectName, dbType, apiPort, onNavigate }:
             <CheckCircle2 size={14} />
             <span>{data?.migrationStatus.applied_count ?? 0} migration(s) tracked</span>
           </div>
+          {schemaDrift.length > 0 && (
+            <div className="health-item warning">
+              <AlertTriangle size={14} />
+              <span title={schemaDrift.map(d => `${d.kind} ${d.name} (${d.change})`).join('\n')}>
+                {schemaDrift.length} object(s) differ from sql/schema.sql — changed outside migrations?
+              </span>
+            </div>
+          )}
           {(data?.tables.length ?? 0) > 0 ? (
             <div className="health-item ok">
               <CheckCircle2 size={14} />
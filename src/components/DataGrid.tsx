@@ -1,7 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { ConfirmDialog } from './ConfirmDialog';
-import type { DataPage, SortParam, FilterParam } from '../types';
+import type { DataPage, SortParam, FilterCondition, RowKeyValue } from '../types';
 import './DataGrid.css';
 import './Skeleton.css';
 
@@ -16,7 +16,7 @@ export function DataGrid({ table, onToast }: DataGridProps) {
   const [page, setPage] = useState(0);
   const [pageSize, setPageSize] = useState(100);
   const [sort, setSort] = useState<SortParam | null>(null);
-  const [filters, setFilters] = useState<FilterParam[]>([]);
+  const [filters, setFilters] = useState<FilterCondition[]>([]);
   const [selectedRows, setSelectedRows] = useState<Set<number>>(new Set());
   const [editingCell, setEditingCell] = useState<{ row: number; col: string } | null>(null);
   const [editValue, setEditValue] = useState('');
@@ -27,7 +27,7 @@ export function DataGrid({ table, onToast }: DataGridProps) {
   const [inspectorRow, setInspectorRow] = useState<Record<string, any> | null>(null);
   const [showInsertDialog, setShowInsertDialog] = useState(false);
   const [insertData, setInsertData] = useState<Record<string, string>>({});
-  const [confirmDelete, setConfirmDelete] = useState<{ ids: number[] } | null>(null);
+  const [confirmDelete, setConfirmDelete] = useState<{ ids: RowKeyValue[] } | null>(null);
   const editInputRef = useRef<HTMLInputElement>(null);
 
   const fetchData = useCallback(async () => {
@@ -83,21 +83,26 @@ export function DataGrid({ table, onToast }: DataGridProps) {
     setEditValue(value === null ? '' : String(value));
   };
 
+  const rowKeyOf = (row: Record<string, any>): RowKeyValue | null => {
+    const keyColumns = data?.rowKey ?? [];
+    if (keyColumns.length === 0) return null;
+    if (keyColumns.length === 1) return row[keyColumns[0]];
+    return Object.fromEntries(keyColumns.map((c) => [c, row[c]]));
+  };
+
   const handleCellEditSave = async () => {
     if (!editingCell || !data) return;
-    const row = data.rows[editingCell.row];
-    const pkCol = data.columns.find((c) => c.name === 'id' || c.name === 'ID');
-    if (!pkCol) {
-      onToast('error', 'Cannot edit: no id column found');
+    const id = rowKeyOf(data.rows[editingCell.row]);
+    if (id === null) {
+      onToast('error', 'Cannot edit: table has no primary key');
       setEditingCell(null);
       return;
     }
 
-    const id = row[pkCol.name];
     try {
       await invoke('adapter_update_row', {
         table,
-        id: Number(id),
+        id,
         data: { [editingCell.col]: editValue || null },
       });
       onToast('success', 'Cell updated');
@@ -152,12 +157,13 @@ export function DataGrid({ table, onToast }: DataGridProps) {
 
   const handleDeleteSelected = () => {
     if (!data) return;
-    const pkCol = data.columns.find((c) => c.name === 'id' || c.name === 'ID');
-    if (!pkCol) {
-      onToast('error', 'Cannot delete: no id column found');
+    if (data.rowKey.length === 0) {
+      onToast('error', 'Cannot delete: table has no primary key');
       return;
     }
-    const ids = Array.from(selectedRows).map((i) => Number(data.rows[i]?.[pkCol.name]));
+    const ids = Array.from(selectedRows)
+      .filter((i) => data.rows[i])
+      .map((i) => rowKeyOf(data.rows[i]) as RowKeyValue);
     setConfirmDelete({ ids });
   };
 
@@ -228,7 +234,7 @@ export function DataGrid({ table, onToast }: DataGridProps) {
     URL.revokeObjectURL(url);
   };
 
-  const totalPages = data ? Math.ceil(data.totalCount / pageSize) : 0;
+  const totalPages = data ? Math.ceil((data.totalCount ?? 0) / pageSize) : 0;
 
   const sortIcon = (col: string) => {
     if (sort?.column !== col) return '⇅';
@@ -240,7 +246,7 @@ export function DataGrid({ table, onToast }: DataGridProps) {
       {/* Toolbar */}
       <div className="data-grid-toolbar">
         <span className="data-grid-info">
-          {data ? `${data.totalCount.toLocaleString()} rows` : 'Loading...'}
+          {data ? `${data.countIsEstimate ? '~' : ''}${(data.totalCount ?? 0).toLocaleString()} rows` : 'Loading...'}
           {data?.executionTimeMs !== undefined && ` · ${data.executionTimeMs}ms`}
         </span>
         <div className="data-grid-actions">
@@ -383,7 +389,7 @@ export function DataGrid({ table, onToast }: DataGridProps) {
       {/* Pagination */}
       <div className="data-grid-pagination">
         <div className="pagination-info">
-          Showing {data ? page * pageSize + 1 : 0}–{data ? Math.min((page + 1) * pageSize, data.totalCount) : 0} of {data?.totalCount.toLocaleString() || 0}
+          Showing {data ? page * pageSize + 1 : 0}–{data ? Math.min((page + 1) * pageSize, data.totalCount ?? 0) : 0} of {data?.totalCount?.toLocaleString() || 0}
         </div>
         <div className="pagination-controls">
           <button
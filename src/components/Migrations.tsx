@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { Plus, Play, Camera, RefreshCw, ChevronDown, ChevronRight, Check, Clock, FileText } from 'lucide-react';
+import { Plus, Play, Camera, RefreshCw, ChevronDown, ChevronRight, Check, Clock, FileText, Undo2 } from 'lucide-react';
 import './Migrations.css';
 
 interface MigrationItem {
@@ -72,6 +72,20 @@ export default function Migrations() {
     }
   }
 
+  async function handleRollback(name: string) {
+    if (!window.confirm(`Roll back ${name}? Its down section will run and it will be marked pending again.`)) return;
+    try {
+      setRunning(true);
+      const rolledBack = await invoke<string[]>('rollback_migrations', { count: 1 });
+      showToast('success', `Rolled back ${rolledBack.length} migration(s)`);
+      await loadMigrations();
+    } catch (e) {
+      showToast('error', String(e));
+    } finally {
+      setRunning(false);
+    }
+  }
+
   async function handleCreate() {
     const name = newName.trim();
     if (!name) return;
@@ -154,6 +168,13 @@ export default function Migrations() {
             <button className="btn btn-ghost btn-sm" onClick={() => setCreating(true)}>
               <Plus size={14} /> New Migration
             </button>
+            <button
+              className="btn btn-ghost btn-sm"
+              onClick={() => handleRollback(applied[applied.length - 1].name)}
+              disabled={running || applied.length === 0}
+            >
+              <Undo2 size={14} /> Roll Back Last
+            </button>
             <button
               className="btn btn-primary btn-sm"
               onClick={handleRunPending}
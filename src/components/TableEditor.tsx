@@ -12,12 +12,17 @@ import { invoke } from '@tauri-apps/api/core';
     GripVertical,
     ChevronDown,
     Grid3X3,
+    Square,
+    TestTube,
 } from 'lucide-react';
-import { Column, TableSchema, MigrationPreview } from '../types';
+import { Column, TableSchema, MigrationPreview, DryRunReport, SqlRunResult, SqlStatement } from '../types';
 import { DataGrid } from './DataGrid';
 import { SqlEditor } from './SqlEditor';
 import './TableEditor.css';
 
+/** Raw SQL that runs longer than this is aborted by the backend */
+const RAW_SQL_TIMEOUT_MS = 5 * 60 * 1000;
+
 interface TableEditorProps { }
 
 export function TableEditor({ }: TableEditorProps) {
@@ -34,10 +39,12 @@ export function TableEditor({ }: TableEditorProps) {
 
     // Migration / Preview
     const [preview, setPreview] = useState<MigrationPreview | null>(null);
+    const [dryRun, setDryRun] = useState<DryRunReport | null>(null);
     const [rawSql, setRawSql] = useState('');
     const [rawOutput, setRawOutput] = useState<string>('');
     const [rawResults, setRawResults] = useState<{ columns: string[]; rows: Record<string, any>[] } | null>(null);
     const [loading, setLoading] = useState(false);
+    const [runningQueryId, setRunningQueryId] = useState<string | null>(null);
     const [error, setError] = useState<string | null>(null);
     const [toastMessages, setToastMessages] = useState<{ id: number; type: 'success' | 'error' | 'info'; message: string }[]>([]);
 
@@ -121,6 +128,7 @@ export function TableEditor({ }: TableEditorProps) {
                 is_unique: c.is_unique,
                 default_value: c.default_value,
                 foreign_key: c.foreign_key,
+                generated: c.generated,
             })));
             setTableName(schema.name);
             setIsNewTable(false);
@@ -198,6 +206,7 @@ export function TableEditor({ }: TableEditorProps) {
                 isNew: isNewTable
             });
             setPreview(result);
+            setDryRun(null);
             setError(null);
         } catch (e) {
             // console.error(e);
@@ -205,6 +214,23 @@ export function TableEditor({ }: TableEditorProps) {
         }
     }
 
+    async function dryRunChanges() {
+        if (!preview) return;
+        setLoading(true);
+        try {
+            const report = await invoke<DryRunReport>('dry_run_generated_migration', {
+                name: preview.name,
+                upSql: preview.upSql,
+            });
+            setDryRun(report);
+            setError(null);
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setLoading(false);
+        }
+    }
+
     async function applyChanges() {
         if (!preview) return;
         setLoading(true);
@@ -220,6 +246,7 @@ export function TableEditor({ }: TableEditorProps) {
                 setIsNewTable(false);
             }
             setPreview(null);
+            setDryRun(null);
             setError(null);
         } catch (e) {
             setError(String(e));
@@ -230,10 +257,28 @@ export function TableEditor({ }: TableEditorProps) {
 
     async function executeRawSql() {
         if (!rawSql.trim()) return;
+        const queryId = crypto.randomUUID();
         setLoading(true);
+        setRunningQueryId(queryId);
         setRawResults(null);
         try {
-            const result = await invoke<any>('execute_raw_sql', { sql: rawSql });
+            let result = await invoke<any>('execute_raw_sql', { sql: rawSql, queryId, timeoutMs: RAW_SQL_TIMEOUT_MS });
+            if (result.requiresConfirmation) {
+                const statements = result.statements as SqlStatement[];
+                const risky = statements.filter(s => s.kind === 'destructive');
+                const preview = result.dryRun as SqlRunResult | null;
+                const impact = preview
+                    ? `\n\n${preview.rowsAtRisk} existing row(s) at risk.`
+                    : '';
+                const ok = window.confirm(
+                    `This SQL is destructive:\n${risky.map(s => `• ${s.reason}`).join('\n')}${impact}\n\nRun it anyway?`
+                );
+                if (!ok) {
+                    setRawOutput('Cancelled');
+                    return;
+                }
+                result = await invoke<any>('execute_raw_sql', { sql: rawSql, confirm: true, queryId, timeoutMs: RAW_SQL_TIMEOUT_MS });
+            }
             if (result.rows) {
                 const rows = result.rows as Record<string, any>[];
                 const columns = rows.length > 0 ? Object.keys(rows[0]) : [];
@@ -249,6 +294,13 @@ export function TableEditor({ }: TableEditorProps) {
             setRawOutput(`Error: ${e}`);
         } finally {
             setLoading(false);
+            setRunningQueryId(null);
+        }
+    }
+
+    async function cancelRawSql() {
+        if (runningQueryId) {
+            await invoke('cancel_query', { queryId: runningQueryId });
         }
     }
 
@@ -330,6 +382,11 @@ export function TableEditor({ }: TableEditorProps) {
                         >
                             <Play size={14} /> Run Query
                         </button>
+                        {runningQueryId && (
+                            <button className="btn btn-ghost" onClick={cancelRawSql}>
+                                <Square size={14} /> Cancel
+                            </button>
+                        )}
                     </div>
                     <div style={{ flex: 1, display: 'flex', flexDirection: 'column' }}>
                         <SqlEditor
@@ -505,6 +562,29 @@ export function TableEditor({ }: TableEditorProps) {
                                 />
                             </div>
 
+                            {dryRun && (
+                                <div className="te-dry-run">
+                                    {dryRun.migrations.map(m => (
+                                        <div key={m.name}>✓ Applied to a copy in {m.durationMs} ms</div>
+                                    ))}
+                                    {dryRun.failure && (
+                                        <div className="te-dry-run-failure">
+                                            ✗ {dryRun.failure.error}
+                                            {dryRun.failure.statement && <pre>{dryRun.failure.statement}</pre>}
+                                        </div>
+                                    )}
+                                    {dryRun.schemaChanges.map(change => (
+                                        <div key={change}>• {change}</div>
+                                    ))}
+                                    {dryRun.rowCounts.map(c => (
+                                        <div key={c.table}>• {c.table}: {c.before ?? '-'} → {c.after ?? '-'} rows</div>
+                                    ))}
+                                    {dryRun.destructive && (
+                                        <div className="te-dry-run-failure">Drops tables or columns, or changes column types</div>
+                                    )}
+                                </div>
+                            )}
+
                             {error && (
                                 <div style={{ padding: '12px', background: 'rgba(239, 68, 68, 0.1)', color: 'var(--error)', fontSize: '12px', borderTop: '1px solid var(--error)' }}>
                                     {error}
@@ -512,6 +592,14 @@ export function TableEditor({ }: TableEditorProps) {
                             )}
 
                             <div className="te-preview-actions">
+                                <button
+                                    className="btn btn-ghost"
+                                    onClick={dryRunChanges}
+                                    disabled={!preview || loading}
+                                    title="Try the changes on a copy of the database"
+                                >
+                                    <TestTube size={14} /> Dry Run
+                                </button>
                                 <button
                                     className="btn btn-primary"
                                     onClick={applyChanges}
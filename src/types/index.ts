@@ -11,6 +11,20 @@ export interface MigrationStatus {
     pending_count: number;
     pending: string[];
     applied?: string[];
+    drift?: MigrationDrift[];
+    schema_drift?: SnapshotDrift[];
+}
+
+export interface MigrationDrift {
+    name: string;
+    kind: 'edited' | 'deleted';
+}
+
+/** A schema object where the database differs from sql/schema.sql */
+export interface SnapshotDrift {
+    kind: 'table' | 'index' | 'view' | 'trigger';
+    name: string;
+    change: 'added' | 'removed' | 'changed';
 }
 
 export interface ApiKey {
@@ -63,6 +77,35 @@ export interface Column {
     is_unique: boolean;
     default_value?: string;
     foreign_key?: { table: string; column: string } | null;
+    /** Set for generated (computed) columns */
+    generated?: GeneratedColumn | null;
+}
+
+export interface GeneratedColumn {
+    expression: string;
+    /** Stored on write rather than computed on read */
+    stored: boolean;
+}
+
+export interface CheckConstraint {
+    name: string | null;
+    expression: string;
+}
+
+export interface TriggerInfo {
+    name: string;
+    table: string;
+    timing: 'BEFORE' | 'AFTER' | 'INSTEAD OF';
+    events: string[];
+    /** Full CREATE TRIGGER statement */
+    definition: string;
+}
+
+export interface ViewInfo {
+    name: string;
+    /** The view's SELECT statement */
+    definition: string;
+    columns: string[];
 }
 
 export interface Index {
@@ -75,6 +118,8 @@ export interface TableSchema {
     name: string;
     columns: Column[];
     indexes: Index[];
+    check_constraints: CheckConstraint[];
+    triggers: TriggerInfo[];
 }
 
 export interface Table {
@@ -89,6 +134,15 @@ export interface MigrationPreview {
     name: string;
 }
 
+/** What migrations would do, found by applying them to a scratch copy */
+export interface DryRunReport {
+    migrations: { name: string; durationMs: number }[];
+    failure: { migration: string; statement: string | null; error: string } | null;
+    schemaChanges: string[];
+    destructive: boolean;
+    rowCounts: { table: string; before: number | null; after: number | null }[];
+}
+
 /* ─── NoSQL Types ─── */
 
 export interface Collection {
@@ -113,20 +167,73 @@ export interface ColumnMeta {
 
 export interface DataPage {
     rows: Record<string, any>[];
-    totalCount: number;
+    /** Null when counting was skipped */
+    totalCount: number | null;
+    /** Whether totalCount is an estimate rather than an exact count */
+    countIsEstimate: boolean;
     columns: ColumnMeta[];
     executionTimeMs: number;
+    /** Columns that address a row: the primary key, or `rowid` for keyless SQLite tables */
+    rowKey: string[];
+    /** Keyset cursors to the neighbouring pages, when paging by cursor */
+    nextCursor: string | null;
+    prevCursor: string | null;
 }
 
+export type CountMode = 'exact' | 'estimate' | 'skip';
+
+/** Opts query_table_data into keyset (cursor) pagination */
+export interface KeysetParam {
+    /** nextCursor/prevCursor from a previous page; omit for the first page */
+    cursor?: string;
+    count?: CountMode;
+}
+
+/** A bare key value for single-column keys, or column → value for composite keys */
+export type RowKeyValue = string | number | Record<string, any>;
+
 export interface SortParam {
     column: string;
     direction: 'asc' | 'desc';
 }
 
-export interface FilterParam {
+export interface FilterCondition {
     column: string;
     operator: string;
-    value: string;
+    value?: string | number | boolean | null;
+    /** Operands for `in` / `not_in`, or the two bounds for `between` */
+    values?: (string | number | boolean | null)[];
+}
+
+/** A condition, or a nested and/or/not group of filters */
+export type FilterParam =
+    | FilterCondition
+    | { and: FilterParam[] }
+    | { or: FilterParam[] }
+    | { not: FilterParam };
+
+/** Full-text index over some text columns of a SQLite table */
+export interface SearchIndex {
+    table: string;
+    columns: string[];
+    /** FTS5 tokenizer spec, e.g. `porter unicode61` */
+    tokenize: string | null;
+}
+
+export interface SearchHit {
+    row: Record<string, any>;
+    /** bm25 relevance; lower is a better match */
+    rank: number;
+    /** Excerpt of the best-matching column with matches wrapped in <mark> */
+    snippet: string;
+}
+
+export interface SearchResult {
+    hits: SearchHit[];
+    totalCount: number;
+    columns: ColumnMeta[];
+    rowKey: string[];
+    executionTimeMs: number;
 }
 
 /* ─── Connection Types ─── */
@@ -139,6 +246,8 @@ export interface ConnectionConfig {
     color?: string;
     isDefault: boolean;
     createdAt?: string;
+    guard?: SqlGuard;
+    tuning?: ConnectionTuning;
 }
 
 export interface SqliteConfig {
@@ -166,10 +275,103 @@ export interface MysqlConfig {
     ssl: boolean;
 }
 
+/* ─── Schema Export Types ─── */
+
+export interface ExportWarning {
+    table: string;
+    column: string | null;
+    message: string;
+}
+
+export interface SchemaExport {
+    source: ConnectionConfig['dialect'];
+    target: ConnectionConfig['dialect'];
+    sql: string;
+    warnings: ExportWarning[];
+}
+
+/* ─── Query Plan Types ─── */
+
+export interface PlanNode {
+    operation: string;
+    detail: string;
+    table: string | null;
+    index: string | null;
+    fullScan: boolean;
+    estimatedRows: number | null;
+    estimatedCost: number | null;
+    children: PlanNode[];
+}
+
+export interface QueryPlan {
+    dialect: ConnectionConfig['dialect'];
+    nodes: PlanNode[];
+    totalCost: number | null;
+    fullScans: string[];
+}
+
+/* ─── Raw SQL Guard Types ─── */
+
+export interface SqlGuard {
+    readOnly: boolean;
+    confirmDestructive: boolean;
+}
+
+export interface ConnectionTuning {
+    poolSize: number;
+    busyTimeoutMs: number;
+    synchronous?: 'off' | 'normal' | 'full' | 'extra' | null;
+    cacheSize?: number | null;
+    mmapSize?: number | null;
+    pragmas?: Record<string, string>;
+}
+
+export type StatementKind = 'read' | 'write' | 'ddl' | 'destructive';
+
+export interface SqlStatement {
+    sql: string;
+    kind: StatementKind;
+    reason: string | null;
+    table: string | null;
+}
+
+export interface SqlRunResult {
+    affectedRows: number;
+    rowsAtRisk: number;
+    dryRun: boolean;
+}
+
+/* ─── Data Transfer Types ─── */
+
+export interface TransferOptions {
+    tables?: string[];
+    batchSize?: number;
+    createSchema?: boolean;
+    restart?: boolean;
+}
+
+export interface TableProgress {
+    table: string;
+    totalRows: number;
+    rowsCopied: number;
+    lastKey: unknown[] | null;
+    done: boolean;
+}
+
+export interface TransferState {
+    source: ConnectionConfig['dialect'];
+    target: ConnectionConfig['dialect'];
+    tables: TableProgress[];
+    schemaCreated: boolean;
+    createdTables: string[];
+    warnings: ExportWarning[];
+}
+
 /* ─── ER Diagram Types ─── */
 
 export interface SchemaGraph {
     tables: SchemaTable[];
+    views: ViewInfo[];
     edges: SchemaEdge[];
 }
 
@@ -177,6 +379,8 @@ export interface SchemaTable {
     name: string;
     columns: SchemaColumn[];
     rowCount: number;
+    checkConstraints: CheckConstraint[];
+    triggers: TriggerInfo[];
 }
 
 export interface SchemaColumn {
@@ -187,6 +391,7 @@ export interface SchemaColumn {
     isNullable: boolean;
     isUnique: boolean;
     defaultValue?: string;
+    generated?: GeneratedColumn | null;
     fkTable?: string;
     fkColumn?: string;
 }